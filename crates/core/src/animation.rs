@@ -0,0 +1,151 @@
+use crate::icon::{decode_error, icon_limits};
+use crate::{Error, Result};
+use gpui::{Image, ImageFormat};
+use image::codecs::gif::GifDecoder;
+use image::{AnimationDecoder, ImageDecoder};
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Shortest frame delay honored; many GIFs declare 0 and expect browsers' clamp.
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// One frame of an [`AnimatedIcon`].
+#[derive(Clone, Debug)]
+pub struct IconFrame {
+    pub image: Image,
+    /// How long the frame stays up before the next one.
+    pub delay: Duration,
+}
+
+/// A looping sequence of tray icon frames.
+///
+/// Cloning is cheap; clones share their frames.
+#[derive(Clone, Debug)]
+pub struct AnimatedIcon {
+    frames: Arc<Vec<IconFrame>>,
+}
+
+impl AnimatedIcon {
+    /// Creates an animation from pre-rendered frames.
+    pub fn new(frames: impl IntoIterator<Item = IconFrame>) -> Result<Self> {
+        let frames: Vec<_> = frames
+            .into_iter()
+            .map(|frame| IconFrame {
+                delay: frame.delay.max(MIN_FRAME_DELAY),
+                ..frame
+            })
+            .collect();
+        if frames.is_empty() {
+            return Err(Error::InvalidIcon);
+        }
+        Ok(Self {
+            frames: Arc::new(frames),
+        })
+    }
+
+    /// Creates an animation showing `images` in turn at `fps` frames per
+    /// second, e.g. the steps of a "syncing" spinner.
+    pub fn from_images(images: impl IntoIterator<Item = Image>, fps: f32) -> Result<Self> {
+        if !fps.is_finite() || fps <= 0.0 {
+            return Err(Error::InvalidIcon);
+        }
+        let delay = Duration::from_secs_f32(1.0 / fps);
+        Self::new(images.into_iter().map(|image| IconFrame { image, delay }))
+    }
+
+    /// Decodes every frame of an animated GIF along with its delay.
+    ///
+    /// Like any icon source, the GIF may be at most
+    /// [`max_icon_dimension`](crate::max_icon_dimension) wide and tall, or
+    /// this fails with [`Error::IconTooLarge`] before any frame is decoded.
+    pub fn from_gif(bytes: &[u8]) -> Result<Self> {
+        let mut decoder = GifDecoder::new(Cursor::new(bytes)).map_err(|_| Error::InvalidIcon)?;
+        decoder.set_limits(icon_limits()).map_err(decode_error)?;
+        let frames = decoder
+            .into_frames()
+            .collect_frames()
+            .map_err(decode_error)?;
+
+        let frames = frames
+            .into_iter()
+            .map(|frame| {
+                let delay = Duration::from(frame.delay());
+                let delay = if delay.is_zero() {
+                    DEFAULT_FRAME_DELAY
+                } else {
+                    delay
+                };
+
+                let mut png = Vec::new();
+                frame
+                    .into_buffer()
+                    .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+                    .map_err(|_| Error::InvalidIcon)?;
+                Ok(IconFrame {
+                    image: Image::from_bytes(ImageFormat::Png, png),
+                    delay,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Self::new(frames)
+    }
+
+    /// Returns the frames in display order.
+    pub fn frames(&self) -> &[IconFrame] {
+        &self.frames
+    }
+
+    /// Whether `other` shares the same frames, i.e. is a clone of this animation.
+    pub fn same_frames(&self, other: &AnimatedIcon) -> bool {
+        Arc::ptr_eq(&self.frames, &other.frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, RgbaImage};
+
+    fn gif(width: u32, height: u32, delays_ms: &[u32]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut bytes);
+            for (index, delay) in delays_ms.iter().enumerate() {
+                let pixels =
+                    RgbaImage::from_pixel(width, height, [index as u8 * 40, 0, 0, 255].into());
+                let delay = Delay::from_numer_denom_ms(*delay, 1);
+                encoder
+                    .encode_frame(Frame::from_parts(pixels, 0, 0, delay))
+                    .unwrap();
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn gif_frames_keep_their_delays() {
+        let animation = AnimatedIcon::from_gif(&gif(4, 4, &[50, 0, 200])).unwrap();
+        let delays: Vec<_> = animation.frames().iter().map(|frame| frame.delay).collect();
+        assert_eq!(
+            delays,
+            [
+                Duration::from_millis(50),
+                DEFAULT_FRAME_DELAY,
+                Duration::from_millis(200)
+            ]
+        );
+    }
+
+    #[test]
+    fn oversized_gifs_are_rejected() {
+        let width = crate::DEFAULT_MAX_ICON_DIMENSION + 1;
+        assert!(matches!(
+            AnimatedIcon::from_gif(&gif(width, 1, &[100])),
+            Err(Error::IconTooLarge { .. })
+        ));
+    }
+}
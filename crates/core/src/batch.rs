@@ -0,0 +1,140 @@
+use crate::{AnimatedIcon, Tray, TrayStatus};
+use gpui::{Image, SharedString};
+
+/// A set of tray changes applied together.
+///
+/// Nothing reaches the platform until the batch closure returns; the backend
+/// then receives the combined result as one update, so the icon never shows
+/// a new image next to an old tooltip or menu.
+pub struct TrayBatch<'a> {
+    tray: &'a mut Tray,
+}
+
+impl<'a> TrayBatch<'a> {
+    /// Collects changes to `tray`.
+    pub fn new(tray: &'a mut Tray) -> Self {
+        Self { tray }
+    }
+
+    /// Returns the tray with the changes made so far.
+    pub fn tray(&self) -> &Tray {
+        self.tray
+    }
+
+    /// Replaces the icon for every theme, stopping any animation.
+    pub fn set_icon(&mut self, icon: Image) -> &mut Self {
+        self.tray.icon = Some(icon);
+        self.tray.dark_icon = None;
+        self.tray.animation = None;
+        self
+    }
+
+    /// Replaces the icons for light and dark desktops, stopping any
+    /// animation.
+    pub fn set_icon_for_theme(&mut self, light: Image, dark: Image) -> &mut Self {
+        self.tray.icon = Some(light);
+        self.tray.dark_icon = Some(dark);
+        self.tray.animation = None;
+        self
+    }
+
+    /// Plays `animation` in place of the static icon.
+    pub fn set_animated_icon(&mut self, animation: AnimatedIcon) -> &mut Self {
+        self.tray.animation = Some(animation);
+        self
+    }
+
+    /// Sets the count drawn over the icon, or removes it with `None`.
+    pub fn set_badge(&mut self, badge: Option<u32>) -> &mut Self {
+        self.tray.badge = badge;
+        self
+    }
+
+    /// Sets the tooltip text, replacing a localized tooltip.
+    pub fn set_tooltip(&mut self, tooltip: impl Into<SharedString>) -> &mut Self {
+        self.tray.tooltip = Some(tooltip.into());
+        self.tray.tooltip_text = None;
+        self
+    }
+
+    /// Sets the text shown under the tooltip, or removes it with `None`.
+    pub fn set_tooltip_body(&mut self, body: Option<SharedString>) -> &mut Self {
+        self.tray.tooltip_body = body;
+        self
+    }
+
+    /// Sets the title text, replacing a localized title.
+    pub fn set_title(&mut self, title: impl Into<SharedString>) -> &mut Self {
+        self.tray.title = Some(title.into());
+        self.tray.title_text = None;
+        self
+    }
+
+    /// Sets the name Linux panels list the item under, see
+    /// [`Tray::sni_title`].
+    pub fn set_sni_title(&mut self, title: impl Into<SharedString>) -> &mut Self {
+        self.tray.sni_title = Some(title.into());
+        self
+    }
+
+    /// Sets the visibility state.
+    pub fn set_visible(&mut self, visible: bool) -> &mut Self {
+        self.tray.visible = visible;
+        self
+    }
+
+    /// Sets the passive state.
+    pub fn set_passive(&mut self, passive: bool) -> &mut Self {
+        self.tray.passive = passive;
+        self
+    }
+
+    /// Sets whether the tray asks for attention.
+    pub fn set_attention(&mut self, attention: bool) -> &mut Self {
+        self.tray.attention = attention;
+        self
+    }
+
+    /// Sets the passive and attention states to match `status`.
+    pub fn set_status(&mut self, status: TrayStatus) -> &mut Self {
+        self.tray.passive = status == TrayStatus::Passive;
+        self.tray.attention = status == TrayStatus::Attention;
+        self
+    }
+
+    /// Sets the count suffix of menu entry `id`.
+    pub fn set_menu_item_count(&mut self, id: impl Into<SharedString>, count: u32) -> &mut Self {
+        self.tray.menu_counts.insert(id.into(), count);
+        self
+    }
+
+    /// Shows or hides menu entry `id`.
+    pub fn set_menu_item_visible(
+        &mut self,
+        id: impl Into<SharedString>,
+        visible: bool,
+    ) -> &mut Self {
+        self.tray.menu_visibility.insert(id.into(), visible);
+        self
+    }
+
+    /// Enables or disables menu entry `id`.
+    pub fn set_menu_item_enabled(
+        &mut self,
+        id: impl Into<SharedString>,
+        enabled: bool,
+    ) -> &mut Self {
+        self.tray.menu_enabled.insert(id.into(), enabled);
+        self
+    }
+
+    /// Checks or unchecks menu entry `id`, see [`Tray::set_menu_item_checked`].
+    pub fn set_menu_item_checked(
+        &mut self,
+        id: impl Into<SharedString>,
+        checked: bool,
+    ) -> &mut Self {
+        self.tray.set_menu_item_checked(id.into(), checked);
+        self
+    }
+}
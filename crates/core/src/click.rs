@@ -0,0 +1,63 @@
+use crate::ClickEvent;
+use gpui::{Action, App};
+use std::fmt;
+use std::sync::Arc;
+
+/// Callback run by [`ClickHandler::Callback`].
+pub type ClickCallback = Arc<dyn Fn(&ClickEvent, &mut App) + Send + Sync>;
+
+/// What a left click on the icon runs, set with
+/// [`Tray::on_left_click`](crate::Tray::on_left_click).
+pub enum ClickHandler {
+    /// Dispatches the action.
+    Action(Box<dyn Action>),
+    /// Calls the closure with the click.
+    Callback(ClickCallback),
+}
+
+impl Clone for ClickHandler {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Action(action) => Self::Action(action.boxed_clone()),
+            Self::Callback(callback) => Self::Callback(callback.clone()),
+        }
+    }
+}
+
+impl fmt::Debug for ClickHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Action(action) => f.debug_tuple("Action").field(&action.name()).finish(),
+            Self::Callback(_) => f.write_str("Callback"),
+        }
+    }
+}
+
+/// An action or a `Fn(&ClickEvent, &mut App)` closure, accepted by
+/// [`Tray::on_left_click`](crate::Tray::on_left_click).
+///
+/// `Marker` only keeps the two implementations apart; it is inferred.
+pub trait IntoClickHandler<Marker> {
+    fn into_click_handler(self) -> ClickHandler;
+}
+
+#[doc(hidden)]
+pub struct ActionMarker;
+
+#[doc(hidden)]
+pub struct CallbackMarker;
+
+impl<A: Action> IntoClickHandler<ActionMarker> for A {
+    fn into_click_handler(self) -> ClickHandler {
+        ClickHandler::Action(Box::new(self))
+    }
+}
+
+impl<F> IntoClickHandler<CallbackMarker> for F
+where
+    F: Fn(&ClickEvent, &mut App) + Send + Sync + 'static,
+{
+    fn into_click_handler(self) -> ClickHandler {
+        ClickHandler::Callback(Arc::new(self))
+    }
+}
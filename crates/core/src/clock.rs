@@ -0,0 +1,36 @@
+use gpui::BackgroundExecutor;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+/// A future that completes once a [`TrayClock`] has advanced far enough.
+pub type ClockSleep = Pin<Box<dyn Future<Output = ()>>>;
+
+/// Source of time for the tray's timers: icon animations, flashes and the
+/// popover's reopen grace period.
+///
+/// The runtime uses [`SystemClock`] unless the app installs another with
+/// `TrayAppContext::set_tray_clock`, which lets tests advance time
+/// themselves instead of sleeping.
+pub trait TrayClock {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+
+    /// Returns a future that completes once `duration` has passed.
+    /// `executor` provides real timers for clocks that need them.
+    fn sleep(&self, duration: Duration, executor: &BackgroundExecutor) -> ClockSleep;
+}
+
+/// Wall-clock time, with timers from GPUI's executor.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct SystemClock;
+
+impl TrayClock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration, executor: &BackgroundExecutor) -> ClockSleep {
+        Box::pin(executor.timer(duration))
+    }
+}
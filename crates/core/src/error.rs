@@ -15,6 +15,15 @@ pub enum Error {
     #[error("Current platform is not supported yet")]
     UnsupportedPlatform,
 
+    /// There is no tray host to show the icon in, such as a running taskbar
+    /// or a StatusNotifierWatcher.
+    #[error("No system tray available: {reason}")]
+    PlatformUnavailable { reason: String },
+
+    /// The platform could not create the context menu.
+    #[error("Failed to build the tray menu")]
+    MenuBuildFailed,
+
     /// The backend runtime is closed.
     #[error("Tray runtime is closed")]
     RuntimeClosed,
@@ -26,6 +35,23 @@ pub enum Error {
     /// The provided icon data is invalid or unsupported.
     #[error("Invalid icon data")]
     InvalidIcon,
+
+    /// The icon source is wider or taller than [`max_icon_dimension`](crate::max_icon_dimension).
+    #[error("Icon is larger than {max}x{max} pixels")]
+    IconTooLarge { max: u32 },
+
+    /// The tooltip is longer than the platform shows and the tray asks for
+    /// [`TooltipOverflow::Error`](crate::TooltipOverflow::Error).
+    #[error("Tooltip is longer than {max} UTF-16 units")]
+    TooltipTooLong { max: usize },
+
+    /// The image data does not match any supported image format.
+    #[error("Unrecognized image format")]
+    UnknownImageFormat,
+
+    /// Reading or writing a [`TrayStorage`](crate::TrayStorage) failed.
+    #[error("Tray storage failed: {0}")]
+    Storage(#[from] std::io::Error),
 }
 
 /// Errors raised from platform backend implementations.
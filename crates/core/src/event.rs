@@ -1,19 +1,272 @@
+use crate::Error;
 use gpui::*;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 #[derive(Clone, PartialEq, Debug, Action)]
 #[action(namespace = gpui_tray, no_json)]
 pub struct ClickEvent {
     pub button: MouseButton,
     pub position: Point<f32>,
+    /// xdg-activation token the host handed over with the click, on Linux.
+    ///
+    /// Wayland compositors only let a client take focus with a token from
+    /// the user's input. Pass it on when the click raises a window outside
+    /// of GPUI, e.g. as `XDG_ACTIVATION_TOKEN` for a spawned process.
+    pub activation_token: Option<SharedString>,
 }
 
-/// Left mouse button double-click event for tray icon.
+/// Double-click on the tray icon.
+///
+/// Follows the [`ClickEvent`] of the second click. Only emitted where the
+/// platform reports double-clicks (Windows, and Linux when docked through
+/// XEmbed); StatusNotifierItem hosts only send single activations.
 #[derive(Clone, PartialEq, Debug, Action)]
 #[action(namespace = gpui_tray, no_json)]
-pub struct DoubleClickEvent;
+pub struct DoubleClickEvent {
+    pub button: MouseButton,
+    pub position: Point<f32>,
+}
+
+/// The host started showing the tray tooltip.
+///
+/// Only emitted where the platform reports it (Windows).
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct TooltipShownEvent;
+
+/// The host stopped showing the tray tooltip.
+///
+/// Only emitted where the platform reports it (Windows).
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct TooltipHiddenEvent;
+
+/// A context menu entry with an id was selected.
+///
+/// Dispatched right before the entry's own action, so one handler can
+/// observe every selection by id.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct MenuSelectEvent {
+    pub id: SharedString,
+}
+
+/// The context menu was opened, by the user or with `show_menu`.
+///
+/// Emitted on Windows, and on Linux where the host reports it.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct MenuOpenedEvent;
+
+/// The user pressed F1 on the highlighted context menu entry `item_id`.
+///
+/// Lets the app open documentation for the entry. Only Windows reports it;
+/// Linux hosts handle the menu's keys themselves. The menu stays open.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct HelpRequestedEvent {
+    pub item_id: SharedString,
+}
+
+/// The tray host restarted and the icon was registered with it again.
+///
+/// Emitted when Explorer recreates the taskbar on Windows, or when the
+/// StatusNotifierWatcher comes back on Linux. The icon, tooltip and menu are
+/// restored by the backend; apps only need this for logging.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct HostRestartedEvent;
+
+/// A checkbox or radio entry with an id was selected.
+///
+/// Dispatched before [`MenuSelectEvent`]. The tray runtime has already
+/// recorded `checked` by then, so the next menu shows the new state.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct MenuToggleEvent {
+    pub id: SharedString,
+    pub checked: bool,
+}
+
+/// The user clicked a notification shown with `TrayAppContext::tray_notify`.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct NotificationClickedEvent;
+
+/// Direction of a [`ScrollDelta`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScrollOrientation {
+    Vertical,
+    Horizontal,
+}
+
+/// Scroll amount normalized across platforms.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ScrollDelta {
+    /// Distance in wheel notches (lines); positive is down or right.
+    pub lines: f32,
+    /// Whether `lines` may be fractional, as with touchpads.
+    pub precise: bool,
+    pub orientation: ScrollOrientation,
+}
+
+/// Mouse wheel or touchpad scroll over the tray icon.
+///
+/// Emitted on Linux when the host forwards scrolling, and on Windows for
+/// trays built with
+/// [`windows_scroll_events`](crate::WindowsTrayExt::windows_scroll_events).
+/// Events that pile up before the app handles them are merged into one.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct ScrollEvent {
+    pub delta: ScrollDelta,
+}
+
+impl ScrollEvent {
+    /// Adds `other` into this event if both scroll the same way.
+    pub fn coalesce(&mut self, other: &ScrollEvent) -> bool {
+        if self.delta.orientation != other.delta.orientation
+            || self.delta.precise != other.delta.precise
+        {
+            return false;
+        }
+        self.delta.lines += other.delta.lines;
+        true
+    }
+}
+
+/// Color scheme of the desktop the tray icon is shown on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SystemTheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// The desktop switched between light and dark mode.
+///
+/// Windows reports the taskbar theme, Linux the color scheme of the
+/// settings portal. The tray runtime already shows the matching icon of
+/// [`Tray::icon_for_theme`](crate::Tray::icon_for_theme) by then.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct ThemeChangedEvent {
+    pub theme: SystemTheme,
+}
+
+/// Background failure reported by the backend after the tray was set up.
+///
+/// Raised for problems that happen outside of a `set_tray` call, such as the
+/// tray host disappearing or an icon failing to decode on the worker thread.
+/// Carries the error itself, so apps can match on its kind, e.g. to retry
+/// once [`Error::PlatformUnavailable`] clears up.
+#[derive(Clone, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct ErrorEvent {
+    pub error: Arc<Error>,
+}
+
+impl ErrorEvent {
+    pub fn new(error: Error) -> Self {
+        Self {
+            error: Arc::new(error),
+        }
+    }
+}
+
+/// Events are equal when they carry the same failure; [`Error`] itself has
+/// no equality.
+impl PartialEq for ErrorEvent {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.error, &other.error)
+    }
+}
+
+/// How often the user interacted with the tray since it was first set.
+///
+/// Meant for support tooling and usage insight, e.g. telling whether users
+/// touch the tray at all.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TrayInteractionStats {
+    pub clicks: u64,
+    pub double_clicks: u64,
+    pub scrolls: u64,
+    /// Times the context menu was opened, where the platform reports it.
+    pub menu_opens: u64,
+    /// Context menu entries selected.
+    pub menu_selections: u64,
+    /// When any of the above last happened.
+    pub last_interaction: Option<SystemTime>,
+}
+
+impl TrayInteractionStats {
+    /// Counts `action` if it is a click, double-click, scroll or menu
+    /// opening. Menu selections are counted with
+    /// [`record_menu_selection`](Self::record_menu_selection) instead, as
+    /// their actions are the app's own.
+    pub fn record(&mut self, action: &dyn Action) {
+        let any = action.as_any();
+        if any.is::<ClickEvent>() {
+            self.clicks += 1;
+        } else if any.is::<DoubleClickEvent>() {
+            self.double_clicks += 1;
+        } else if any.is::<ScrollEvent>() {
+            self.scrolls += 1;
+        } else if any.is::<MenuOpenedEvent>() {
+            self.menu_opens += 1;
+        } else {
+            return;
+        }
+        self.last_interaction = Some(SystemTime::now());
+    }
+
+    /// Counts one selected context menu entry.
+    pub fn record_menu_selection(&mut self) {
+        self.menu_selections += 1;
+        self.last_interaction = Some(SystemTime::now());
+    }
+}
 
 /// Internal runtime event emitted by platform backends.
 #[derive(Debug)]
 pub enum RuntimeEvent {
     Action(Box<dyn Action>),
+    /// The actions of a selected context menu entry, as returned by
+    /// [`TrayMenuItem::selection_actions`](crate::TrayMenuItem::selection_actions).
+    MenuSelection(Vec<Box<dyn Action>>),
+    Error(Error),
+}
+
+#[cfg(test)]
+mod tests {
+    // Not a glob import: gpui's own `test` attribute would shadow the
+    // standard one.
+    use super::{
+        ClickEvent, MenuOpenedEvent, MenuSelectEvent, SystemTheme, ThemeChangedEvent,
+        TrayInteractionStats,
+    };
+    use gpui::{MouseButton, Point};
+
+    #[test]
+    fn stats_count_interactions_only() {
+        let mut stats = TrayInteractionStats::default();
+        stats.record(&ClickEvent {
+            button: MouseButton::Left,
+            position: Point::default(),
+            activation_token: None,
+        });
+        stats.record(&MenuOpenedEvent);
+        stats.record(&MenuSelectEvent { id: "quit".into() });
+        stats.record(&ThemeChangedEvent {
+            theme: SystemTheme::Dark,
+        });
+        assert_eq!(stats.clicks, 1);
+        assert_eq!(stats.menu_opens, 1);
+        assert_eq!(stats.menu_selections, 0);
+
+        stats.record_menu_selection();
+        assert_eq!(stats.menu_selections, 1);
+        assert!(stats.last_interaction.is_some());
+    }
 }
@@ -0,0 +1,74 @@
+//! Names of common icons from the freedesktop.org Icon Naming
+//! Specification, for [`Tray::icon_name`](crate::Tray::icon_name).
+//!
+//! Every icon theme that follows the specification provides these, so the
+//! icon matches the rest of the panel:
+//!
+//! ```rust,ignore
+//! let tray = Tray::new()
+//!     .icon(offline_png)
+//!     .icon_name(freedesktop_icons::NETWORK_OFFLINE);
+//! ```
+
+// Status
+pub const DIALOG_INFORMATION: &str = "dialog-information";
+pub const DIALOG_WARNING: &str = "dialog-warning";
+pub const DIALOG_ERROR: &str = "dialog-error";
+pub const DIALOG_QUESTION: &str = "dialog-question";
+pub const DIALOG_PASSWORD: &str = "dialog-password";
+pub const NETWORK_IDLE: &str = "network-idle";
+pub const NETWORK_OFFLINE: &str = "network-offline";
+pub const NETWORK_ERROR: &str = "network-error";
+pub const NETWORK_RECEIVE: &str = "network-receive";
+pub const NETWORK_TRANSMIT: &str = "network-transmit";
+pub const NETWORK_TRANSMIT_RECEIVE: &str = "network-transmit-receive";
+pub const NETWORK_WIRELESS_ENCRYPTED: &str = "network-wireless-encrypted";
+pub const AUDIO_VOLUME_HIGH: &str = "audio-volume-high";
+pub const AUDIO_VOLUME_MEDIUM: &str = "audio-volume-medium";
+pub const AUDIO_VOLUME_LOW: &str = "audio-volume-low";
+pub const AUDIO_VOLUME_MUTED: &str = "audio-volume-muted";
+pub const BATTERY: &str = "battery";
+pub const BATTERY_CAUTION: &str = "battery-caution";
+pub const BATTERY_LOW: &str = "battery-low";
+pub const MAIL_UNREAD: &str = "mail-unread";
+pub const MAIL_READ: &str = "mail-read";
+pub const MAIL_ATTACHMENT: &str = "mail-attachment";
+pub const SECURITY_HIGH: &str = "security-high";
+pub const SECURITY_MEDIUM: &str = "security-medium";
+pub const SECURITY_LOW: &str = "security-low";
+pub const SOFTWARE_UPDATE_AVAILABLE: &str = "software-update-available";
+pub const SOFTWARE_UPDATE_URGENT: &str = "software-update-urgent";
+pub const TASK_DUE: &str = "task-due";
+pub const TASK_PAST_DUE: &str = "task-past-due";
+pub const USER_AVAILABLE: &str = "user-available";
+pub const USER_AWAY: &str = "user-away";
+pub const USER_IDLE: &str = "user-idle";
+pub const USER_OFFLINE: &str = "user-offline";
+pub const USER_TRASH_FULL: &str = "user-trash-full";
+pub const WEATHER_CLEAR: &str = "weather-clear";
+pub const WEATHER_OVERCAST: &str = "weather-overcast";
+pub const WEATHER_STORM: &str = "weather-storm";
+
+// Actions
+pub const MEDIA_PLAYBACK_START: &str = "media-playback-start";
+pub const MEDIA_PLAYBACK_PAUSE: &str = "media-playback-pause";
+pub const MEDIA_PLAYBACK_STOP: &str = "media-playback-stop";
+pub const MEDIA_RECORD: &str = "media-record";
+pub const PROCESS_STOP: &str = "process-stop";
+pub const SYSTEM_SHUTDOWN: &str = "system-shutdown";
+pub const VIEW_REFRESH: &str = "view-refresh";
+
+// Devices
+pub const AUDIO_INPUT_MICROPHONE: &str = "audio-input-microphone";
+pub const CAMERA_WEB: &str = "camera-web";
+pub const DRIVE_HARDDISK: &str = "drive-harddisk";
+pub const INPUT_KEYBOARD: &str = "input-keyboard";
+pub const PRINTER: &str = "printer";
+
+// Applications and places
+pub const APPLICATIONS_SYSTEM: &str = "applications-system";
+pub const FOLDER: &str = "folder";
+pub const FOLDER_DOWNLOAD: &str = "folder-download";
+pub const HELP_BROWSER: &str = "help-browser";
+pub const PREFERENCES_SYSTEM: &str = "preferences-system";
+pub const UTILITIES_TERMINAL: &str = "utilities-terminal";
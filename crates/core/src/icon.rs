@@ -0,0 +1,267 @@
+use crate::{Error, Result};
+use gpui::{Image, ImageFormat};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageError, ImageReader, Limits, RgbaImage};
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Default of [`max_icon_dimension`].
+pub const DEFAULT_MAX_ICON_DIMENSION: u32 = 4096;
+
+static MAX_ICON_DIMENSION: AtomicU32 = AtomicU32::new(DEFAULT_MAX_ICON_DIMENSION);
+
+/// Largest width or height of a raster image accepted as an icon source.
+pub fn max_icon_dimension() -> u32 {
+    MAX_ICON_DIMENSION.load(Ordering::Relaxed)
+}
+
+/// Sets the largest width or height of a raster image accepted as an icon
+/// source, for the whole process.
+///
+/// Sources within the limit are scaled to the platform size as usual. Larger
+/// ones fail with [`Error::IconTooLarge`] before their pixels are decoded, so
+/// a bad image cannot take unbounded memory on the backend thread.
+pub fn set_max_icon_dimension(max: u32) {
+    MAX_ICON_DIMENSION.store(max.max(1), Ordering::Relaxed);
+}
+
+/// Square icon bitmap in straight-alpha RGBA8, row by row.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RgbaIcon {
+    /// Edge length in pixels.
+    pub size: u32,
+    /// `size * size * 4` bytes.
+    pub rgba: Vec<u8>,
+}
+
+/// Where a tray icon is shown, which decides the bitmap sizes it needs.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum IconTarget {
+    /// The Windows notification area at `dpi`, 96 being 100% scaling.
+    WindowsTray { dpi: u32 },
+    /// The large icon of a Windows notification at `dpi`.
+    WindowsNotification { dpi: u32 },
+    /// The macOS menu bar at a backing `scale` of 1.0, or 2.0 on Retina.
+    MacMenuBar { scale: f32 },
+    /// StatusNotifierItem pixmaps. Hosts pick the one closest to their
+    /// panel size and scale it, so several common sizes are offered.
+    StatusNotifierItem,
+}
+
+impl IconTarget {
+    /// Edge lengths in pixels the icon is rendered at, smallest first.
+    pub fn sizes(&self) -> Vec<u32> {
+        let at_dpi = |size: u32, dpi: u32| (size * dpi.max(1) + 48) / 96;
+        match *self {
+            Self::WindowsTray { dpi } => vec![at_dpi(16, dpi)],
+            Self::WindowsNotification { dpi } => vec![at_dpi(32, dpi)],
+            // Menu bar icons are 18pt tall.
+            Self::MacMenuBar { scale } => vec![(18.0 * scale.max(1.0)).round() as u32],
+            Self::StatusNotifierItem => vec![16, 22, 24, 32, 48],
+        }
+    }
+}
+
+/// Decodes `image` at every size `target` needs, decoding the source only once.
+pub fn decode_icon_for(image: &Image, target: IconTarget) -> Result<Vec<RgbaIcon>> {
+    decode_icon_sizes(image, &target.sizes())
+}
+
+/// Decodes `image` into a `size` x `size` bitmap for the platform.
+///
+/// Raster images are scaled to fill the square, cropping any overflow. SVG
+/// images are rendered directly at `size`, so they stay sharp at every DPI;
+/// without the `svg` feature they fail with [`Error::InvalidIcon`].
+pub fn decode_icon(image: &Image, size: u32) -> Result<RgbaIcon> {
+    let mut icons = decode_icon_sizes(image, &[size])?;
+    Ok(icons.remove(0))
+}
+
+/// Like [`decode_icon`], for several sizes at once, decoding the image only once.
+pub fn decode_icon_sizes(image: &Image, sizes: &[u32]) -> Result<Vec<RgbaIcon>> {
+    if image.format == ImageFormat::Svg {
+        return sizes
+            .iter()
+            .map(|size| render_svg(&image.bytes, (*size).max(1)))
+            .collect();
+    }
+
+    let decoded = load_raster(&image.bytes)?;
+    Ok(sizes
+        .iter()
+        .map(|size| {
+            let size = (*size).max(1);
+            let rgba = decoded
+                .resize_to_fill(size, size, FilterType::Lanczos3)
+                .to_rgba8()
+                .into_raw();
+            RgbaIcon { size, rgba }
+        })
+        .collect())
+}
+
+#[cfg(feature = "svg")]
+fn render_svg(bytes: &[u8], size: u32) -> Result<RgbaIcon> {
+    use resvg::{tiny_skia, usvg};
+
+    let tree =
+        usvg::Tree::from_data(bytes, &usvg::Options::default()).map_err(|_| Error::InvalidIcon)?;
+    let mut pixmap = tiny_skia::Pixmap::new(size, size).ok_or(Error::InvalidIcon)?;
+
+    // Fit the document into the square, centered, keeping its aspect ratio.
+    let (width, height) = (tree.size().width(), tree.size().height());
+    let scale = (size as f32 / width).min(size as f32 / height);
+    let offset_x = (size as f32 - width * scale) / 2.0;
+    let offset_y = (size as f32 - height * scale) / 2.0;
+    let transform = tiny_skia::Transform::from_row(scale, 0.0, 0.0, scale, offset_x, offset_y);
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    Ok(RgbaIcon { size, rgba })
+}
+
+#[cfg(not(feature = "svg"))]
+fn render_svg(_bytes: &[u8], _size: u32) -> Result<RgbaIcon> {
+    Err(Error::InvalidIcon)
+}
+
+/// Decoder limits keeping images within [`max_icon_dimension`].
+pub(crate) fn icon_limits() -> Limits {
+    let max = max_icon_dimension();
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(max);
+    limits.max_image_height = Some(max);
+    limits
+}
+
+/// Maps a decoder error, telling images over the limits apart.
+pub(crate) fn decode_error(err: ImageError) -> Error {
+    match err {
+        ImageError::Limits(_) => Error::IconTooLarge {
+            max: max_icon_dimension(),
+        },
+        _ => Error::InvalidIcon,
+    }
+}
+
+/// Decodes raster image data within [`max_icon_dimension`], rejecting empty images.
+pub(crate) fn load_raster(bytes: &[u8]) -> Result<DynamicImage> {
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|_| Error::InvalidIcon)?;
+    reader.limits(icon_limits());
+    let decoded = reader.decode().map_err(decode_error)?;
+
+    if decoded.width() == 0 || decoded.height() == 0 {
+        return Err(Error::InvalidIcon);
+    }
+    Ok(decoded)
+}
+
+/// Guesses the format of encoded image data from its leading bytes.
+///
+/// Recognizes PNG, JPEG, GIF, BMP, ICO, WebP, TIFF and SVG. GPUI has no ICO
+/// format, so icon files are reported as [`ImageFormat::Bmp`]. Tray icons
+/// are decoded by sniffing the bytes again rather than by trusting the
+/// reported format, which tells the two apart.
+pub fn detect_image_format(bytes: &[u8]) -> Option<ImageFormat> {
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    if bytes.starts_with(PNG) {
+        Some(ImageFormat::Png)
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        Some(ImageFormat::Jpeg)
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some(ImageFormat::Gif)
+    } else if bytes.starts_with(b"BM") || bytes.starts_with(b"\0\0\x01\0") {
+        Some(ImageFormat::Bmp)
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        Some(ImageFormat::Webp)
+    } else if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+        Some(ImageFormat::Tiff)
+    } else if looks_like_svg(bytes) {
+        Some(ImageFormat::Svg)
+    } else {
+        None
+    }
+}
+
+/// Wraps encoded image data in an [`Image`], detecting its format.
+pub fn image_from_bytes(bytes: impl Into<Vec<u8>>) -> Result<Image> {
+    let bytes = bytes.into();
+    let format = detect_image_format(&bytes).ok_or(Error::UnknownImageFormat)?;
+    Ok(Image::from_bytes(format, bytes))
+}
+
+/// Encodes a tightly packed, non-premultiplied RGBA8 buffer as a PNG [`Image`].
+///
+/// Fails with [`Error::InvalidIcon`] if `rgba` does not hold exactly
+/// `width * height` pixels.
+pub fn image_from_rgba(width: u32, height: u32, rgba: Vec<u8>) -> Result<Image> {
+    let pixels = RgbaImage::from_raw(width, height, rgba).ok_or(Error::InvalidIcon)?;
+    let mut bytes = Vec::new();
+    pixels
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .map_err(|_| Error::InvalidIcon)?;
+    Ok(Image::from_bytes(ImageFormat::Png, bytes))
+}
+
+/// SVG is text, so look for the root element near the start instead of a
+/// fixed signature.
+fn looks_like_svg(bytes: &[u8]) -> bool {
+    let head = &bytes[..bytes.len().min(1024)];
+    let head = head.strip_prefix(b"\xef\xbb\xbf").unwrap_or(head);
+    let Some(start) = head.iter().position(|b| !b.is_ascii_whitespace()) else {
+        return false;
+    };
+    let head = &head[start..];
+
+    (head.starts_with(b"<svg") || head.starts_with(b"<?xml") || head.starts_with(b"<!--"))
+        && head.windows(4).any(|window| window == b"<svg")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(format: image::ImageFormat) -> Vec<u8> {
+        let pixels = RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]));
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(pixels)
+            .write_to(&mut Cursor::new(&mut bytes), format)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn detected_formats_decode() {
+        let cases = [
+            (image::ImageFormat::Png, ImageFormat::Png),
+            (image::ImageFormat::Gif, ImageFormat::Gif),
+            (image::ImageFormat::Bmp, ImageFormat::Bmp),
+            (image::ImageFormat::Ico, ImageFormat::Bmp),
+            (image::ImageFormat::Tiff, ImageFormat::Tiff),
+        ];
+        for (encoding, expected) in cases {
+            let image = image_from_bytes(encode(encoding)).unwrap();
+            assert_eq!(image.format, expected, "{encoding:?}");
+            let icon = decode_icon(&image, 4).unwrap();
+            assert_eq!(icon.rgba.len(), 4 * 4 * 4, "{encoding:?}");
+        }
+    }
+
+    #[test]
+    fn unknown_bytes_are_rejected() {
+        assert!(matches!(
+            image_from_bytes(b"not an image".to_vec()),
+            Err(Error::UnknownImageFormat)
+        ));
+    }
+}
@@ -0,0 +1,418 @@
+//! Software compositing for tray icons.
+//!
+//! Builds derived icons (badges, counters, overlays, tints, plates, progress
+//! bars) from a base image without any platform API, so the result can be
+//! handed to [`Tray::icon`](crate::Tray::icon) like any other image.
+//!
+//! ```rust,ignore
+//! let icon = IconComposer::new(&base, 32)?
+//!     .tint(rgb(0x808080).into())
+//!     .badge(rgb(0xdc3232).into())
+//!     .finish()?;
+//! cx.update_tray(|tray| tray.icon = Some(icon))?;
+//! ```
+
+use crate::icon::load_raster;
+use crate::{Error, Result};
+use gpui::{Image, ImageFormat, Rgba};
+use image::imageops::{self, FilterType};
+use image::{Rgba as Pixel, RgbaImage};
+use std::io::Cursor;
+
+/// Corner of the icon an overlay or badge is anchored to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Corner {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Outline of the backdrop drawn by [`IconComposer::plate`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlateShape {
+    Circle,
+    /// A square with corners rounded by `radius`, as a fraction of the
+    /// canvas size; `0.5` gives a circle.
+    RoundedRect {
+        radius: f32,
+    },
+}
+
+impl Default for PlateShape {
+    fn default() -> Self {
+        Self::RoundedRect { radius: 0.2 }
+    }
+}
+
+/// Square RGBA canvas that compositing steps are applied to in order.
+#[derive(Clone, Debug)]
+pub struct IconComposer {
+    canvas: RgbaImage,
+}
+
+impl IconComposer {
+    /// Decodes `base` and scales it to a `size` x `size` canvas.
+    pub fn new(base: &Image, size: u32) -> Result<Self> {
+        let size = size.max(1);
+        let canvas = decode(base)?;
+        let canvas = imageops::resize(&canvas, size, size, FilterType::Lanczos3);
+        Ok(Self { canvas })
+    }
+
+    /// Starts from a transparent `size` x `size` canvas.
+    pub fn blank(size: u32) -> Self {
+        Self {
+            canvas: RgbaImage::new(size.max(1), size.max(1)),
+        }
+    }
+
+    /// Edge length of the canvas in pixels.
+    pub fn size(&self) -> u32 {
+        self.canvas.width()
+    }
+
+    /// Recolors every pixel to `color`, keeping the icon's shape and shading.
+    ///
+    /// The color's alpha sets how strongly the tint replaces the original.
+    pub fn tint(mut self, color: Rgba) -> Self {
+        let target = [color.r, color.g, color.b];
+        for pixel in self.canvas.pixels_mut() {
+            let luma =
+                (0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32)
+                    / 255.0;
+            for channel in 0..3 {
+                let tinted = target[channel] * luma * 255.0;
+                pixel[channel] = lerp(pixel[channel] as f32, tinted, color.a);
+            }
+        }
+        self
+    }
+
+    /// Draws `overlay` into `corner`, scaled to `fraction` of the canvas size.
+    pub fn overlay(mut self, overlay: &Image, corner: Corner, fraction: f32) -> Result<Self> {
+        let side = self.scaled(fraction);
+        let overlay = imageops::resize(&decode(overlay)?, side, side, FilterType::Lanczos3);
+        let (x, y) = self.anchor(corner, side);
+        imageops::overlay(&mut self.canvas, &overlay, x as i64, y as i64);
+        Ok(self)
+    }
+
+    /// Draws a filled dot in the top-right corner, the usual "attention" badge.
+    pub fn badge(self, color: Rgba) -> Self {
+        self.badge_at(Corner::TopRight, 0.4, color)
+    }
+
+    /// Draws a filled dot of `fraction` of the canvas size into `corner`.
+    pub fn badge_at(mut self, corner: Corner, fraction: f32, color: Rgba) -> Self {
+        let side = self.scaled(fraction);
+        let (x, y) = self.anchor(corner, side);
+        let radius = side as f32 / 2.0;
+        let fill = to_pixel(color);
+
+        for dy in 0..side {
+            for dx in 0..side {
+                let (cx, cy) = (dx as f32 + 0.5 - radius, dy as f32 + 0.5 - radius);
+                // One pixel of coverage falloff keeps the edge smooth at 16px.
+                let coverage = (radius - (cx * cx + cy * cy).sqrt() + 0.5).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    blend(self.canvas.get_pixel_mut(x + dx, y + dy), fill, coverage);
+                }
+            }
+        }
+        self
+    }
+
+    /// Draws a filled plate of `color` behind the icon.
+    ///
+    /// Keeps thin or dark glyphs legible on transparent panels and busy
+    /// wallpapers; a low alpha gives a subtle backdrop.
+    pub fn plate(mut self, shape: PlateShape, color: Rgba) -> Self {
+        let size = self.size();
+        let half = size as f32 / 2.0;
+        let radius = match shape {
+            PlateShape::Circle => half,
+            PlateShape::RoundedRect { radius } => radius.clamp(0.0, 0.5) * size as f32,
+        };
+        let fill = to_pixel(color);
+
+        let mut plate = RgbaImage::new(size, size);
+        for (x, y, pixel) in plate.enumerate_pixels_mut() {
+            let coverage = rounded_rect_coverage(
+                x as f32 + 0.5 - half,
+                y as f32 + 0.5 - half,
+                (half, half),
+                radius,
+            );
+            if coverage > 0.0 {
+                blend(pixel, fill, coverage);
+            }
+        }
+
+        imageops::overlay(&mut plate, &self.canvas, 0, 0);
+        self.canvas = plate;
+        self
+    }
+
+    /// Draws `count` on a pill in the top-right corner, e.g. unread messages.
+    ///
+    /// Counts above 99 are shown as "99+". The pill grows to the left to fit
+    /// the digits.
+    pub fn count_badge(mut self, count: u32, fill: Rgba, text: Rgba) -> Self {
+        let label = count_label(count);
+        let size = self.size();
+        let height = self.scaled(0.5);
+        // Glyphs are 3x5 cells with one cell between them.
+        let cells = label.len() as u32 * 4 - 1;
+        let mut scale = (height / 8).max(1);
+        while scale > 1 && cells * scale + 2 > size {
+            scale -= 1;
+        }
+        let width = (cells * scale + height / 2).max(height).min(size);
+
+        let (x0, fill) = (size - width, to_pixel(fill));
+        let half = (width as f32 / 2.0, height as f32 / 2.0);
+        for y in 0..height {
+            for x in 0..width {
+                let coverage = rounded_rect_coverage(
+                    x as f32 + 0.5 - half.0,
+                    y as f32 + 0.5 - half.1,
+                    half,
+                    half.1,
+                );
+                if coverage > 0.0 {
+                    blend(self.canvas.get_pixel_mut(x0 + x, y), fill, coverage);
+                }
+            }
+        }
+
+        let text = to_pixel(text);
+        let left = x0 as i64 + (width as i64 - (cells * scale) as i64) / 2;
+        let top = (height as i64 - 5 * scale as i64) / 2;
+        for (index, glyph) in label.chars().map(glyph).enumerate() {
+            let glyph_left = left + (index as u32 * 4 * scale) as i64;
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            let x = glyph_left + (column * scale + dx) as i64;
+                            let y = top + (row as u32 * scale + dy) as i64;
+                            if (0..size as i64).contains(&x) && (0..size as i64).contains(&y) {
+                                blend(self.canvas.get_pixel_mut(x as u32, y as u32), text, 1.0);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Draws a progress bar along the bottom edge; `progress` is clamped to `0.0..=1.0`.
+    pub fn progress(mut self, progress: f32, fill: Rgba, track: Rgba) -> Self {
+        let size = self.size();
+        // A canvas smaller than the minimum bar height is all bar.
+        let height = (size / 8).max(2).min(size);
+        let filled = (size as f32 * progress.clamp(0.0, 1.0)).round() as u32;
+        let (fill, track) = (to_pixel(fill), to_pixel(track));
+
+        for y in size - height..size {
+            for x in 0..size {
+                let color = if x < filled { fill } else { track };
+                blend(self.canvas.get_pixel_mut(x, y), color, 1.0);
+            }
+        }
+        self
+    }
+
+    /// Encodes the canvas as a PNG image.
+    pub fn finish(self) -> Result<Image> {
+        let mut bytes = Vec::new();
+        self.canvas
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .map_err(|_| Error::InvalidIcon)?;
+        Ok(Image::from_bytes(ImageFormat::Png, bytes))
+    }
+
+    fn scaled(&self, fraction: f32) -> u32 {
+        ((self.size() as f32 * fraction.clamp(0.0, 1.0)).round() as u32).clamp(1, self.size())
+    }
+
+    fn anchor(&self, corner: Corner, side: u32) -> (u32, u32) {
+        let far = self.size() - side;
+        match corner {
+            Corner::TopLeft => (0, 0),
+            Corner::TopRight => (far, 0),
+            Corner::BottomLeft => (0, far),
+            Corner::BottomRight => (far, far),
+        }
+    }
+}
+
+fn count_label(count: u32) -> String {
+    if count > 99 {
+        "99+".to_string()
+    } else {
+        count.to_string()
+    }
+}
+
+fn decode(image: &Image) -> Result<RgbaImage> {
+    load_raster(&image.bytes).map(|decoded| decoded.to_rgba8())
+}
+
+/// Coverage of the pixel at offset (`x`, `y`) from the center of a
+/// rectangle with half extents `half` and corner `radius`.
+fn rounded_rect_coverage(x: f32, y: f32, half: (f32, f32), radius: f32) -> f32 {
+    // Signed distance to the rounded rectangle, negative inside.
+    let qx = x.abs() - (half.0 - radius);
+    let qy = y.abs() - (half.1 - radius);
+    let outside = (qx.max(0.0).powi(2) + qy.max(0.0).powi(2)).sqrt();
+    let distance = outside + qx.max(qy).min(0.0) - radius;
+    (0.5 - distance).clamp(0.0, 1.0)
+}
+
+/// Rows of a 3x5 pixel glyph for the characters of a badge count, most
+/// significant bit on the left.
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        _ => [0; 5],
+    }
+}
+
+fn to_pixel(color: Rgba) -> Pixel<u8> {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Pixel([
+        channel(color.r),
+        channel(color.g),
+        channel(color.b),
+        channel(color.a),
+    ])
+}
+
+/// Source-over blending of `src` onto `dst`, with `src` alpha scaled by `coverage`.
+fn blend(dst: &mut Pixel<u8>, src: Pixel<u8>, coverage: f32) {
+    let src_a = src[3] as f32 / 255.0 * coverage;
+    let dst_a = dst[3] as f32 / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+    if out_a <= 0.0 {
+        *dst = Pixel([0, 0, 0, 0]);
+        return;
+    }
+
+    for channel in 0..3 {
+        let value =
+            (src[channel] as f32 * src_a + dst[channel] as f32 * dst_a * (1.0 - src_a)) / out_a;
+        dst[channel] = value.round().clamp(0.0, 255.0) as u8;
+    }
+    dst[3] = (out_a * 255.0).round() as u8;
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> u8 {
+    (from + (to - from) * t.clamp(0.0, 1.0))
+        .round()
+        .clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Pixel<u8> = Pixel([255, 255, 255, 255]);
+    const CLEAR: Pixel<u8> = Pixel([0, 0, 0, 0]);
+
+    fn rgba(r: f32, g: f32, b: f32, a: f32) -> Rgba {
+        Rgba { r, g, b, a }
+    }
+
+    fn filled(size: u32, pixel: Pixel<u8>) -> IconComposer {
+        IconComposer {
+            canvas: RgbaImage::from_pixel(size, size, pixel),
+        }
+    }
+
+    #[test]
+    fn tint_recolors_by_luminance() {
+        let tinted = filled(4, WHITE).tint(rgba(1.0, 0.0, 0.0, 1.0));
+        assert!(
+            tinted
+                .canvas
+                .pixels()
+                .all(|p| *p == Pixel([255, 0, 0, 255]))
+        );
+
+        let untouched = filled(4, WHITE).tint(rgba(1.0, 0.0, 0.0, 0.0));
+        assert!(untouched.canvas.pixels().all(|p| *p == WHITE));
+    }
+
+    #[test]
+    fn count_badge_caps_at_99_plus() {
+        assert_eq!(count_label(7), "7");
+        assert_eq!(count_label(99), "99");
+        assert_eq!(count_label(100), "99+");
+
+        let fill = rgba(1.0, 0.0, 0.0, 1.0);
+        let text = rgba(1.0, 1.0, 1.0, 1.0);
+        let draw = |count| {
+            IconComposer::blank(32)
+                .count_badge(count, fill, text)
+                .canvas
+        };
+        assert_eq!(draw(100), draw(12_345));
+        assert_ne!(draw(99), draw(100));
+    }
+
+    #[test]
+    fn count_badge_fits_tiny_canvases() {
+        let fill = rgba(1.0, 0.0, 0.0, 1.0);
+        for size in 1..=8 {
+            IconComposer::blank(size).count_badge(1_000, fill, fill);
+        }
+    }
+
+    #[test]
+    fn plate_fills_behind_the_icon() {
+        let plated = IconComposer::blank(32).plate(PlateShape::Circle, rgba(0.0, 0.0, 1.0, 1.0));
+        assert_eq!(*plated.canvas.get_pixel(16, 16), Pixel([0, 0, 255, 255]));
+        assert_eq!(*plated.canvas.get_pixel(0, 0), CLEAR);
+
+        // The icon stays on top of the plate.
+        let plated = filled(8, WHITE).plate(PlateShape::default(), rgba(0.0, 0.0, 1.0, 1.0));
+        assert!(plated.canvas.pixels().all(|p| *p == WHITE));
+    }
+
+    #[test]
+    fn progress_stays_within_the_canvas() {
+        let (fill, track) = (rgba(0.0, 1.0, 0.0, 1.0), rgba(0.5, 0.5, 0.5, 1.0));
+        for size in 1..=3 {
+            let bar = IconComposer::blank(size).progress(0.5, fill, track);
+            assert_eq!(bar.size(), size);
+        }
+
+        let bar = IconComposer::blank(16).progress(0.5, fill, track);
+        // Bottom two rows: filled up to half, track after.
+        assert_eq!(*bar.canvas.get_pixel(7, 15), Pixel([0, 255, 0, 255]));
+        assert_eq!(*bar.canvas.get_pixel(8, 14), Pixel([128, 128, 128, 255]));
+        assert_eq!(*bar.canvas.get_pixel(0, 13), CLEAR);
+
+        let full = IconComposer::blank(16).progress(2.0, fill, track);
+        assert_eq!(*full.canvas.get_pixel(15, 15), Pixel([0, 255, 0, 255]));
+        let empty = IconComposer::blank(16).progress(-1.0, fill, track);
+        assert_eq!(*empty.canvas.get_pixel(0, 15), Pixel([128, 128, 128, 255]));
+    }
+}
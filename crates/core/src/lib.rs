@@ -1,9 +1,43 @@
+pub use animation::*;
+pub use batch::*;
+pub use click::*;
+pub use clock::*;
 pub use error::*;
 pub use event::*;
+pub use icon::*;
+pub use locale::*;
+pub use menu::*;
+pub use menu_section::*;
+pub use menu_template::*;
+pub use notification::*;
+pub use queue::*;
+pub use raw_event::*;
+pub use router::*;
+pub use storage::*;
+pub use tooltip::*;
 pub use tray::*;
+pub use update::*;
 
+mod animation;
+mod batch;
+mod click;
+mod clock;
 pub mod error;
 mod event;
+pub mod freedesktop_icons;
+mod icon;
+pub mod icon_compose;
+mod locale;
+mod menu;
+mod menu_section;
+mod menu_template;
+mod notification;
 #[doc(hidden)]
 pub mod platform_trait;
+mod queue;
+mod raw_event;
+mod router;
+mod storage;
+mod tooltip;
 mod tray;
+mod update;
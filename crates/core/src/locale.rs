@@ -0,0 +1,45 @@
+use gpui::SharedString;
+
+/// Translates message keys into display text.
+///
+/// Install one with `TrayAppContext::set_localizer` and call
+/// `TrayAppContext::relocalize` after switching languages; every localized
+/// tray text is then resolved again and pushed to the platform.
+pub trait Localizer: Send + Sync {
+    /// Resolves `key` with the given arguments, or `None` if the key is unknown.
+    fn localize(&self, key: &str, args: &[(SharedString, SharedString)]) -> Option<String>;
+}
+
+/// A message key plus arguments, resolved through a [`Localizer`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalizedText {
+    /// Message key, for example a Fluent message id.
+    pub key: SharedString,
+    /// Named arguments substituted into the message.
+    pub args: Vec<(SharedString, SharedString)>,
+}
+
+impl LocalizedText {
+    /// Creates a localized text from a key and its arguments.
+    pub fn new<K, V>(key: impl Into<SharedString>, args: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<SharedString>,
+        V: Into<SharedString>,
+    {
+        Self {
+            key: key.into(),
+            args: args
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect(),
+        }
+    }
+
+    /// Resolves the text, falling back to the raw key when it can't be translated.
+    pub fn resolve(&self, localizer: Option<&dyn Localizer>) -> SharedString {
+        localizer
+            .and_then(|localizer| localizer.localize(&self.key, &self.args))
+            .map(SharedString::from)
+            .unwrap_or_else(|| self.key.clone())
+    }
+}
@@ -0,0 +1,431 @@
+use crate::{MenuSelectEvent, MenuToggleEvent};
+use gpui::{Action, MenuItem, SharedString};
+use std::fmt;
+
+/// A context menu entry.
+///
+/// Menu builders may return either `Vec<TrayMenuItem>` or plain
+/// `Vec<gpui::MenuItem>`; the latter is converted automatically.
+pub struct TrayMenuItem {
+    /// Stable identifier used to address the item after the menu was built.
+    pub id: Option<SharedString>,
+    /// Text displayed for the item.
+    pub label: SharedString,
+    /// Count rendered after the label, e.g. "Inbox (42)". Zero is not shown.
+    pub count: Option<u32>,
+    /// Whether the item is shown. Hidden items keep their place in the menu.
+    pub visible: bool,
+    /// Whether the item can be selected. Disabled items are shown grayed out.
+    pub enabled: bool,
+    /// Standard meaning of the item, if any.
+    pub role: Option<MenuRole>,
+    /// Keyboard shortcut shown next to the label, e.g. "Ctrl+Shift+Q".
+    pub accelerator: Option<SharedString>,
+    /// Question the user has to confirm before the entry's action runs.
+    pub confirm: Option<SharedString>,
+    /// What the item does.
+    pub kind: MenuItemKind,
+}
+
+/// Opens `url` with the platform opener.
+///
+/// The tray runtime handles this action itself when it comes from the tray
+/// menu, see [`TrayMenuItem::open_url`].
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct OpenUrl {
+    pub url: SharedString,
+}
+
+/// An entry with [`TrayMenuItem::confirm`] was selected.
+///
+/// The tray runtime asks `message` in a small window and dispatches
+/// `actions` once the user confirms. Apps that handle this action ask in
+/// their own UI instead.
+#[derive(Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct ConfirmMenuSelection {
+    pub message: SharedString,
+    /// What selecting the entry dispatches, in order.
+    pub actions: Vec<Box<dyn Action>>,
+}
+
+/// Standard meaning of a menu entry.
+///
+/// Roles are hints for the macOS backend, which maps them to the standard
+/// item tags and selectors once it shows menus. Elsewhere they have no
+/// effect: entries stay where the app put them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuRole {
+    About,
+    Preferences,
+    Help,
+    Quit,
+}
+
+/// The behavior of a [`TrayMenuItem`].
+pub enum MenuItemKind {
+    /// A separator line.
+    Separator,
+    /// An entry dispatching an action when selected.
+    Action(Box<dyn Action>),
+    /// An entry with a check mark, dispatching an action when selected.
+    ///
+    /// Selecting an entry that has an id flips the check mark, see
+    /// [`MenuToggleEvent`](crate::MenuToggleEvent). Without an id the app
+    /// has to rebuild the menu itself.
+    Checkbox {
+        checked: bool,
+        action: Box<dyn Action>,
+    },
+    /// One option of a radio group, dispatching an action when selected.
+    ///
+    /// Adjacent radio entries on the same menu level form a group.
+    Radio {
+        checked: bool,
+        action: Box<dyn Action>,
+    },
+    /// A nested menu.
+    Submenu(Vec<TrayMenuItem>),
+}
+
+impl TrayMenuItem {
+    /// Creates an entry dispatching `action` when selected.
+    pub fn action(label: impl Into<SharedString>, action: impl Action) -> Self {
+        Self::new(label, MenuItemKind::Action(Box::new(action)))
+    }
+
+    /// Creates an entry with a check mark.
+    pub fn checkbox(label: impl Into<SharedString>, checked: bool, action: impl Action) -> Self {
+        Self::new(
+            label,
+            MenuItemKind::Checkbox {
+                checked,
+                action: Box::new(action),
+            },
+        )
+    }
+
+    /// Creates an option of a radio group.
+    pub fn radio(label: impl Into<SharedString>, checked: bool, action: impl Action) -> Self {
+        Self::new(
+            label,
+            MenuItemKind::Radio {
+                checked,
+                action: Box::new(action),
+            },
+        )
+    }
+
+    /// Creates a separator.
+    pub fn separator() -> Self {
+        Self::new(SharedString::default(), MenuItemKind::Separator)
+    }
+
+    /// Creates a nested menu.
+    pub fn submenu(label: impl Into<SharedString>, items: Vec<TrayMenuItem>) -> Self {
+        Self::new(label, MenuItemKind::Submenu(items))
+    }
+
+    /// Creates an entry with a count suffix that can later be changed with
+    /// `TrayAppContext::set_menu_item_count`.
+    pub fn with_count(
+        id: impl Into<SharedString>,
+        label: impl Into<SharedString>,
+        count: u32,
+        action: impl Action,
+    ) -> Self {
+        Self::action(label, action).id(id).count(count)
+    }
+
+    /// Creates an entry opening `url` in the default browser, with no handler
+    /// needed in the app.
+    pub fn open_url(
+        id: impl Into<SharedString>,
+        label: impl Into<SharedString>,
+        url: impl Into<SharedString>,
+    ) -> Self {
+        Self::action(label, OpenUrl { url: url.into() }).id(id)
+    }
+
+    fn new(label: impl Into<SharedString>, kind: MenuItemKind) -> Self {
+        Self {
+            id: None,
+            label: label.into(),
+            count: None,
+            visible: true,
+            enabled: true,
+            role: None,
+            accelerator: None,
+            confirm: None,
+            kind,
+        }
+    }
+
+    /// Sets the stable identifier.
+    pub fn id(mut self, id: impl Into<SharedString>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets the count suffix.
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Sets whether the item is shown.
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Sets whether the item can be selected.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Sets the standard meaning of the item.
+    pub fn role(mut self, role: MenuRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    /// Sets the keyboard shortcut shown next to the label.
+    ///
+    /// Keys are joined with `+`, modifiers first, e.g. "Ctrl+Shift+Q". This
+    /// only displays the shortcut; registering it as a hotkey is up to the
+    /// app.
+    pub fn accelerator(mut self, accelerator: impl Into<SharedString>) -> Self {
+        self.accelerator = Some(accelerator.into());
+        self
+    }
+
+    /// Asks the user to confirm `message`, e.g. "Really quit while
+    /// syncing?", before the action runs.
+    pub fn confirm(mut self, message: impl Into<SharedString>) -> Self {
+        self.confirm = Some(message.into());
+        self
+    }
+
+    /// Returns what selecting the entry dispatches: [`MenuToggleEvent`] and
+    /// [`MenuSelectEvent`] for entries with an id, then the entry's action,
+    /// all wrapped in a [`ConfirmMenuSelection`] if the entry asks first.
+    ///
+    /// [`MenuToggleEvent`]: crate::MenuToggleEvent
+    /// [`MenuSelectEvent`]: crate::MenuSelectEvent
+    pub fn selection_actions(&self) -> Vec<Box<dyn Action>> {
+        let mut actions: Vec<Box<dyn Action>> = Vec::new();
+        if let Some(id) = self.id.clone() {
+            if let Some(checked) = self.kind.toggled() {
+                actions.push(Box::new(MenuToggleEvent {
+                    id: id.clone(),
+                    checked,
+                }));
+            }
+            actions.push(Box::new(MenuSelectEvent { id }));
+        }
+        if let Some(action) = self.kind.action() {
+            actions.push(action.boxed_clone());
+        }
+
+        match self.confirm.clone() {
+            Some(message) if !actions.is_empty() => {
+                vec![Box::new(ConfirmMenuSelection { message, actions })]
+            }
+            _ => actions,
+        }
+    }
+
+    /// Returns the text shown by the platform, including the count suffix.
+    pub fn display_label(&self) -> SharedString {
+        match self.count {
+            Some(count) if count > 0 => format!("{} ({count})", self.label).into(),
+            _ => self.label.clone(),
+        }
+    }
+}
+
+impl MenuItemKind {
+    /// Returns the action dispatched when the item is selected.
+    pub fn action(&self) -> Option<&dyn Action> {
+        match self {
+            Self::Action(action) | Self::Checkbox { action, .. } | Self::Radio { action, .. } => {
+                Some(action.as_ref())
+            }
+            Self::Separator | Self::Submenu(_) => None,
+        }
+    }
+
+    /// Returns the check state of checkbox and radio entries.
+    pub fn checked(&self) -> Option<bool> {
+        match self {
+            Self::Checkbox { checked, .. } | Self::Radio { checked, .. } => Some(*checked),
+            _ => None,
+        }
+    }
+
+    /// Returns the check state a checkbox or radio entry has once selected.
+    pub fn toggled(&self) -> Option<bool> {
+        match self {
+            Self::Checkbox { checked, .. } => Some(!checked),
+            Self::Radio { .. } => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl Clone for TrayMenuItem {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            label: self.label.clone(),
+            count: self.count,
+            visible: self.visible,
+            enabled: self.enabled,
+            role: self.role,
+            accelerator: self.accelerator.clone(),
+            confirm: self.confirm.clone(),
+            kind: self.kind.clone(),
+        }
+    }
+}
+
+impl Clone for ConfirmMenuSelection {
+    fn clone(&self) -> Self {
+        Self {
+            message: self.message.clone(),
+            actions: self
+                .actions
+                .iter()
+                .map(|action| action.boxed_clone())
+                .collect(),
+        }
+    }
+}
+
+impl PartialEq for ConfirmMenuSelection {
+    fn eq(&self, other: &Self) -> bool {
+        self.message == other.message
+            && self.actions.len() == other.actions.len()
+            && self
+                .actions
+                .iter()
+                .zip(&other.actions)
+                .all(|(a, b)| a.partial_eq(b.as_ref()))
+    }
+}
+
+impl fmt::Debug for ConfirmMenuSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.actions.iter().map(|action| action.name()).collect();
+        f.debug_struct("ConfirmMenuSelection")
+            .field("message", &self.message)
+            .field("actions", &names)
+            .finish()
+    }
+}
+
+impl Clone for MenuItemKind {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Separator => Self::Separator,
+            Self::Action(action) => Self::Action(action.boxed_clone()),
+            Self::Checkbox { checked, action } => Self::Checkbox {
+                checked: *checked,
+                action: action.boxed_clone(),
+            },
+            Self::Radio { checked, action } => Self::Radio {
+                checked: *checked,
+                action: action.boxed_clone(),
+            },
+            Self::Submenu(items) => Self::Submenu(items.clone()),
+        }
+    }
+}
+
+impl fmt::Debug for TrayMenuItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrayMenuItem")
+            .field("id", &self.id)
+            .field("label", &self.label)
+            .field("count", &self.count)
+            .field("visible", &self.visible)
+            .field("enabled", &self.enabled)
+            .field("role", &self.role)
+            .field("accelerator", &self.accelerator)
+            .field("confirm", &self.confirm)
+            .field("kind", &self.kind)
+            .finish()
+    }
+}
+
+impl fmt::Debug for MenuItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Separator => f.write_str("Separator"),
+            Self::Action(action) => f.debug_tuple("Action").field(&action.name()).finish(),
+            Self::Checkbox { checked, action } => f
+                .debug_struct("Checkbox")
+                .field("checked", checked)
+                .field("action", &action.name())
+                .finish(),
+            Self::Radio { checked, action } => f
+                .debug_struct("Radio")
+                .field("checked", checked)
+                .field("action", &action.name())
+                .finish(),
+            Self::Submenu(items) => f.debug_tuple("Submenu").field(items).finish(),
+        }
+    }
+}
+
+/// A menu described by an enum with one unit variant per entry.
+///
+/// Usually derived with `#[derive(TrayMenu)]` from the `derive` feature of
+/// `gpui-tray`. Every entry dispatches its own variant as the action and
+/// carries a stable id, so the same variant can be recovered from a
+/// [`MenuSelectEvent`](crate::MenuSelectEvent).
+pub trait TrayMenu: Action + Sized {
+    /// Builds the menu, one entry per variant in declaration order.
+    fn menu_items() -> Vec<TrayMenuItem>;
+
+    /// Returns the id of this variant's entry.
+    fn menu_id(&self) -> &'static str;
+
+    /// Returns the variant whose entry has `id`.
+    fn from_menu_id(id: &str) -> Option<Self>;
+}
+
+/// Conversion of a menu builder's return value into tray menu items.
+pub trait IntoMenuItems {
+    fn into_menu_items(self) -> Vec<TrayMenuItem>;
+}
+
+impl IntoMenuItems for Vec<TrayMenuItem> {
+    fn into_menu_items(self) -> Vec<TrayMenuItem> {
+        self
+    }
+}
+
+impl IntoMenuItems for Vec<MenuItem> {
+    fn into_menu_items(self) -> Vec<TrayMenuItem> {
+        self.into_iter().filter_map(from_gpui_item).collect()
+    }
+}
+
+/// System menus (such as macOS Services) have no tray equivalent and are dropped.
+fn from_gpui_item(item: MenuItem) -> Option<TrayMenuItem> {
+    match item {
+        MenuItem::Separator => Some(TrayMenuItem::separator()),
+        MenuItem::Action { name, action, .. } => {
+            Some(TrayMenuItem::new(name, MenuItemKind::Action(action)))
+        }
+        MenuItem::Submenu(menu) => Some(TrayMenuItem::submenu(
+            menu.name,
+            menu.items.into_menu_items(),
+        )),
+        MenuItem::SystemMenu(_) => None,
+    }
+}
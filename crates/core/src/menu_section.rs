@@ -0,0 +1,77 @@
+use crate::{MenuItemKind, TrayMenuItem};
+use std::cmp::Ordering;
+
+/// A run of menu entries whose order is worked out each time the menu is
+/// built, e.g. a list of devices or servers.
+///
+/// Menu builders run whenever the platform needs the menu, so a section
+/// built inside one is re-sorted from the app's current data every time:
+///
+/// ```rust,ignore
+/// let tray = Tray::new().menu(move || {
+///     let servers = MenuSection::new(servers.borrow().iter().map(|server| {
+///         TrayMenuItem::action(server.name.clone(), Connect(server.id))
+///             .id(format!("server-{}", server.id))
+///     }))
+///     .sorted_by(|a, b| a.label.cmp(&b.label));
+///
+///     let mut items: Vec<TrayMenuItem> = servers.into();
+///     items.push(TrayMenuItem::separator());
+///     items.push(TrayMenuItem::action("Quit", Quit));
+///     items
+/// });
+/// ```
+///
+/// Give the entries ids: checked, visible and count overrides are looked
+/// up by id, so they follow an entry to wherever the sort puts it. While a
+/// menu is open, backends keep showing the order it opened with and pick
+/// up the new one once it closes.
+pub struct MenuSection {
+    items: Vec<TrayMenuItem>,
+}
+
+impl MenuSection {
+    /// Creates a section of `items` in the given order.
+    pub fn new(items: impl IntoIterator<Item = TrayMenuItem>) -> Self {
+        Self {
+            items: items.into_iter().collect(),
+        }
+    }
+
+    /// Orders the entries with `compare`; equal entries keep their order.
+    ///
+    /// Separators stay where they are and the entries between two of them
+    /// are sorted on their own, so groups inside the section are kept.
+    pub fn sorted_by(
+        mut self,
+        mut compare: impl FnMut(&TrayMenuItem, &TrayMenuItem) -> Ordering,
+    ) -> Self {
+        for group in self
+            .items
+            .split_mut(|item| matches!(item.kind, MenuItemKind::Separator))
+        {
+            group.sort_by(&mut compare);
+        }
+        self
+    }
+
+    /// Returns the entries in their final order.
+    pub fn into_items(self) -> Vec<TrayMenuItem> {
+        self.items
+    }
+}
+
+impl From<MenuSection> for Vec<TrayMenuItem> {
+    fn from(section: MenuSection) -> Self {
+        section.into_items()
+    }
+}
+
+impl IntoIterator for MenuSection {
+    type Item = TrayMenuItem;
+    type IntoIter = std::vec::IntoIter<TrayMenuItem>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
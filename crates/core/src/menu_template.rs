@@ -0,0 +1,90 @@
+use crate::{MenuRole, TrayMenuItem};
+use gpui::{Action, SharedString};
+
+/// Shows the app's about window. Dispatched by [`MenuTemplate::standard_app`].
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct ShowAbout;
+
+/// Starts an update check. Dispatched by [`MenuTemplate::standard_app`].
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct CheckForUpdates;
+
+/// Shows the app's settings. Dispatched by [`MenuTemplate::standard_app`].
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct ShowPreferences;
+
+/// Quits the app. Dispatched by [`MenuTemplate::standard_app`].
+///
+/// The tray runtime quits the app itself when no handler is registered.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct QuitApp;
+
+/// Ready-made groups of menu entries to splice into a tray menu.
+pub struct MenuTemplate;
+
+impl MenuTemplate {
+    /// About, Check for Updates, Preferences and Quit, labeled and ordered
+    /// the way the current platform does it.
+    ///
+    /// The entries have the ids `about`, `check_for_updates`, `preferences`
+    /// and `quit`, so they can be hidden or disabled like any other entry,
+    /// and dispatch [`ShowAbout`], [`CheckForUpdates`], [`ShowPreferences`]
+    /// and [`QuitApp`].
+    ///
+    /// ```rust,ignore
+    /// let tray = Tray::new().menu(|| {
+    ///     let mut items = vec![TrayMenuItem::action("Open", Open), TrayMenuItem::separator()];
+    ///     items.extend(MenuTemplate::standard_app("Notes"));
+    ///     items
+    /// });
+    /// ```
+    pub fn standard_app(app_name: impl Into<SharedString>) -> Vec<TrayMenuItem> {
+        let app_name = app_name.into();
+        let about = TrayMenuItem::action(format!("About {app_name}"), ShowAbout)
+            .id("about")
+            .role(MenuRole::About);
+
+        if cfg!(target_os = "macos") {
+            vec![
+                about,
+                TrayMenuItem::action("Check for Updates…", CheckForUpdates).id("check_for_updates"),
+                TrayMenuItem::separator(),
+                TrayMenuItem::action("Settings…", ShowPreferences)
+                    .id("preferences")
+                    .role(MenuRole::Preferences),
+                TrayMenuItem::separator(),
+                TrayMenuItem::action(format!("Quit {app_name}"), QuitApp)
+                    .id("quit")
+                    .role(MenuRole::Quit),
+            ]
+        } else if cfg!(target_os = "windows") {
+            vec![
+                TrayMenuItem::action("Settings", ShowPreferences)
+                    .id("preferences")
+                    .role(MenuRole::Preferences),
+                TrayMenuItem::action("Check for updates", CheckForUpdates).id("check_for_updates"),
+                about,
+                TrayMenuItem::separator(),
+                TrayMenuItem::action("Exit", QuitApp)
+                    .id("quit")
+                    .role(MenuRole::Quit),
+            ]
+        } else {
+            vec![
+                TrayMenuItem::action("Preferences", ShowPreferences)
+                    .id("preferences")
+                    .role(MenuRole::Preferences),
+                TrayMenuItem::action("Check for Updates", CheckForUpdates).id("check_for_updates"),
+                about,
+                TrayMenuItem::separator(),
+                TrayMenuItem::action("Quit", QuitApp)
+                    .id("quit")
+                    .role(MenuRole::Quit),
+            ]
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use gpui::{Image, SharedString};
+
+/// A notification shown from the tray icon.
+///
+/// ```rust,ignore
+/// cx.tray_notify(
+///     Notification::new("Upload complete", "3 files were uploaded")
+///         .options(NotificationOptions::new().icon(NotificationIcon::Info)),
+/// )?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct Notification {
+    /// Bold first line.
+    pub title: SharedString,
+    /// Message text.
+    pub body: SharedString,
+    /// Presentation options.
+    pub options: NotificationOptions,
+}
+
+impl Notification {
+    /// Creates a notification with default options.
+    pub fn new(title: impl Into<SharedString>, body: impl Into<SharedString>) -> Self {
+        Self {
+            title: title.into(),
+            body: body.into(),
+            options: NotificationOptions::default(),
+        }
+    }
+
+    /// Sets the presentation options.
+    pub fn options(mut self, options: NotificationOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+/// Severity icon shown next to a tray notification.
+#[derive(Clone, Debug, Default)]
+pub enum NotificationIcon {
+    /// No icon.
+    #[default]
+    None,
+    Info,
+    Warning,
+    Error,
+    /// An app-provided image.
+    Custom(Image),
+}
+
+/// How urgent a notification is (freedesktop `urgency` hint).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum NotificationUrgency {
+    Low,
+    #[default]
+    Normal,
+    /// Stays on screen until dismissed on most notification servers.
+    Critical,
+}
+
+/// Presentation options for a tray notification.
+///
+/// Options a platform has no equivalent for are ignored there.
+#[derive(Clone, Debug, Default)]
+pub struct NotificationOptions {
+    /// Severity icon (Windows `NIIF_INFO`/`NIIF_WARNING`/`NIIF_ERROR`/`NIIF_USER`).
+    pub icon: NotificationIcon,
+    /// Suppresses the notification sound (Windows `NIIF_NOSOUND`, freedesktop
+    /// `suppress-sound` hint).
+    pub silent: bool,
+    /// Drops the notification instead of queueing it when it cannot be shown
+    /// right away, e.g. in presentation mode (Windows `NIF_REALTIME`).
+    pub realtime: bool,
+    /// Urgency level (freedesktop `urgency` hint).
+    pub urgency: NotificationUrgency,
+    /// Notification category such as `"im.received"` (freedesktop `category` hint).
+    pub category: Option<SharedString>,
+    /// Keeps the notification around after it was activated (freedesktop `resident` hint).
+    pub resident: bool,
+    /// Skips the notification history (freedesktop `transient` hint).
+    pub transient: bool,
+    /// Themed sound to play, e.g. `"message-new-instant"` (freedesktop `sound-name` hint).
+    pub sound_name: Option<SharedString>,
+}
+
+impl NotificationOptions {
+    /// Creates options with no icon, sound enabled and queueing allowed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the severity icon.
+    pub fn icon(mut self, icon: NotificationIcon) -> Self {
+        self.icon = icon;
+        self
+    }
+
+    /// Sets whether the notification plays a sound.
+    pub fn silent(mut self, silent: bool) -> Self {
+        self.silent = silent;
+        self
+    }
+
+    /// Sets whether the notification is dropped rather than queued.
+    pub fn realtime(mut self, realtime: bool) -> Self {
+        self.realtime = realtime;
+        self
+    }
+
+    /// Sets the urgency level.
+    pub fn urgency(mut self, urgency: NotificationUrgency) -> Self {
+        self.urgency = urgency;
+        self
+    }
+
+    /// Sets the notification category.
+    pub fn category(mut self, category: impl Into<SharedString>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    /// Sets whether the notification stays after being activated.
+    pub fn resident(mut self, resident: bool) -> Self {
+        self.resident = resident;
+        self
+    }
+
+    /// Sets whether the notification skips the history.
+    pub fn transient(mut self, transient: bool) -> Self {
+        self.transient = transient;
+        self
+    }
+
+    /// Sets the themed sound to play.
+    pub fn sound_name(mut self, sound_name: impl Into<SharedString>) -> Self {
+        self.sound_name = Some(sound_name.into());
+        self
+    }
+}
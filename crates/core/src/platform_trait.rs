@@ -1,4 +1,5 @@
-use crate::{Result, RuntimeEvent, Tray};
+use crate::{Error, EventQueuePolicy, EventQueueStats, Notification, Result, RuntimeEvent, Tray};
+use gpui::Image;
 
 /// Platform-specific tray backend.
 ///
@@ -8,12 +9,40 @@ pub trait PlatformTray: Send + Sync {
     /// Applies the latest tray snapshot.
     fn set_tray(&self, tray: Tray) -> Result<()>;
 
+    /// Replaces only the icon of the tray set last, such as with the next
+    /// frame of an animation, leaving its menu and everything else as is.
+    ///
+    /// Fails with [`Error::UnsupportedPlatform`] by default, in which case the
+    /// caller sets the whole tray again instead.
+    fn set_icon(&self, icon: Option<Image>) -> Result<()> {
+        let _ = icon;
+        Err(Error::UnsupportedPlatform)
+    }
+
     /// Removes the tray icon.
     fn remove_tray(&self) -> Result<()>;
 
+    /// Opens the context menu at the pointer.
+    ///
+    /// Fails with [`Error::UnsupportedPlatform`](crate::Error::UnsupportedPlatform)
+    /// where only the host may open the menu.
+    fn show_menu(&self) -> Result<()>;
+
+    /// Shows a notification from the tray icon.
+    fn notify(&self, notification: Notification) -> Result<()>;
+
+    /// Blocks until every previously queued update has been applied to the OS.
+    fn flush(&self) -> Result<()>;
+
     /// Attempts to receive one runtime event from the backend.
     fn try_recv_event(&self) -> Result<Option<RuntimeEvent>>;
 
+    /// Replaces the policy bounding the backend event queue.
+    fn set_event_policy(&self, policy: EventQueuePolicy);
+
+    /// Returns the backend event queue counters.
+    fn event_stats(&self) -> EventQueueStats;
+
     /// Requests graceful shutdown of the backend runtime.
     fn shutdown(&self) -> Result<()>;
 }
@@ -0,0 +1,297 @@
+use crate::{Error, Result, RuntimeEvent, ScrollEvent};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// What to do with a new event when the queue is full.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued action to make room, keeping the most recent input.
+    #[default]
+    DropOldest,
+    /// Drop the incoming event.
+    DropNewest,
+}
+
+/// Bounds how many backend events may wait for the app to consume them.
+///
+/// Queued errors are never dropped by [`OverflowPolicy::DropOldest`]; only
+/// actions such as clicks make room for newer events, and once the queue
+/// holds nothing but errors the incoming event is dropped instead.
+/// Consecutive scroll events are merged before the limit applies.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EventQueuePolicy {
+    /// Maximum number of queued events.
+    pub capacity: usize,
+    /// Behavior once `capacity` is reached.
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for EventQueuePolicy {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            overflow: OverflowPolicy::DropOldest,
+        }
+    }
+}
+
+/// Counters describing the backend event queue.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct EventQueueStats {
+    /// Events accepted from the backend.
+    pub pushed: u64,
+    /// Events discarded because the queue was full.
+    pub dropped: u64,
+    /// Events currently waiting to be dispatched.
+    pub queued: usize,
+}
+
+struct QueueState {
+    events: VecDeque<RuntimeEvent>,
+    policy: EventQueuePolicy,
+    pushed: u64,
+    dropped: u64,
+}
+
+struct Shared {
+    state: Mutex<QueueState>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Creates a bounded queue carrying events from a backend to the app.
+#[doc(hidden)]
+pub fn event_queue(policy: EventQueuePolicy) -> (EventSender, EventReceiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(QueueState {
+            events: VecDeque::new(),
+            policy,
+            pushed: 0,
+            dropped: 0,
+        }),
+    });
+    (
+        EventSender {
+            shared: shared.clone(),
+        },
+        EventReceiver { shared },
+    )
+}
+
+/// Backend side of the event queue.
+#[doc(hidden)]
+#[derive(Clone)]
+pub struct EventSender {
+    shared: Arc<Shared>,
+}
+
+impl EventSender {
+    /// Queues `event`, applying the overflow policy if the queue is full.
+    pub fn send(&self, event: RuntimeEvent) {
+        let mut state = self.shared.lock();
+        state.pushed += 1;
+
+        if let Some(scroll) = as_scroll(&event)
+            && let Some(RuntimeEvent::Action(last)) = state.events.back_mut()
+            && let Some(last) = (last.as_mut() as &mut dyn Any).downcast_mut::<ScrollEvent>()
+            && last.coalesce(scroll)
+        {
+            return;
+        }
+
+        if state.events.len() >= state.policy.capacity.max(1) {
+            state.dropped += 1;
+            match state.policy.overflow {
+                OverflowPolicy::DropNewest => return,
+                OverflowPolicy::DropOldest => {
+                    let oldest_action = state
+                        .events
+                        .iter()
+                        .position(|event| !matches!(event, RuntimeEvent::Error(_)));
+                    match oldest_action {
+                        Some(index) => {
+                            state.events.remove(index);
+                        }
+                        None => return,
+                    }
+                }
+            }
+        }
+
+        state.events.push_back(event);
+    }
+}
+
+fn as_scroll(event: &RuntimeEvent) -> Option<&ScrollEvent> {
+    match event {
+        RuntimeEvent::Action(action) => action.as_any().downcast_ref(),
+        RuntimeEvent::MenuSelection(_) | RuntimeEvent::Error(_) => None,
+    }
+}
+
+/// App side of the event queue.
+#[doc(hidden)]
+pub struct EventReceiver {
+    shared: Arc<Shared>,
+}
+
+impl EventReceiver {
+    /// Takes the next event, failing with [`Error::RuntimeClosed`] once every
+    /// sender is gone and the queue is drained.
+    pub fn try_recv(&self) -> Result<Option<RuntimeEvent>> {
+        if let Some(event) = self.shared.lock().events.pop_front() {
+            return Ok(Some(event));
+        }
+
+        // A sender may have queued one last event before going away.
+        if Arc::strong_count(&self.shared) == 1 {
+            return match self.shared.lock().events.pop_front() {
+                Some(event) => Ok(Some(event)),
+                None => Err(Error::RuntimeClosed),
+            };
+        }
+        Ok(None)
+    }
+
+    /// Replaces the queue policy; events beyond a smaller capacity are kept.
+    pub fn set_policy(&self, policy: EventQueuePolicy) {
+        self.shared.lock().policy = policy;
+    }
+
+    /// Returns the current counters.
+    pub fn stats(&self) -> EventQueueStats {
+        let state = self.shared.lock();
+        EventQueueStats {
+            pushed: state.pushed,
+            dropped: state.dropped,
+            queued: state.events.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventQueuePolicy, EventReceiver, EventSender, OverflowPolicy, event_queue};
+    use crate::{
+        ClickEvent, Error, MenuOpenedEvent, RuntimeEvent, ScrollDelta, ScrollEvent,
+        ScrollOrientation,
+    };
+    use gpui::{MouseButton, Point};
+
+    fn queue(capacity: usize, overflow: OverflowPolicy) -> (EventSender, EventReceiver) {
+        event_queue(EventQueuePolicy { capacity, overflow })
+    }
+
+    fn click(button: MouseButton) -> RuntimeEvent {
+        RuntimeEvent::Action(Box::new(ClickEvent {
+            button,
+            position: Point::default(),
+            activation_token: None,
+        }))
+    }
+
+    fn scroll(lines: f32, orientation: ScrollOrientation) -> RuntimeEvent {
+        RuntimeEvent::Action(Box::new(ScrollEvent {
+            delta: ScrollDelta {
+                lines,
+                precise: false,
+                orientation,
+            },
+        }))
+    }
+
+    /// Describes the queued events in order, draining the queue.
+    fn drain(rx: &EventReceiver) -> Vec<String> {
+        std::iter::from_fn(|| rx.try_recv().unwrap())
+            .map(|event| match event {
+                RuntimeEvent::Action(action) => {
+                    if let Some(click) = action.as_any().downcast_ref::<ClickEvent>() {
+                        format!("click {:?}", click.button)
+                    } else if let Some(scroll) = action.as_any().downcast_ref::<ScrollEvent>() {
+                        format!("scroll {}", scroll.delta.lines)
+                    } else if action.as_any().is::<MenuOpenedEvent>() {
+                        "menu opened".to_string()
+                    } else {
+                        action.name().to_string()
+                    }
+                }
+                RuntimeEvent::MenuSelection(actions) => format!("selection of {}", actions.len()),
+                RuntimeEvent::Error(err) => format!("error {err}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drop_oldest_keeps_the_latest_events() {
+        let (tx, rx) = queue(2, OverflowPolicy::DropOldest);
+        tx.send(click(MouseButton::Left));
+        tx.send(click(MouseButton::Right));
+        tx.send(click(MouseButton::Middle));
+        assert_eq!(drain(&rx), ["click Right", "click Middle"]);
+    }
+
+    #[test]
+    fn drop_newest_keeps_the_earliest_events() {
+        let (tx, rx) = queue(2, OverflowPolicy::DropNewest);
+        tx.send(click(MouseButton::Left));
+        tx.send(click(MouseButton::Right));
+        tx.send(click(MouseButton::Middle));
+        assert_eq!(drain(&rx), ["click Left", "click Right"]);
+    }
+
+    #[test]
+    fn drop_oldest_keeps_queued_errors() {
+        let (tx, rx) = queue(2, OverflowPolicy::DropOldest);
+        tx.send(RuntimeEvent::Error(Error::NotFound));
+        tx.send(click(MouseButton::Left));
+        tx.send(click(MouseButton::Right));
+        assert_eq!(drain(&rx), ["error Tray not found", "click Right"]);
+
+        // With only errors queued, the newcomer makes way.
+        tx.send(RuntimeEvent::Error(Error::NotFound));
+        tx.send(RuntimeEvent::Error(Error::MenuBuildFailed));
+        tx.send(click(MouseButton::Left));
+        tx.send(RuntimeEvent::Error(Error::RuntimeClosed));
+        assert_eq!(
+            drain(&rx),
+            [
+                "error Tray not found",
+                "error Failed to build the tray menu"
+            ]
+        );
+    }
+
+    #[test]
+    fn consecutive_scrolls_merge() {
+        let (tx, rx) = queue(8, OverflowPolicy::DropOldest);
+        tx.send(scroll(1.0, ScrollOrientation::Vertical));
+        tx.send(scroll(2.0, ScrollOrientation::Vertical));
+        tx.send(scroll(1.0, ScrollOrientation::Horizontal));
+        tx.send(RuntimeEvent::Action(Box::new(MenuOpenedEvent)));
+        tx.send(scroll(-1.0, ScrollOrientation::Horizontal));
+        assert_eq!(
+            drain(&rx),
+            ["scroll 3", "scroll 1", "menu opened", "scroll -1"]
+        );
+    }
+
+    #[test]
+    fn stats_count_pushed_and_dropped_events() {
+        let (tx, rx) = queue(1, OverflowPolicy::DropNewest);
+        tx.send(click(MouseButton::Left));
+        tx.send(click(MouseButton::Right));
+        tx.send(click(MouseButton::Middle));
+        let stats = rx.stats();
+        assert_eq!((stats.pushed, stats.dropped, stats.queued), (3, 2, 1));
+
+        rx.try_recv().unwrap();
+        assert_eq!(rx.stats().queued, 0);
+    }
+}
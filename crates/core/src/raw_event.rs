@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+/// Callback type for [`Tray::on_raw_platform_event`](crate::Tray::on_raw_platform_event).
+pub type RawEventHandler = Arc<dyn Fn(&RawPlatformEvent) + Send + Sync>;
+
+/// A message from the platform as the backend received it, before it is
+/// turned into tray events.
+///
+/// Which variants exist depends on the target, so matching on them needs
+/// the same `#[cfg]`. The legacy X11 icon on Linux reports nothing, and
+/// macOS has no variant until it gets a backend.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum RawPlatformEvent {
+    /// A message sent to the tray's hidden window, such as the icon's
+    /// `WM_TRAYICON` callback, which carries the mouse or notification
+    /// message in the low word of `lparam`.
+    #[cfg(target_os = "windows")]
+    WindowMessage {
+        msg: u32,
+        wparam: usize,
+        lparam: isize,
+    },
+    /// A method call from the host to the StatusNotifierItem or its
+    /// `com.canonical.dbusmenu` menu, e.g. `Activate` with `(812, 4)`.
+    #[cfg(target_os = "linux")]
+    DBusCall {
+        interface: &'static str,
+        member: &'static str,
+        /// The decoded arguments, formatted with `Debug`.
+        args: String,
+    },
+}
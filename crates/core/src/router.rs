@@ -0,0 +1,95 @@
+use crate::{
+    ClickEvent, DoubleClickEvent, MenuSelectEvent, NotificationClickedEvent, ScrollEvent,
+    ScrollOrientation,
+};
+use gpui::{Action, MouseButton, SharedString};
+
+/// A tray event a [`TrayActionRouter`] reacts to.
+#[derive(Clone, PartialEq, Debug)]
+pub enum TrayRoute {
+    /// A click with the given button.
+    Click(MouseButton),
+    /// A double-click with the given button.
+    DoubleClick(MouseButton),
+    /// Selection of the menu entry with the given id.
+    MenuSelect(SharedString),
+    /// Scrolling up over the icon.
+    ScrollUp,
+    /// Scrolling down over the icon.
+    ScrollDown,
+    /// A click on a tray notification.
+    NotificationClicked,
+}
+
+impl TrayRoute {
+    /// Whether the tray event `action` is the one this route describes.
+    pub fn matches(&self, action: &dyn Action) -> bool {
+        let any = action.as_any();
+        match self {
+            Self::Click(button) => any
+                .downcast_ref::<ClickEvent>()
+                .is_some_and(|click| click.button == *button),
+            Self::DoubleClick(button) => any
+                .downcast_ref::<DoubleClickEvent>()
+                .is_some_and(|click| click.button == *button),
+            Self::MenuSelect(id) => any
+                .downcast_ref::<MenuSelectEvent>()
+                .is_some_and(|select| select.id == *id),
+            Self::ScrollUp | Self::ScrollDown => {
+                any.downcast_ref::<ScrollEvent>().is_some_and(|scroll| {
+                    let delta = scroll.delta;
+                    delta.orientation == ScrollOrientation::Vertical
+                        && if *self == Self::ScrollUp {
+                            delta.lines < 0.0
+                        } else {
+                            delta.lines > 0.0
+                        }
+                })
+            }
+            Self::NotificationClicked => any.is::<NotificationClickedEvent>(),
+        }
+    }
+}
+
+/// Maps tray events to app actions, in place of a handler matching on them.
+///
+/// Install one with `TrayAppContext::set_tray_router`. A tray event with a
+/// matching route is replaced by the routed actions, which are dispatched in
+/// the order the routes were added; events without a route reach the app as
+/// before.
+///
+/// ```rust,ignore
+/// cx.set_tray_router(
+///     TrayActionRouter::new()
+///         .route(TrayRoute::Click(MouseButton::Left), ShowWindow)
+///         .route(TrayRoute::MenuSelect("quit".into()), Quit)
+///         .route(TrayRoute::ScrollUp, VolumeUp),
+/// );
+/// ```
+#[derive(Default)]
+pub struct TrayActionRouter {
+    routes: Vec<(TrayRoute, Box<dyn Action>)>,
+}
+
+impl TrayActionRouter {
+    /// Creates a router without routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dispatches `action` whenever `route` matches.
+    pub fn route(mut self, route: TrayRoute, action: impl Action) -> Self {
+        self.routes.push((route, Box::new(action)));
+        self
+    }
+
+    /// Returns the actions routed from the tray event `action`, empty when
+    /// no route matches.
+    pub fn resolve(&self, action: &dyn Action) -> Vec<Box<dyn Action>> {
+        self.routes
+            .iter()
+            .filter(|(route, _)| route.matches(action))
+            .map(|(_, routed)| routed.boxed_clone())
+            .collect()
+    }
+}
@@ -0,0 +1,71 @@
+use crate::Result;
+use gpui::SharedString;
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Keeps the check state of checkbox and radio entries across restarts.
+///
+/// Install one with `TrayAppContext::set_tray_storage` before the first
+/// `set_tray`. Saved states are applied to the menu before it is shown, and
+/// every check state change of an entry with an id is saved again.
+pub trait TrayStorage: Send + Sync {
+    /// Returns the saved check states keyed by menu entry id.
+    fn load(&self) -> Result<HashMap<SharedString, bool>>;
+
+    /// Replaces the saved check states.
+    fn save(&self, checked: &HashMap<SharedString, bool>) -> Result<()>;
+}
+
+/// Stores check states in a text file, one `id=true` or `id=false` line per
+/// entry.
+#[derive(Clone, Debug)]
+pub struct FileTrayStorage {
+    path: PathBuf,
+}
+
+impl FileTrayStorage {
+    /// Stores into `path`, creating it and its parent directories on the
+    /// first save.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl TrayStorage for FileTrayStorage {
+    fn load(&self) -> Result<HashMap<SharedString, bool>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(text
+            .lines()
+            .filter_map(|line| {
+                let (id, checked) = line.rsplit_once('=')?;
+                let checked = checked.trim().parse().ok()?;
+                Some((SharedString::from(id.to_string()), checked))
+            })
+            .collect())
+    }
+
+    fn save(&self, checked: &HashMap<SharedString, bool>) -> Result<()> {
+        let mut entries: Vec<_> = checked
+            .iter()
+            .filter(|(id, _)| !id.contains('\n'))
+            .map(|(id, checked)| format!("{id}={checked}\n"))
+            .collect();
+        entries.sort();
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write next to the file and rename, so a crash never leaves half of it.
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, entries.concat())?;
+        fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use std::borrow::Cow;
+
+/// Longest tooltip Windows shows, in UTF-16 code units. `NOTIFYICONDATAW`
+/// holds 128 including the terminating NUL.
+pub const WINDOWS_TOOLTIP_MAX_LEN: usize = 127;
+
+/// What happens to a tooltip longer than the platform can show, set with
+/// [`Tray::tooltip_overflow`](crate::Tray::tooltip_overflow).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TooltipOverflow {
+    /// Cut the text to fit, ending it with an ellipsis.
+    #[default]
+    Truncate,
+    /// Reject the tray with [`Error::TooltipTooLong`](crate::Error::TooltipTooLong).
+    Error,
+}
+
+/// Cuts `text` to at most `max` UTF-16 code units, replacing the end with
+/// "…" when anything is cut. Characters are never split, so a surrogate
+/// pair is kept or dropped as a whole.
+pub fn truncate_utf16(text: &str, max: usize) -> Cow<'_, str> {
+    if text.encode_utf16().count() <= max {
+        return Cow::Borrowed(text);
+    }
+    if max == 0 {
+        return Cow::Borrowed("");
+    }
+
+    // The ellipsis takes one unit itself.
+    let mut len = 0;
+    let end = text
+        .char_indices()
+        .find(|(_, ch)| {
+            len += ch.len_utf16();
+            len > max - 1
+        })
+        .map_or(text.len(), |(index, _)| index);
+    Cow::Owned(format!("{}…", &text[..end]))
+}
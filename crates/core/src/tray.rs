@@ -1,15 +1,277 @@
+use crate::{
+    AnimatedIcon, ClickHandler, IntoClickHandler, IntoMenuItems, LocalizedText, Localizer,
+    MenuItemKind, RawEventHandler, RawPlatformEvent, TooltipOverflow, TrayMenuItem,
+};
 use gpui::*;
+use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Builder function type for constructing context menus.
-pub type MenuBuilder = Arc<dyn Fn() -> Vec<MenuItem> + Send + Sync>;
+pub type MenuBuilder = Arc<dyn Fn() -> Vec<TrayMenuItem> + Send + Sync>;
+
+/// Starting configurations for common kinds of tray apps.
+///
+/// A preset only sets [`Tray`] fields, so any builder call made after
+/// [`Tray::preset`] overrides it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TrayPreset {
+    /// Always-visible icon reflecting some state, e.g. a sync or VPN
+    /// indicator. The menu is the app's main interface, so either button
+    /// opens it.
+    StatusMonitor,
+    /// Always-visible icon with an unread count. A left click brings the
+    /// main window to the front, the right button opens the menu, and
+    /// bursts of count updates are coalesced.
+    Messenger,
+    /// Registered but hidden until the app has something to show; either
+    /// button then opens the menu.
+    BackgroundService,
+}
+
+/// How prominently the host shows the icon, as set by [`Tray::status`].
+///
+/// This is the StatusNotifierItem `Status`: hosts hide passive icons, for
+/// example in an overflow area, and highlight ones that ask for attention.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TrayStatus {
+    /// Hidden while staying registered, see [`Tray::passive`].
+    Passive,
+    /// Shown as usual.
+    #[default]
+    Active,
+    /// Shown and asking the user to look at it, see [`Tray::attention`].
+    Attention,
+}
+
+/// When `TrayAppContext::update_tray` changes reach the platform.
+///
+/// This coalesces whole updates, not only menu rebuilds: the icon,
+/// tooltip and status wait along with the menu.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MenuBuildPolicy {
+    /// Apply every update right away, rebuilding the menu each time.
+    #[default]
+    Immediate,
+    /// Collect the updates made until the current batch of app effects has
+    /// been handled (`App::defer`) and apply them as one, so a burst of
+    /// updates, e.g. from several notifications handled together, costs a
+    /// single backend update and menu rebuild.
+    ///
+    /// This is not an idle callback: the update still lands within the same
+    /// frame, only after the code that caused it has finished.
+    OnIdle,
+}
+
+/// What happens to menu changes that arrive while the tray menu is open.
+///
+/// Icon, tooltip and title changes always apply right away. Windows builds
+/// the menu as it opens and cannot change it while it is shown, so there
+/// both policies behave like `Defer`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum UpdateDuringMenuPolicy {
+    /// Keep the open menu as it is and apply the changes once it closes, so
+    /// it never shows a mix of old and new entries.
+    #[default]
+    Defer,
+    /// Update the open menu right away, where the host supports it (Linux).
+    Immediate,
+}
+
+/// What a left click does when the app has no [`ClickEvent`](crate::ClickEvent) handler.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FallbackActivation {
+    /// Activates the app and brings its first window to the front.
+    #[default]
+    FocusMainWindow,
+    /// Opens the context menu, where the platform allows it (Windows).
+    ShowMenu,
+    /// Does nothing.
+    None,
+}
+
+/// Which mouse button opens the context menu, set with [`Tray::menu_on`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuTrigger {
+    /// The given button.
+    Button(MouseButton),
+    /// The left or the right button.
+    Both,
+}
+
+impl Default for MenuTrigger {
+    fn default() -> Self {
+        Self::Button(MouseButton::Right)
+    }
+}
+
+impl From<MouseButton> for MenuTrigger {
+    fn from(button: MouseButton) -> Self {
+        Self::Button(button)
+    }
+}
+
+impl MenuTrigger {
+    /// Whether a click with `button` opens the menu.
+    pub fn opens_on(self, button: MouseButton) -> bool {
+        match self {
+            Self::Button(trigger) => trigger == button,
+            Self::Both => matches!(button, MouseButton::Left | MouseButton::Right),
+        }
+    }
+}
+
+/// Which tray protocol the Linux backend speaks. Ignored on other platforms.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LinuxTrayBackend {
+    /// StatusNotifierItem, falling back to XEmbed when no
+    /// StatusNotifierWatcher is running but an X11 system tray is.
+    #[default]
+    Auto,
+    /// StatusNotifierItem over D-Bus only.
+    Sni,
+    /// The legacy X11 system tray only. It has no menus or tooltips.
+    XEmbed,
+}
+
+/// `NOTIFYICONDATAW` settings the portable API does not model, set through
+/// [`WindowsTrayExt`]. Ignored on other platforms.
+#[derive(Clone, Debug)]
+pub struct WindowsTrayOptions {
+    /// Whether `NIF_SHOWTIP` is passed so the shell shows its standard
+    /// tooltip. Without it only [`TooltipShownEvent`](crate::TooltipShownEvent)
+    /// is sent, for apps that draw a tooltip of their own.
+    pub show_tip: bool,
+    /// `hBalloonIcon` of notifications that bring no icon of their own.
+    pub balloon_icon: Option<Image>,
+    /// Whether the icon is hidden with `NIS_HIDDEN`, even while asking for
+    /// attention.
+    pub hidden: bool,
+    /// GUID identifying the icon in place of the window, see
+    /// [`WindowsTrayExt::windows_guid`].
+    pub guid: Option<u128>,
+    /// Whether [`ScrollEvent`](crate::ScrollEvent)s are reported, see
+    /// [`WindowsTrayExt::windows_scroll_events`].
+    pub scroll_events: bool,
+}
+
+impl Default for WindowsTrayOptions {
+    fn default() -> Self {
+        Self {
+            show_tip: true,
+            balloon_icon: None,
+            hidden: false,
+            guid: None,
+            scroll_events: false,
+        }
+    }
+}
+
+/// Builder methods for the [`WindowsTrayOptions`] of a [`Tray`].
+pub trait WindowsTrayExt {
+    /// Sets whether the shell shows its standard tooltip.
+    fn windows_show_tip(self, show_tip: bool) -> Self;
+
+    /// Sets the icon of notifications that bring none.
+    fn windows_balloon_icon(self, icon: Image) -> Self;
+
+    /// Sets whether the icon is hidden with `NIS_HIDDEN`.
+    fn windows_hidden(self, hidden: bool) -> Self;
+
+    /// Identifies the icon by `guid` rather than by its window.
+    ///
+    /// The shell then keeps the icon's placement across runs, and an icon
+    /// left behind by a crashed run is removed before this one is added.
+    /// Windows ties the GUID to the executable's path, so use a different
+    /// one per install location. Linux needs no such cleanup: hosts drop an
+    /// item together with the bus connection of the process that crashed.
+    fn windows_guid(self, guid: u128) -> Self;
+
+    /// Sets whether scrolling over the icon sends
+    /// [`ScrollEvent`](crate::ScrollEvent)s.
+    ///
+    /// The shell does not forward the mouse wheel to tray icons, so this
+    /// installs a low-level mouse hook that sees every wheel event on the
+    /// desktop while the icon is shown. It is off by default; Linux always
+    /// reports scrolling.
+    fn windows_scroll_events(self, enabled: bool) -> Self;
+}
+
+impl WindowsTrayExt for Tray {
+    fn windows_show_tip(mut self, show_tip: bool) -> Self {
+        self.windows.show_tip = show_tip;
+        self
+    }
+
+    fn windows_balloon_icon(mut self, icon: Image) -> Self {
+        self.windows.balloon_icon = Some(icon);
+        self
+    }
+
+    fn windows_hidden(mut self, hidden: bool) -> Self {
+        self.windows.hidden = hidden;
+        self
+    }
+
+    fn windows_guid(mut self, guid: u128) -> Self {
+        self.windows.guid = Some(guid);
+        self
+    }
+
+    fn windows_scroll_events(mut self, enabled: bool) -> Self {
+        self.windows.scroll_events = enabled;
+        self
+    }
+}
+
+/// A temporary icon and tooltip, such as "Copied!", shown with
+/// `TrayAppContext::flash_tray`.
+///
+/// While a flash is shown, tray updates still apply underneath it and take
+/// effect once it ends.
+#[derive(Clone, Debug)]
+pub struct TrayFlash {
+    /// Icon shown instead of the tray's own icon or animation.
+    pub icon: Option<Image>,
+    /// Tooltip shown instead of the tray's own tooltip.
+    pub tooltip: Option<SharedString>,
+    /// How long the flash stays before the tray reverts.
+    pub duration: Duration,
+}
+
+impl TrayFlash {
+    /// Creates a flash lasting `duration` that changes nothing yet.
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            icon: None,
+            tooltip: None,
+            duration,
+        }
+    }
+
+    /// Sets the temporary icon.
+    pub fn icon(mut self, icon: Image) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Sets the temporary tooltip.
+    pub fn tooltip(mut self, tooltip: impl Into<SharedString>) -> Self {
+        self.tooltip = Some(tooltip.into());
+        self
+    }
+}
 
 /// Configuration for a system tray icon.
 ///
 /// Use the builder pattern to construct a tray configuration:
 ///
 /// ```rust
+/// # use gpui::{Image, ImageFormat, MenuItem};
+/// # use gpui_tray_core::Tray;
+/// # gpui::actions!(app, [Quit]);
+/// # let image = Image::from_bytes(ImageFormat::Png, Vec::new());
 /// let tray = Tray::new()
 ///     .tooltip("My Application")
 ///     .icon(image)
@@ -18,14 +280,91 @@ pub type MenuBuilder = Arc<dyn Fn() -> Vec<MenuItem> + Send + Sync>;
 pub struct Tray {
     /// Tooltip text displayed when hovering over the tray icon.
     pub tooltip: Option<SharedString>,
+    /// Longer text shown under the tooltip. Linux hosts set `tooltip` as its
+    /// heading; Windows puts it on the next line of a plain tooltip.
+    pub tooltip_body: Option<SharedString>,
+    /// What happens to a tooltip longer than the platform shows.
+    pub tooltip_overflow: TooltipOverflow,
     /// Title text for the tray item (platform-dependent).
     pub title: Option<SharedString>,
+    /// Name Linux panels list the item under, in place of `title`.
+    pub sni_title: Option<SharedString>,
+    /// Localized tooltip, resolved into `tooltip` whenever the tray is applied.
+    pub tooltip_text: Option<LocalizedText>,
+    /// Localized title, resolved into `title` whenever the tray is applied.
+    pub title_text: Option<LocalizedText>,
     /// Icon image displayed in the system tray.
     pub icon: Option<Image>,
+    /// Icon shown instead of `icon` while the desktop uses a dark theme.
+    pub dark_icon: Option<Image>,
+    /// Icon from the desktop's icon theme, shown instead of `icon` by Linux
+    /// StatusNotifierItem hosts that find it.
+    pub icon_name: Option<SharedString>,
+    /// Icon Linux StatusNotifierItem hosts show in place of `icon` while the
+    /// tray asks for [`attention`](Self::attention).
+    pub attention_icon: Option<Image>,
+    /// Themed icon hosts show in place of `attention_icon`, see
+    /// [`icon_name`](Self::icon_name).
+    pub attention_icon_name: Option<SharedString>,
+    /// Animation hosts may play while the tray asks for attention: an icon
+    /// name from the theme or the path of an animated image.
+    pub attention_movie_name: Option<SharedString>,
+    /// Looping animation shown instead of `icon` while set.
+    pub animation: Option<AnimatedIcon>,
+    /// Count drawn over the top-right corner of the icon, e.g. unread
+    /// messages. Zero is not shown; counts above 99 show as "99+".
+    pub badge: Option<u32>,
+    /// Whether the icon is a template image that the macOS menu bar tints to
+    /// match its light or dark appearance. Ignored on other platforms.
+    pub template: bool,
     /// Whether the tray icon is currently visible.
     pub visible: bool,
+    /// Whether the tray icon is hidden while staying registered with the host.
+    ///
+    /// Unlike `visible(false)`, which fully unregisters the icon, a passive
+    /// tray keeps its OS registration so showing it again is instant and the
+    /// host preserves its placement.
+    pub passive: bool,
+    /// Whether the tray asks the user to look at it.
+    ///
+    /// The icon is shown even while passive. Linux hosts also highlight it;
+    /// Windows has no such state, so the icon blinks there instead.
+    pub attention: bool,
     /// Optional menu builder for context menu.
     pub menu_builder: Option<MenuBuilder>,
+    /// Optional builder for the menu shown while the platform's modifier is held.
+    pub alt_menu_builder: Option<MenuBuilder>,
+    /// Runs on a left click instead of the click reaching the app.
+    pub left_click: Option<ClickHandler>,
+    /// Mouse button that opens the context menu.
+    pub menu_trigger: MenuTrigger,
+    /// Left-click behavior used when the app does not handle clicks itself.
+    pub fallback_activation: FallbackActivation,
+    /// Window that a left click hides instead of activating while it has
+    /// focus.
+    pub suppress_clicks_when_focused: Option<AnyWindowHandle>,
+    /// Whether updates, and with them menu rebuilds, are applied one by one
+    /// or coalesced.
+    pub menu_build_policy: MenuBuildPolicy,
+    /// What happens to menu changes while the menu is open.
+    pub update_during_menu: UpdateDuringMenuPolicy,
+    /// Tray protocol used on Linux.
+    pub linux_backend: LinuxTrayBackend,
+    /// X11 window exported as the StatusNotifierItem `WindowId`. The app's
+    /// first window is used when unset; ignored on Wayland.
+    pub linux_window_id: Option<u32>,
+    /// Advanced settings of the Windows notification area icon.
+    pub windows: WindowsTrayOptions,
+    /// Receives platform messages before they are translated.
+    pub raw_event_handler: Option<RawEventHandler>,
+    /// Count overrides keyed by menu item id, applied on top of the builder output.
+    pub menu_counts: HashMap<SharedString, u32>,
+    /// Visibility overrides keyed by menu item id, applied on top of the builder output.
+    pub menu_visibility: HashMap<SharedString, bool>,
+    /// Enabled state overrides keyed by menu item id, applied on top of the builder output.
+    pub menu_enabled: HashMap<SharedString, bool>,
+    /// Check state overrides keyed by checkbox or radio item id, applied on top of the builder output.
+    pub menu_checked: HashMap<SharedString, bool>,
 }
 
 impl Tray {
@@ -33,11 +372,75 @@ impl Tray {
     pub fn new() -> Self {
         Self {
             tooltip: None,
+            tooltip_body: None,
+            tooltip_overflow: TooltipOverflow::default(),
             title: None,
+            sni_title: None,
+            tooltip_text: None,
+            title_text: None,
             icon: None,
+            dark_icon: None,
+            icon_name: None,
+            attention_icon: None,
+            attention_icon_name: None,
+            attention_movie_name: None,
+            animation: None,
+            badge: None,
+            template: false,
             visible: true,
+            passive: false,
+            attention: false,
             menu_builder: None,
+            alt_menu_builder: None,
+            left_click: None,
+            menu_trigger: MenuTrigger::default(),
+            fallback_activation: FallbackActivation::FocusMainWindow,
+            suppress_clicks_when_focused: None,
+            menu_build_policy: MenuBuildPolicy::Immediate,
+            update_during_menu: UpdateDuringMenuPolicy::Defer,
+            linux_backend: LinuxTrayBackend::Auto,
+            linux_window_id: None,
+            windows: WindowsTrayOptions::default(),
+            raw_event_handler: None,
+            menu_counts: HashMap::new(),
+            menu_visibility: HashMap::new(),
+            menu_enabled: HashMap::new(),
+            menu_checked: HashMap::new(),
+        }
+    }
+
+    /// Applies the defaults of `preset`.
+    ///
+    /// A click handler set with [`on_left_click`](Self::on_left_click) is
+    /// cleared, so the preset's fallback activation applies.
+    pub fn preset(mut self, preset: TrayPreset) -> Self {
+        self.visible = true;
+        self.left_click = None;
+        match preset {
+            TrayPreset::StatusMonitor => {
+                self.passive = false;
+                self.menu_trigger = MenuTrigger::Both;
+                self.fallback_activation = FallbackActivation::ShowMenu;
+                self.badge = None;
+                self.menu_build_policy = MenuBuildPolicy::Immediate;
+            }
+            TrayPreset::Messenger => {
+                self.passive = false;
+                self.menu_trigger = MenuTrigger::Button(MouseButton::Right);
+                self.fallback_activation = FallbackActivation::FocusMainWindow;
+                // Zero is not drawn, so the count only needs incrementing.
+                self.badge = Some(0);
+                self.menu_build_policy = MenuBuildPolicy::OnIdle;
+            }
+            TrayPreset::BackgroundService => {
+                self.passive = true;
+                self.menu_trigger = MenuTrigger::Both;
+                self.fallback_activation = FallbackActivation::ShowMenu;
+                self.badge = None;
+                self.menu_build_policy = MenuBuildPolicy::Immediate;
+            }
         }
+        self
     }
 
     /// Sets the tooltip text.
@@ -46,32 +449,406 @@ impl Tray {
         self
     }
 
+    /// Sets the text shown under the tooltip.
+    pub fn tooltip_body(mut self, body: impl Into<SharedString>) -> Self {
+        self.tooltip_body = Some(body.into());
+        self
+    }
+
+    /// Sets what happens to a tooltip longer than the platform shows.
+    ///
+    /// Windows shows [`WINDOWS_TOOLTIP_MAX_LEN`](crate::WINDOWS_TOOLTIP_MAX_LEN) UTF-16 units of the tooltip
+    /// and body together, and cuts longer text to fit by default. Apps that
+    /// need more can draw their own tooltip on `TooltipShownEvent` with
+    /// `windows_show_tip(false)`. Linux hosts have no limit.
+    pub fn tooltip_overflow(mut self, overflow: TooltipOverflow) -> Self {
+        self.tooltip_overflow = overflow;
+        self
+    }
+
     /// Sets the title text.
     pub fn title(mut self, title: impl Into<SharedString>) -> Self {
         self.title = Some(title.into());
         self
     }
 
+    /// Sets the name Linux panels list the item under, such as the product
+    /// name, independently of the tooltip.
+    ///
+    /// StatusNotifierItem hosts show it in their settings and context menus,
+    /// e.g. KDE's "Entries" list. Without it the item is listed under
+    /// `title`, or the tooltip, which changes whenever the tooltip shows a
+    /// new status.
+    pub fn sni_title(mut self, title: impl Into<SharedString>) -> Self {
+        self.sni_title = Some(title.into());
+        self
+    }
+
+    /// Returns the name Linux panels list the item under: `sni_title`,
+    /// else `title`, else the tooltip, else "gpui-tray".
+    pub fn resolved_sni_title(&self) -> SharedString {
+        self.sni_title
+            .as_ref()
+            .or(self.title.as_ref())
+            .or(self.tooltip.as_ref().filter(|tooltip| !tooltip.is_empty()))
+            .cloned()
+            .unwrap_or_else(|| "gpui-tray".into())
+    }
+
+    /// Sets the tooltip from a message key resolved through the app's [`Localizer`].
+    pub fn tooltip_fmt<K, V>(
+        mut self,
+        key: impl Into<SharedString>,
+        args: impl IntoIterator<Item = (K, V)>,
+    ) -> Self
+    where
+        K: Into<SharedString>,
+        V: Into<SharedString>,
+    {
+        self.tooltip_text = Some(LocalizedText::new(key, args));
+        self
+    }
+
+    /// Sets the title from a message key resolved through the app's [`Localizer`].
+    pub fn title_fmt<K, V>(
+        mut self,
+        key: impl Into<SharedString>,
+        args: impl IntoIterator<Item = (K, V)>,
+    ) -> Self
+    where
+        K: Into<SharedString>,
+        V: Into<SharedString>,
+    {
+        self.title_text = Some(LocalizedText::new(key, args));
+        self
+    }
+
     /// Sets the icon image.
     pub fn icon(mut self, icon: Image) -> Self {
         self.icon = Some(icon);
         self
     }
 
+    /// Sets one icon for light and one for dark desktops.
+    ///
+    /// The shown icon follows the system theme as it changes. Pick each so
+    /// that it stands out against the panel, e.g. a dark glyph for `light`.
+    pub fn icon_for_theme(mut self, light: Image, dark: Image) -> Self {
+        self.icon = Some(light);
+        self.dark_icon = Some(dark);
+        self
+    }
+
+    /// Sets the name of an icon from the desktop's icon theme, such as one of
+    /// [`freedesktop_icons`](crate::freedesktop_icons).
+    ///
+    /// Linux StatusNotifierItem hosts show the named icon in place of
+    /// `icon`, so it matches the panel. `icon` remains the fallback for
+    /// hosts that cannot find the name, XEmbed and Windows. Animations,
+    /// flashes and badges still show as images. Debug builds warn when the
+    /// installed icon themes lack the name.
+    pub fn icon_name(mut self, name: impl Into<SharedString>) -> Self {
+        self.icon_name = Some(name.into());
+        self
+    }
+
+    /// Sets the icon shown while the tray asks for attention, e.g. a "new
+    /// message" variant of the app's icon.
+    ///
+    /// Linux StatusNotifierItem hosts show it natively, some of them
+    /// blinking it; XEmbed trays and Windows keep showing `icon`.
+    pub fn attention_icon(mut self, icon: Image) -> Self {
+        self.attention_icon = Some(icon);
+        self
+    }
+
+    /// Sets the name of an icon from the desktop's icon theme shown while
+    /// the tray asks for attention. `attention_icon` remains the fallback
+    /// for hosts that cannot find the name.
+    pub fn attention_icon_name(mut self, name: impl Into<SharedString>) -> Self {
+        self.attention_icon_name = Some(name.into());
+        self
+    }
+
+    /// Sets the animation hosts may play while the tray asks for attention,
+    /// as an icon name from the theme or the path of an animated image.
+    /// Hosts without animation support show the attention icon instead.
+    pub fn attention_movie_name(mut self, name: impl Into<SharedString>) -> Self {
+        self.attention_movie_name = Some(name.into());
+        self
+    }
+
+    /// Sets an animation played in place of the static icon.
+    pub fn animated_icon(mut self, animation: AnimatedIcon) -> Self {
+        self.animation = Some(animation);
+        self
+    }
+
+    /// Sets the count drawn over the icon, or removes it with `None`.
+    pub fn badge(mut self, badge: Option<u32>) -> Self {
+        self.badge = badge;
+        self
+    }
+
+    /// Marks the icon as a template image.
+    ///
+    /// Template icons should be black with transparency; macOS recolors them
+    /// for the current menu bar appearance. Windows and Linux show the icon
+    /// as is.
+    pub fn template(mut self, template: bool) -> Self {
+        self.template = template;
+        self
+    }
+
     /// Sets the visibility state.
     pub fn visible(mut self, visible: bool) -> Self {
         self.visible = visible;
         self
     }
 
+    /// Sets the passive state.
+    pub fn passive(mut self, passive: bool) -> Self {
+        self.passive = passive;
+        self
+    }
+
+    /// Sets whether the tray asks for attention.
+    pub fn attention(mut self, attention: bool) -> Self {
+        self.attention = attention;
+        self
+    }
+
+    /// Sets [`passive`](Self::passive) and [`attention`](Self::attention)
+    /// to match `status`.
+    pub fn status(mut self, status: TrayStatus) -> Self {
+        self.passive = status == TrayStatus::Passive;
+        self.attention = status == TrayStatus::Attention;
+        self
+    }
+
+    /// Returns the status the passive and attention states amount to;
+    /// attention wins over passive.
+    pub fn current_status(&self) -> TrayStatus {
+        if self.attention {
+            TrayStatus::Attention
+        } else if self.passive {
+            TrayStatus::Passive
+        } else {
+            TrayStatus::Active
+        }
+    }
+
     /// Sets the context menu builder.
-    pub fn menu<F>(mut self, builder: F) -> Self
+    ///
+    /// The builder may return `Vec<TrayMenuItem>` or `Vec<gpui::MenuItem>`.
+    /// It runs again each time the menu is about to open, so a builder that
+    /// reads shared state shows it current without calling `set_tray`. It is
+    /// called from the backend's thread.
+    pub fn menu<F, M>(mut self, builder: F) -> Self
+    where
+        F: Fn() -> M + Send + Sync + 'static,
+        M: IntoMenuItems,
+    {
+        self.menu_builder = Some(Arc::new(move || builder().into_menu_items()));
+        self
+    }
+
+    /// Sets the builder for the alternate context menu.
+    ///
+    /// The alternate menu replaces the regular one when the menu is opened
+    /// with Shift held on Windows. Linux hosts do not report modifiers, so
+    /// only the regular menu is shown there.
+    pub fn alt_menu<F, M>(mut self, builder: F) -> Self
     where
-        F: Fn() -> Vec<MenuItem> + Send + Sync + 'static,
+        F: Fn() -> M + Send + Sync + 'static,
+        M: IntoMenuItems,
     {
-        self.menu_builder = Some(Arc::new(builder));
+        self.alt_menu_builder = Some(Arc::new(move || builder().into_menu_items()));
         self
     }
+
+    /// Calls `handler` with every message the backend receives from the
+    /// platform, before it is translated into tray events.
+    ///
+    /// Meant for debugging and for platform features this crate does not
+    /// model. The handler runs on the backend's thread and must not call
+    /// back into the tray. See [`RawPlatformEvent`] for what each platform
+    /// reports.
+    ///
+    /// ```rust,ignore
+    /// let tray = Tray::new().on_raw_platform_event(|event| log::trace!("{event:?}"));
+    /// ```
+    pub fn on_raw_platform_event(
+        mut self,
+        handler: impl Fn(&RawPlatformEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.raw_event_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Sets the tray protocol used on Linux.
+    ///
+    /// With [`LinuxTrayBackend::Auto`] the protocol is picked when the icon is
+    /// first shown and kept until the tray is hidden.
+    pub fn linux_backend(mut self, backend: LinuxTrayBackend) -> Self {
+        self.linux_backend = backend;
+        self
+    }
+
+    /// Sets the X11 window the Linux tray item belongs to, for hosts that
+    /// group the icon with the app's window.
+    pub fn linux_window_id(mut self, window_id: u32) -> Self {
+        self.linux_window_id = Some(window_id);
+        self
+    }
+
+    /// Sets the primary action of the icon, run on a left click: the
+    /// activation of a StatusNotifierItem on Linux and `WM_LBUTTONUP` on
+    /// Windows.
+    ///
+    /// Takes an action to dispatch or a closure:
+    ///
+    /// ```rust,ignore
+    /// let tray = Tray::new().on_left_click(ShowWindow);
+    /// let tray = Tray::new().on_left_click(|click: &ClickEvent, cx: &mut App| {
+    ///     open_main_window(cx, click.activation_token.clone());
+    /// });
+    /// ```
+    ///
+    /// The click then no longer reaches the app as a [`ClickEvent`](crate::ClickEvent), and the
+    /// fallback activation does not run. Routes of a `TrayActionRouter` for
+    /// the left click take precedence.
+    pub fn on_left_click<M>(mut self, handler: impl IntoClickHandler<M>) -> Self {
+        self.left_click = Some(handler.into_click_handler());
+        self
+    }
+
+    /// Sets the mouse button that opens the context menu, the right one by
+    /// default.
+    ///
+    /// ```rust,ignore
+    /// // macOS convention: the menu on a left click.
+    /// let tray = Tray::new().menu_on(MouseButton::Left);
+    /// let tray = Tray::new().menu_on(MenuTrigger::Both);
+    /// ```
+    ///
+    /// A left click that opens the menu is not reported as a
+    /// [`ClickEvent`](crate::ClickEvent), so it does not run the primary
+    /// action as well; other buttons are reported either way. The keyboard
+    /// opens the menu regardless. On Linux the host decides: a left trigger
+    /// marks the item as a menu, which hosts open on a left click, and they
+    /// keep opening it on a right click.
+    pub fn menu_on(mut self, trigger: impl Into<MenuTrigger>) -> Self {
+        self.menu_trigger = trigger.into();
+        self
+    }
+
+    /// Sets what an unhandled left click does.
+    pub fn fallback_activation(mut self, fallback: FallbackActivation) -> Self {
+        self.fallback_activation = fallback;
+        self
+    }
+
+    /// Makes a left click on the icon hide `window` while it has focus,
+    /// instead of activating it again, so the icon toggles the window.
+    ///
+    /// The click is swallowed: neither the app nor the fallback activation
+    /// sees it. The window is hidden like closing it would if it was passed
+    /// to `TrayWindowExt::manage_window`, and minimized otherwise. Clicking
+    /// the icon takes focus from the window on Windows, so a window that
+    /// lost focus just before the click still counts as focused.
+    pub fn suppress_clicks_when_focused(mut self, window: impl Into<AnyWindowHandle>) -> Self {
+        self.suppress_clicks_when_focused = Some(window.into());
+        self
+    }
+
+    /// Sets whether updates are applied one by one or coalesced, see
+    /// [`MenuBuildPolicy`].
+    pub fn menu_build_policy(mut self, policy: MenuBuildPolicy) -> Self {
+        self.menu_build_policy = policy;
+        self
+    }
+
+    /// Sets what happens to menu changes while the menu is open.
+    pub fn update_during_menu(mut self, policy: UpdateDuringMenuPolicy) -> Self {
+        self.update_during_menu = policy;
+        self
+    }
+
+    /// Runs the menu builder and applies per-item overrides.
+    ///
+    /// Backends call this instead of the raw builder so that every platform
+    /// renders the same menu.
+    pub fn build_menu(&self) -> Vec<TrayMenuItem> {
+        self.menu_builder
+            .as_ref()
+            .map(|builder| self.finish_menu(builder()))
+            .unwrap_or_default()
+    }
+
+    /// Like [`Tray::build_menu`], but for the alternate menu.
+    ///
+    /// Returns `None` when no alternate menu is configured.
+    pub fn build_alt_menu(&self) -> Option<Vec<TrayMenuItem>> {
+        self.alt_menu_builder
+            .as_ref()
+            .map(|builder| self.finish_menu(builder()))
+    }
+
+    /// Overrides the check state of the checkbox or radio entry `id`.
+    ///
+    /// Checking a radio entry unchecks the other entries of its group.
+    pub fn set_menu_item_checked(&mut self, id: SharedString, checked: bool) {
+        if checked {
+            let menus = [Some(self.build_menu()), self.build_alt_menu()];
+            for menu in menus.iter().flatten() {
+                for sibling in radio_group(menu, &id) {
+                    self.menu_checked.insert(sibling, false);
+                }
+            }
+        }
+        self.menu_checked.insert(id, checked);
+    }
+
+    fn finish_menu(&self, mut items: Vec<TrayMenuItem>) -> Vec<TrayMenuItem> {
+        self.apply_menu_overrides(&mut items);
+        items
+    }
+
+    fn apply_menu_overrides(&self, items: &mut [TrayMenuItem]) {
+        for item in items {
+            if let Some(id) = item.id.as_ref() {
+                if let Some(count) = self.menu_counts.get(id) {
+                    item.count = Some(*count);
+                }
+                if let Some(visible) = self.menu_visibility.get(id) {
+                    item.visible = *visible;
+                }
+                if let Some(enabled) = self.menu_enabled.get(id) {
+                    item.enabled = *enabled;
+                }
+                if let Some(value) = self.menu_checked.get(id)
+                    && let MenuItemKind::Checkbox { checked, .. }
+                    | MenuItemKind::Radio { checked, .. } = &mut item.kind
+                {
+                    *checked = *value;
+                }
+            }
+            if let MenuItemKind::Submenu(children) = &mut item.kind {
+                self.apply_menu_overrides(children);
+            }
+        }
+    }
+
+    /// Resolves localized tooltip and title into their plain text fields.
+    pub fn localize(&mut self, localizer: Option<&dyn Localizer>) {
+        if let Some(text) = self.tooltip_text.as_ref() {
+            self.tooltip = Some(text.resolve(localizer));
+        }
+        if let Some(text) = self.title_text.as_ref() {
+            self.title = Some(text.resolve(localizer));
+        }
+    }
 }
 
 impl Default for Tray {
@@ -80,14 +857,79 @@ impl Default for Tray {
     }
 }
 
+/// Ids of the other radio entries in the group of `id`.
+///
+/// A group is a run of adjacent radio entries on one menu level.
+fn radio_group(items: &[TrayMenuItem], id: &SharedString) -> Vec<SharedString> {
+    let is_radio = |item: &TrayMenuItem| matches!(item.kind, MenuItemKind::Radio { .. });
+
+    let Some(index) = items
+        .iter()
+        .position(|item| is_radio(item) && item.id.as_ref() == Some(id))
+    else {
+        return items
+            .iter()
+            .filter_map(|item| match &item.kind {
+                MenuItemKind::Submenu(children) => Some(radio_group(children, id)),
+                _ => None,
+            })
+            .find(|group| !group.is_empty())
+            .unwrap_or_default();
+    };
+
+    let start = items[..index]
+        .iter()
+        .rposition(|item| !is_radio(item))
+        .map_or(0, |i| i + 1);
+    let end = items[index..]
+        .iter()
+        .position(|item| !is_radio(item))
+        .map_or(items.len(), |i| index + i);
+    items[start..end]
+        .iter()
+        .filter_map(|item| item.id.clone())
+        .filter(|sibling| sibling != id)
+        .collect()
+}
+
 impl Clone for Tray {
     fn clone(&self) -> Self {
         Self {
             tooltip: self.tooltip.clone(),
+            tooltip_body: self.tooltip_body.clone(),
+            tooltip_overflow: self.tooltip_overflow,
             title: self.title.clone(),
+            sni_title: self.sni_title.clone(),
+            tooltip_text: self.tooltip_text.clone(),
+            title_text: self.title_text.clone(),
             icon: self.icon.clone(),
+            dark_icon: self.dark_icon.clone(),
+            icon_name: self.icon_name.clone(),
+            attention_icon: self.attention_icon.clone(),
+            attention_icon_name: self.attention_icon_name.clone(),
+            attention_movie_name: self.attention_movie_name.clone(),
+            animation: self.animation.clone(),
+            badge: self.badge,
+            template: self.template,
             visible: self.visible,
+            passive: self.passive,
+            attention: self.attention,
             menu_builder: self.menu_builder.clone(),
+            alt_menu_builder: self.alt_menu_builder.clone(),
+            left_click: self.left_click.clone(),
+            menu_trigger: self.menu_trigger,
+            fallback_activation: self.fallback_activation,
+            suppress_clicks_when_focused: self.suppress_clicks_when_focused,
+            menu_build_policy: self.menu_build_policy,
+            update_during_menu: self.update_during_menu,
+            linux_backend: self.linux_backend,
+            linux_window_id: self.linux_window_id,
+            windows: self.windows.clone(),
+            raw_event_handler: self.raw_event_handler.clone(),
+            menu_counts: self.menu_counts.clone(),
+            menu_visibility: self.menu_visibility.clone(),
+            menu_enabled: self.menu_enabled.clone(),
+            menu_checked: self.menu_checked.clone(),
         }
     }
 }
@@ -96,9 +938,45 @@ impl fmt::Debug for Tray {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Tray")
             .field("tooltip", &self.tooltip)
+            .field("tooltip_body", &self.tooltip_body)
+            .field("tooltip_overflow", &self.tooltip_overflow)
             .field("title", &self.title)
+            .field("sni_title", &self.sni_title)
+            .field("tooltip_text", &self.tooltip_text)
+            .field("title_text", &self.title_text)
+            .field("icon_name", &self.icon_name)
+            .field("attention_icon_name", &self.attention_icon_name)
+            .field("attention_movie_name", &self.attention_movie_name)
+            .field(
+                "animation_frames",
+                &self.animation.as_ref().map(|a| a.frames().len()),
+            )
+            .field("badge", &self.badge)
+            .field("template", &self.template)
             .field("visible", &self.visible)
+            .field("passive", &self.passive)
+            .field("attention", &self.attention)
             .field("menu_builder", &self.menu_builder.is_some())
+            .field("alt_menu_builder", &self.alt_menu_builder.is_some())
+            .field("left_click", &self.left_click)
+            .field("menu_trigger", &self.menu_trigger)
+            .field("fallback_activation", &self.fallback_activation)
+            .field(
+                "suppress_clicks_when_focused",
+                &self
+                    .suppress_clicks_when_focused
+                    .map(|window| window.window_id()),
+            )
+            .field("menu_build_policy", &self.menu_build_policy)
+            .field("update_during_menu", &self.update_during_menu)
+            .field("linux_backend", &self.linux_backend)
+            .field("linux_window_id", &self.linux_window_id)
+            .field("windows", &self.windows)
+            .field("raw_event_handler", &self.raw_event_handler.is_some())
+            .field("menu_counts", &self.menu_counts)
+            .field("menu_visibility", &self.menu_visibility)
+            .field("menu_enabled", &self.menu_enabled)
+            .field("menu_checked", &self.menu_checked)
             .finish()
     }
 }
@@ -0,0 +1,34 @@
+use gpui::{Action, SharedString};
+
+/// An available update, as reported by the app's update check.
+#[derive(Clone, PartialEq, Debug)]
+pub struct UpdateInfo {
+    /// Version shown to the user, e.g. "2.4.0".
+    pub version: SharedString,
+    /// Release page opened by the update menu entry. Without one the entry
+    /// dispatches [`InstallUpdate`] instead.
+    pub url: Option<SharedString>,
+}
+
+impl UpdateInfo {
+    /// Creates an update to `version`.
+    pub fn new(version: impl Into<SharedString>) -> Self {
+        Self {
+            version: version.into(),
+            url: None,
+        }
+    }
+
+    /// Sets the release page.
+    pub fn url(mut self, url: impl Into<SharedString>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+}
+
+/// The update menu entry of an [`UpdateInfo`] without a URL was selected.
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct InstallUpdate {
+    pub version: SharedString,
+}
@@ -0,0 +1,186 @@
+//! Derive macros for gpui-tray.
+//!
+//! Use them through the `derive` feature of `gpui-tray` rather than depending
+//! on this crate directly; the generated code refers to `::gpui_tray`.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, parse_macro_input};
+
+/// Derives `gpui_tray::TrayMenu` for an enum of unit variants.
+///
+/// Each variant becomes one entry that dispatches the variant itself, so the
+/// enum must also implement `gpui::Action`. Entry ids are the variant names in
+/// snake case. Variants accept a `#[menu(...)]` attribute:
+///
+/// - `label = "..."`: text of the entry, the variant name by default.
+/// - `checkbox`: render the entry with a check mark.
+/// - `checked`: start a checkbox checked; implies `checkbox`.
+/// - `separator_after`: put a separator after the entry.
+///
+/// ```rust,ignore
+/// #[derive(Clone, Copy, PartialEq, Debug, Action, TrayMenu)]
+/// #[action(namespace = my_app, no_json)]
+/// enum AppMenu {
+///     #[menu(label = "Open window")]
+///     Open,
+///     #[menu(label = "Start at login", checked, separator_after)]
+///     Autostart,
+///     Quit,
+/// }
+///
+/// let tray = Tray::new().menu(AppMenu::menu_items);
+/// cx.on_action(|menu: &AppMenu, cx| match menu {
+///     AppMenu::Open => { /* ... */ }
+///     AppMenu::Autostart => { /* ... */ }
+///     AppMenu::Quit => cx.quit(),
+/// });
+/// cx.set_menu_item_checked(AppMenu::Autostart.menu_id(), false)?;
+/// ```
+#[proc_macro_derive(TrayMenu, attributes(menu))]
+pub fn derive_tray_menu(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct Entry {
+    variant: syn::Ident,
+    id: String,
+    label: LitStr,
+    checkbox: bool,
+    checked: bool,
+    separator_after: bool,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "TrayMenu can only be derived for enums",
+        ));
+    };
+
+    let entries = data
+        .variants
+        .iter()
+        .map(|variant| {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "TrayMenu variants cannot have fields",
+                ));
+            }
+            parse_entry(variant)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let items = entries.iter().map(|entry| {
+        let Entry {
+            variant,
+            id,
+            label,
+            checkbox,
+            checked,
+            separator_after,
+        } = entry;
+        let item = if *checkbox {
+            quote! { ::gpui_tray::TrayMenuItem::checkbox(#label, #checked, Self::#variant) }
+        } else {
+            quote! { ::gpui_tray::TrayMenuItem::action(#label, Self::#variant) }
+        };
+        let separator = separator_after.then(|| {
+            quote! { items.push(::gpui_tray::TrayMenuItem::separator()); }
+        });
+        quote! {
+            items.push(#item.id(#id));
+            #separator
+        }
+    });
+    let ids = entries.iter().map(|Entry { variant, id, .. }| {
+        quote! { Self::#variant => #id, }
+    });
+    let variants = entries.iter().map(|Entry { variant, id, .. }| {
+        quote! { #id => ::std::option::Option::Some(Self::#variant), }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::gpui_tray::TrayMenu for #name #ty_generics #where_clause {
+            fn menu_items() -> ::std::vec::Vec<::gpui_tray::TrayMenuItem> {
+                let mut items = ::std::vec::Vec::new();
+                #(#items)*
+                items
+            }
+
+            fn menu_id(&self) -> &'static str {
+                match self {
+                    #(#ids)*
+                }
+            }
+
+            fn from_menu_id(id: &str) -> ::std::option::Option<Self> {
+                match id {
+                    #(#variants)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+    })
+}
+
+fn parse_entry(variant: &syn::Variant) -> syn::Result<Entry> {
+    let mut entry = Entry {
+        variant: variant.ident.clone(),
+        id: snake_case(&variant.ident.to_string()),
+        label: LitStr::new(&variant.ident.to_string(), Span::call_site()),
+        checkbox: false,
+        checked: false,
+        separator_after: false,
+    };
+
+    for attr in variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("menu"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("label") {
+                entry.label = meta.value()?.parse()?;
+            } else if meta.path.is_ident("checkbox") {
+                entry.checkbox = true;
+            } else if meta.path.is_ident("checked") {
+                entry.checkbox = true;
+                entry.checked = true;
+            } else if meta.path.is_ident("separator_after") {
+                entry.separator_after = true;
+            } else {
+                return Err(
+                    meta.error("expected `label`, `checkbox`, `checked` or `separator_after`")
+                );
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(entry)
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, ch) in name.char_indices() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(ch.to_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
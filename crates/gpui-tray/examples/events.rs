@@ -1,8 +1,10 @@
 //! Events example - handling tray click events.
 
 use gpui::{App, Application, Image, ImageFormat, actions};
-use gpui_tray::{Tray, TrayAppContext};
-use gpui_tray_core::{ClickEvent, DoubleClickEvent};
+use gpui_tray::{
+    ClickEvent, DoubleClickEvent, ErrorEvent, ScrollEvent, TooltipHiddenEvent, TooltipShownEvent,
+    Tray, TrayAppContext,
+};
 use log::info;
 
 actions!(events_example, [ShowWindow]);
@@ -16,6 +18,10 @@ fn main() {
         // Register click event handlers
         cx.on_action(on_tray_click);
         cx.on_action(on_tray_double_click);
+        cx.on_action(on_tray_error);
+        cx.on_action(|_: &TooltipShownEvent, _| info!("Tooltip shown"));
+        cx.on_action(|_: &TooltipHiddenEvent, _| info!("Tooltip hidden"));
+        cx.on_action(|event: &ScrollEvent, _| info!("Scrolled {:?}", event.delta));
         cx.on_action(|_: &ShowWindow, _| println!("Show window!"));
 
         let icon = Image::from_bytes(
@@ -42,6 +48,13 @@ fn on_tray_click(event: &ClickEvent, _cx: &mut App) {
     }
 }
 
-fn on_tray_double_click(_event: &DoubleClickEvent, _cx: &mut App) {
-    info!("Tray double-clicked!");
+fn on_tray_double_click(event: &DoubleClickEvent, _cx: &mut App) {
+    info!(
+        "Tray double-clicked: button={:?}, position={:?}",
+        event.button, event.position
+    );
+}
+
+fn on_tray_error(event: &ErrorEvent, _cx: &mut App) {
+    log::warn!("Tray backend error: {}", event.error);
 }
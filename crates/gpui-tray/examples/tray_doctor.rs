@@ -0,0 +1,185 @@
+//! Tray doctor - probes the desktop environment and shows a numbered test icon.
+//!
+//! Run it when a tray icon does not show up and attach the printed report to
+//! the issue. Every left click bumps the badge number, so you can confirm that
+//! both icon updates and click events make it through the host.
+
+use gpui::{App, Application, Image, ImageFormat, MenuItem, MouseButton, actions, rgb};
+use gpui_tray::icon_compose::IconComposer;
+use gpui_tray::{ClickEvent, ErrorEvent, Tray, TrayAppContext};
+use image::{Rgba, RgbaImage};
+use std::io::Cursor;
+
+actions!(tray_doctor, [Quit]);
+
+const ICON_SIZE: u32 = 32;
+
+struct DoctorState {
+    badge: u32,
+}
+
+impl gpui::Global for DoctorState {}
+
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let mut report = Report::default();
+    probe_environment(&mut report);
+    #[cfg(target_os = "linux")]
+    probe_status_notifier(&mut report);
+
+    Application::new().run(move |cx: &mut App| {
+        cx.set_global(DoctorState { badge: 1 });
+        cx.on_action(on_tray_click);
+        cx.on_action(|_: &Quit, cx: &mut App| cx.quit());
+        cx.on_action(|event: &ErrorEvent, _| println!("[tray] error event: {}", event.error));
+
+        match cx.set_tray(doctor_tray(1)) {
+            Ok(()) => report.push("set_tray", "ok"),
+            Err(err) => report.push("set_tray", format!("failed: {err}")),
+        }
+
+        report.print();
+        println!("Left-click the icon to bump the badge; right-click shows the menu.");
+    });
+}
+
+fn on_tray_click(event: &ClickEvent, cx: &mut App) {
+    println!(
+        "[tray] click button={:?} position={:?}",
+        event.button, event.position
+    );
+
+    if event.button != MouseButton::Left {
+        return;
+    }
+
+    let state = cx.global_mut::<DoctorState>();
+    state.badge = (state.badge + 1) % 100;
+    let badge = state.badge;
+
+    if let Err(err) = cx.update_tray(|tray| tray.icon = Some(badge_icon(badge))) {
+        println!("[tray] update_tray failed: {err}");
+    }
+}
+
+fn doctor_tray(badge: u32) -> Tray {
+    Tray::new()
+        .tooltip("gpui-tray doctor")
+        .title("gpui-tray doctor")
+        .icon(badge_icon(badge))
+        .menu(|| vec![MenuItem::action("Quit", Quit)])
+}
+
+#[derive(Default)]
+struct Report {
+    entries: Vec<(&'static str, String)>,
+}
+
+impl Report {
+    fn push(&mut self, key: &'static str, value: impl Into<String>) {
+        self.entries.push((key, value.into()));
+    }
+
+    fn print(&self) {
+        let width = self.entries.iter().map(|(key, _)| key.len()).max();
+        let width = width.unwrap_or_default();
+
+        println!("==== gpui-tray doctor ====");
+        for (key, value) in &self.entries {
+            println!("{key:<width$} : {value}");
+        }
+        println!("==========================");
+    }
+}
+
+fn probe_environment(report: &mut Report) {
+    let env = |name: &str| std::env::var(name).unwrap_or_else(|_| "<unset>".to_string());
+
+    report.push("os", std::env::consts::OS);
+    report.push("arch", std::env::consts::ARCH);
+
+    if cfg!(target_os = "linux") {
+        report.push("desktop", env("XDG_CURRENT_DESKTOP"));
+        report.push("session", env("DESKTOP_SESSION"));
+        report.push("session_type", env("XDG_SESSION_TYPE"));
+        report.push("wayland_display", env("WAYLAND_DISPLAY"));
+        report.push("x11_display", env("DISPLAY"));
+        report.push("dbus_session", env("DBUS_SESSION_BUS_ADDRESS"));
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn probe_status_notifier(report: &mut Report) {
+    const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+    const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+
+    let connection = match zbus::blocking::Connection::session() {
+        Ok(connection) => connection,
+        Err(err) => {
+            report.push("session_bus", format!("unavailable: {err}"));
+            return;
+        }
+    };
+    report.push("session_bus", "connected");
+
+    let has_watcher = zbus::blocking::fdo::DBusProxy::new(&connection)
+        .and_then(|proxy| Ok(proxy.name_has_owner(WATCHER.try_into()?)?));
+    match has_watcher {
+        Ok(true) => report.push("sni_watcher", "present"),
+        Ok(false) => {
+            report.push("sni_watcher", "missing (tray icons will not show up)");
+            return;
+        }
+        Err(err) => {
+            report.push("sni_watcher", format!("probe failed: {err}"));
+            return;
+        }
+    }
+
+    let proxy = match zbus::blocking::Proxy::new(&connection, WATCHER, WATCHER_PATH, WATCHER) {
+        Ok(proxy) => proxy,
+        Err(err) => {
+            report.push("sni_watcher_proxy", format!("failed: {err}"));
+            return;
+        }
+    };
+
+    match proxy.get_property::<bool>("IsStatusNotifierHostRegistered") {
+        Ok(true) => report.push("sni_host", "registered"),
+        Ok(false) => report.push("sni_host", "not registered (no panel is rendering items)"),
+        Err(err) => report.push("sni_host", format!("probe failed: {err}")),
+    }
+
+    match proxy.get_property::<i32>("ProtocolVersion") {
+        Ok(version) => report.push("sni_protocol", version.to_string()),
+        Err(err) => report.push("sni_protocol", format!("probe failed: {err}")),
+    }
+
+    match proxy.get_property::<Vec<String>>("RegisteredStatusNotifierItems") {
+        Ok(items) => report.push("sni_items", items.len().to_string()),
+        Err(err) => report.push("sni_items", format!("probe failed: {err}")),
+    }
+}
+
+/// Renders a flat test icon with the given number in a badge.
+fn badge_icon(badge: u32) -> Image {
+    let mut pixels = RgbaImage::new(ICON_SIZE, ICON_SIZE);
+    let base = Rgba([40, 110, 220, 255]);
+    for (x, y, pixel) in pixels.enumerate_pixels_mut() {
+        if (2..30).contains(&x) && (2..30).contains(&y) {
+            *pixel = base;
+        }
+    }
+
+    let mut bytes = Vec::new();
+    pixels
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .expect("encoding an in-memory PNG should not fail");
+    IconComposer::new(&Image::from_bytes(ImageFormat::Png, bytes), ICON_SIZE)
+        .and_then(|icon| {
+            icon.count_badge(badge, rgb(0xdc3232), rgb(0xffffff))
+                .finish()
+        })
+        .expect("composing an in-memory PNG should not fail")
+}
@@ -1,11 +1,10 @@
 //! Update example - dynamically changing tray properties with UI controls.
 
 use gpui::{
-    App, Application, Context, Div, Image, ImageFormat, MenuItem, Stateful, Window, WindowOptions,
-    actions, div, prelude::*,
+    App, Application, Context, Div, Image, ImageFormat, Stateful, Window, WindowOptions, actions,
+    div, prelude::*,
 };
-use gpui_tray::{Tray, TrayAppContext};
-use gpui_tray_core::{ClickEvent, DoubleClickEvent};
+use gpui_tray::{ClickEvent, DoubleClickEvent, Tray, TrayAppContext, TrayMenuItem};
 use log::info;
 
 actions!(
@@ -37,11 +36,11 @@ fn main() {
             .icon(png_icon)
             .menu(|| {
                 vec![
-                    MenuItem::action("Update Tooltip", UpdateTooltip),
-                    MenuItem::action("Toggle Visibility", ToggleVisible),
-                    MenuItem::action("Toggle Icon", ToggleIcon),
-                    MenuItem::separator(),
-                    MenuItem::action("Quit", Quit),
+                    TrayMenuItem::with_count("updates", "Update Tooltip", 0, UpdateTooltip),
+                    TrayMenuItem::action("Toggle Visibility", ToggleVisible),
+                    TrayMenuItem::action("Toggle Icon", ToggleIcon),
+                    TrayMenuItem::separator(),
+                    TrayMenuItem::action("Quit", Quit),
                 ]
             });
 
@@ -86,6 +85,10 @@ fn update_tooltip(_: &UpdateTooltip, cx: &mut App) {
     app_state.counter += 1;
     let count = app_state.counter;
     app_state.tray.tooltip = Some(format!("Updated {} times", count).into());
+    app_state
+        .tray
+        .menu_counts
+        .insert("updates".into(), count as u32);
 
     let tray = app_state.tray.clone();
     cx.set_tray(tray).unwrap();
@@ -0,0 +1,71 @@
+/*
+ * C interface to gpui-tray, available when the crate is built with the
+ * `ffi` feature. See crates/gpui-tray/src/ffi.rs for the full documentation.
+ */
+
+#ifndef GPUI_TRAY_H
+#define GPUI_TRAY_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define GPUI_TRAY_OK 0
+#define GPUI_TRAY_INVALID_ARGUMENT (-1)
+#define GPUI_TRAY_INVALID_ICON (-2)
+#define GPUI_TRAY_UNSUPPORTED (-3)
+#define GPUI_TRAY_CLOSED (-4)
+#define GPUI_TRAY_BACKEND_ERROR (-5)
+
+#define GPUI_TRAY_ITEM_ACTION 0u
+#define GPUI_TRAY_ITEM_SEPARATOR 1u
+
+#define GPUI_TRAY_EVENT_CLICK 1u
+#define GPUI_TRAY_EVENT_DOUBLE_CLICK 2u
+#define GPUI_TRAY_EVENT_MENU_SELECT 3u
+#define GPUI_TRAY_EVENT_SCROLL 4u
+#define GPUI_TRAY_EVENT_ERROR 5u
+
+#define GPUI_TRAY_BUTTON_LEFT 0u
+#define GPUI_TRAY_BUTTON_RIGHT 1u
+#define GPUI_TRAY_BUTTON_MIDDLE 2u
+
+typedef struct GpuiTray GpuiTray;
+
+typedef struct GpuiTrayMenuItem {
+    uint32_t kind;
+    const char *id;
+    const char *label;
+} GpuiTrayMenuItem;
+
+typedef struct GpuiTrayEvent {
+    uint32_t kind;
+    uint32_t button;
+    float x;
+    float y;
+    float scroll;
+    bool horizontal;
+    /* Menu item id or error message, valid until the next poll. */
+    const char *text;
+} GpuiTrayEvent;
+
+GpuiTray *gpui_tray_create(void);
+void gpui_tray_destroy(GpuiTray *tray);
+
+int32_t gpui_tray_set_tooltip(GpuiTray *tray, const char *tooltip);
+int32_t gpui_tray_set_icon_rgba(GpuiTray *tray, const uint8_t *rgba, uint32_t width,
+                                uint32_t height);
+int32_t gpui_tray_set_menu(GpuiTray *tray, const GpuiTrayMenuItem *items, size_t len);
+
+/* Returns 1 if `event` was filled in, 0 if none is pending, or a negative status. */
+int32_t gpui_tray_poll_event(GpuiTray *tray, GpuiTrayEvent *event);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GPUI_TRAY_H */
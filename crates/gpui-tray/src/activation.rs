@@ -0,0 +1,170 @@
+//! Raising a window from a tray click.
+//!
+//! Wayland compositors only give focus to a client that presents a token
+//! from the user's input. GPUI asks for its own token, which the compositor
+//! rejects when the input went to the panel, so the window stays behind.
+//! The panel passes its token with the click instead, in
+//! [`ClickEvent::activation_token`](gpui_tray_core::ClickEvent::activation_token):
+//!
+//! ```rust,ignore
+//! cx.on_action(|event: &ClickEvent, cx| {
+//!     if let Some(window) = cx.windows().first() {
+//!         let token = event.activation_token.clone();
+//!         let _ = window.update(cx, |_, window, cx| {
+//!             activate_window(window, cx, token.as_ref());
+//!         });
+//!     }
+//! });
+//! ```
+
+use gpui::{App, SharedString, Window};
+
+/// Brings `window` to the front and focuses it, with the activation token of
+/// the tray click when there is one.
+///
+/// On Wayland the token goes to the compositor through `xdg_activation_v1`.
+/// Elsewhere, or without a token, this is [`Window::activate_window`].
+pub fn activate_window(window: &mut Window, cx: &mut App, token: Option<&SharedString>) {
+    #[cfg(target_os = "linux")]
+    if let Some(token) = token
+        && wayland::activate(window, cx, token)
+    {
+        return;
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = (cx, token);
+
+    window.activate_window();
+}
+
+/// Closes our handle on the Wayland connection while GPUI's display is
+/// still open.
+pub(crate) fn release(cx: &mut App) {
+    #[cfg(target_os = "linux")]
+    if cx.has_global::<wayland::Activation>() {
+        cx.remove_global::<wayland::Activation>();
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = cx;
+}
+
+#[cfg(target_os = "linux")]
+mod wayland {
+    use gpui::{App, Global, Window};
+    use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawDisplayHandle, RawWindowHandle};
+    use wayland_backend::client::{Backend, ObjectId};
+    use wayland_client::globals::{GlobalList, GlobalListContents, registry_queue_init};
+    use wayland_client::protocol::wl_registry::{self, WlRegistry};
+    use wayland_client::protocol::wl_surface::WlSurface;
+    use wayland_client::{Connection, Dispatch, EventQueue, Proxy, QueueHandle};
+    use wayland_protocols::xdg::activation::v1::client::xdg_activation_v1::{
+        self, XdgActivationV1,
+    };
+
+    /// Our own handle on GPUI's Wayland connection, bound once on first use.
+    /// `activation` is `None` when the compositor lacks the protocol.
+    pub(super) struct Activation {
+        connection: Connection,
+        activation: Option<XdgActivationV1>,
+        _globals: GlobalList,
+        _queue: EventQueue<ActivationState>,
+    }
+
+    impl Global for Activation {}
+
+    /// Neither object sends events we need.
+    struct ActivationState;
+
+    impl Dispatch<WlRegistry, GlobalListContents> for ActivationState {
+        fn event(
+            _: &mut Self,
+            _: &WlRegistry,
+            _: wl_registry::Event,
+            _: &GlobalListContents,
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<XdgActivationV1, ()> for ActivationState {
+        fn event(
+            _: &mut Self,
+            _: &XdgActivationV1,
+            _: xdg_activation_v1::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    /// Activates `window` with `token`. Returns `false` when the window is
+    /// not on Wayland or the compositor cannot take the token.
+    pub(super) fn activate(window: &Window, cx: &mut App, token: &str) -> bool {
+        let Ok(display) = window.display_handle() else {
+            return false;
+        };
+        let RawDisplayHandle::Wayland(display) = display.as_raw() else {
+            return false;
+        };
+        let Ok(handle) = HasWindowHandle::window_handle(window) else {
+            return false;
+        };
+        let RawWindowHandle::Wayland(handle) = handle.as_raw() else {
+            return false;
+        };
+
+        if !cx.has_global::<Activation>() {
+            // Safety: GPUI keeps its display open for as long as the app,
+            // which owns this connection, runs.
+            let backend = unsafe { Backend::from_foreign_display(display.display.as_ptr().cast()) };
+            let Some(activation) = Activation::bind(Connection::from_backend(backend)) else {
+                return false;
+            };
+            cx.set_global(activation);
+        }
+
+        let state = cx.global::<Activation>();
+        let Some(activation) = state.activation.as_ref() else {
+            return false;
+        };
+        // Safety: the surface belongs to `window`, which outlives this call.
+        let surface =
+            unsafe { ObjectId::from_ptr(WlSurface::interface(), handle.surface.as_ptr().cast()) }
+                .and_then(|id| WlSurface::from_id(&state.connection, id));
+        let Ok(surface) = surface else {
+            return false;
+        };
+
+        activation.activate(token.to_string(), &surface);
+        if let Err(err) = state.connection.flush() {
+            log::debug!("wayland activation failed: {err}");
+            return false;
+        }
+        true
+    }
+
+    impl Activation {
+        fn bind(connection: Connection) -> Option<Self> {
+            let (globals, queue) = match registry_queue_init::<ActivationState>(&connection) {
+                Ok(init) => init,
+                Err(err) => {
+                    log::debug!("wayland registry unavailable: {err}");
+                    return None;
+                }
+            };
+            let activation = globals
+                .bind::<XdgActivationV1, _, _>(&queue.handle(), 1..=1, ())
+                .inspect_err(|err| log::debug!("xdg_activation_v1 unavailable: {err}"))
+                .ok();
+
+            Some(Self {
+                connection,
+                activation,
+                _globals: globals,
+                _queue: queue,
+            })
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Confirmation window for menu entries built with `TrayMenuItem::confirm`.
+
+use crate::manager::{handle_backend_action, localize_builtin};
+use gpui::{
+    Action, App, Bounds, Context, FocusHandle, Focusable, KeyDownEvent, Rgba, SharedString, Window,
+    WindowAppearance, WindowBounds, WindowKind, WindowOptions, div, prelude::*, px, rgb, size,
+};
+use gpui_tray_core::{BackendError, ConfirmMenuSelection, Error, Result};
+
+/// Message key of the button that runs the entry's action, "OK".
+pub const CONFIRM_OK_KEY: &str = "gpui-tray-confirm-ok";
+
+/// Message key of the button that dismisses the question, "Cancel".
+pub const CONFIRM_CANCEL_KEY: &str = "gpui-tray-confirm-cancel";
+
+/// Asks the question of `selection` and dispatches its actions on
+/// confirmation.
+pub(crate) fn open(cx: &mut App, selection: &ConfirmMenuSelection) -> Result<()> {
+    let selection = selection.clone();
+    let ok_label = localize_builtin(cx, CONFIRM_OK_KEY, &[], || "OK".to_string());
+    let cancel_label = localize_builtin(cx, CONFIRM_CANCEL_KEY, &[], || "Cancel".to_string());
+    let options = WindowOptions {
+        window_bounds: Some(WindowBounds::Windowed(Bounds::centered(
+            None,
+            size(px(360.), px(140.)),
+            cx,
+        ))),
+        titlebar: None,
+        focus: true,
+        show: true,
+        kind: WindowKind::PopUp,
+        is_movable: false,
+        is_resizable: false,
+        is_minimizable: false,
+        ..Default::default()
+    };
+
+    cx.activate(true);
+    cx.open_window(options, |window, cx| {
+        let dialog = cx.new(|cx| ConfirmDialog {
+            message: selection.message,
+            actions: selection.actions,
+            ok_label,
+            cancel_label,
+            confirm_focused: false,
+            focus: cx.focus_handle(),
+        });
+        window.focus(&dialog.focus_handle(cx));
+        dialog
+    })
+    .map(|_| ())
+    .map_err(|err| Error::Backend(BackendError::platform("open_window", err.to_string())))
+}
+
+struct ConfirmDialog {
+    message: SharedString,
+    actions: Vec<Box<dyn Action>>,
+    ok_label: SharedString,
+    cancel_label: SharedString,
+    /// Whether Enter confirms rather than cancels. Starts out false, as the
+    /// actions asked about are usually destructive.
+    confirm_focused: bool,
+    focus: FocusHandle,
+}
+
+/// Colors of the dialog for the window's light or dark appearance.
+struct Palette {
+    background: Rgba,
+    text: Rgba,
+    border: Rgba,
+    accent: Rgba,
+    on_accent: Rgba,
+}
+
+impl Palette {
+    fn for_appearance(appearance: WindowAppearance) -> Self {
+        match appearance {
+            WindowAppearance::Dark | WindowAppearance::VibrantDark => Self {
+                background: rgb(0x2b2b2b),
+                text: rgb(0xe8e8e8),
+                border: rgb(0x5a5a5a),
+                accent: rgb(0x3d7fe6),
+                on_accent: rgb(0xffffff),
+            },
+            WindowAppearance::Light | WindowAppearance::VibrantLight => Self {
+                background: rgb(0xf6f6f6),
+                text: rgb(0x202020),
+                border: rgb(0xc8c8c8),
+                accent: rgb(0x2f6fde),
+                on_accent: rgb(0xffffff),
+            },
+        }
+    }
+}
+impl ConfirmDialog {
+    fn confirm(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let actions = std::mem::take(&mut self.actions);
+        window.remove_window();
+        cx.defer(move |cx| {
+            for action in actions {
+                handle_backend_action(cx, action.as_ref());
+            }
+        });
+    }
+
+    fn cancel(&mut self, window: &mut Window, _: &mut Context<Self>) {
+        log::debug!("menu selection cancelled: {}", self.message);
+        window.remove_window();
+    }
+}
+
+impl Focusable for ConfirmDialog {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus.clone()
+    }
+}
+
+impl Render for ConfirmDialog {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let palette = Palette::for_appearance(window.appearance());
+        // The focused button gets the accent color and answers Enter.
+        let button = |id: &'static str, label: SharedString, focused: bool| {
+            let button = div()
+                .id(id)
+                .px_3()
+                .py_1()
+                .rounded_md()
+                .border_1()
+                .cursor_pointer()
+                .child(label);
+            if focused {
+                button
+                    .bg(palette.accent)
+                    .border_color(palette.accent)
+                    .text_color(palette.on_accent)
+            } else {
+                button.border_color(palette.border)
+            }
+        };
+
+        div()
+            .track_focus(&self.focus)
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, window, cx| {
+                match event.keystroke.key.as_str() {
+                    "enter" if this.confirm_focused => this.confirm(window, cx),
+                    "enter" | "escape" => this.cancel(window, cx),
+                    "tab" | "left" | "right" => {
+                        this.confirm_focused = !this.confirm_focused;
+                        cx.notify();
+                    }
+                    _ => {}
+                }
+            }))
+            .size_full()
+            .flex()
+            .flex_col()
+            .justify_between()
+            .p_4()
+            .bg(palette.background)
+            .text_color(palette.text)
+            .text_sm()
+            .child(self.message.clone())
+            .child(
+                div()
+                    .flex()
+                    .justify_end()
+                    .gap_2()
+                    .child(
+                        button("cancel", self.cancel_label.clone(), !self.confirm_focused)
+                            .on_click(cx.listener(|this, _, window, cx| this.cancel(window, cx))),
+                    )
+                    .child(
+                        button("confirm", self.ok_label.clone(), self.confirm_focused)
+                            .on_click(cx.listener(|this, _, window, cx| this.confirm(window, cx))),
+                    ),
+            )
+    }
+}
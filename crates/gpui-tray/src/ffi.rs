@@ -0,0 +1,361 @@
+//! C ABI for driving a tray icon from non-Rust code.
+//!
+//! Enabled with the `ffi` feature; the matching declarations live in
+//! `include/gpui_tray.h`. A [`GpuiTray`] handle owns its own backend, separate
+//! from the tray managed through [`TrayAppContext`](crate::TrayAppContext),
+//! and reports events through [`gpui_tray_poll_event`] instead of GPUI actions.
+//!
+//! Functions return [`GPUI_TRAY_OK`] on success and a negative status code
+//! otherwise. Handles are not thread-safe: use each one from a single thread.
+
+use crate::platform_impl;
+use gpui::{MouseButton, SharedString};
+use gpui_tray_core::platform_trait::PlatformTray;
+use gpui_tray_core::{
+    ClickEvent, DoubleClickEvent, Error, MenuSelectEvent, RuntimeEvent, ScrollEvent,
+    ScrollOrientation, Tray, TrayMenuItem, image_from_rgba,
+};
+use std::ffi::{CStr, CString, c_char};
+use std::sync::Arc;
+
+pub const GPUI_TRAY_OK: i32 = 0;
+pub const GPUI_TRAY_INVALID_ARGUMENT: i32 = -1;
+pub const GPUI_TRAY_INVALID_ICON: i32 = -2;
+pub const GPUI_TRAY_UNSUPPORTED: i32 = -3;
+pub const GPUI_TRAY_CLOSED: i32 = -4;
+pub const GPUI_TRAY_BACKEND_ERROR: i32 = -5;
+
+pub const GPUI_TRAY_ITEM_ACTION: u32 = 0;
+pub const GPUI_TRAY_ITEM_SEPARATOR: u32 = 1;
+
+pub const GPUI_TRAY_EVENT_CLICK: u32 = 1;
+pub const GPUI_TRAY_EVENT_DOUBLE_CLICK: u32 = 2;
+pub const GPUI_TRAY_EVENT_MENU_SELECT: u32 = 3;
+pub const GPUI_TRAY_EVENT_SCROLL: u32 = 4;
+pub const GPUI_TRAY_EVENT_ERROR: u32 = 5;
+
+pub const GPUI_TRAY_BUTTON_LEFT: u32 = 0;
+pub const GPUI_TRAY_BUTTON_RIGHT: u32 = 1;
+pub const GPUI_TRAY_BUTTON_MIDDLE: u32 = 2;
+
+/// Opaque tray handle created by [`gpui_tray_create`].
+pub struct GpuiTray {
+    backend: Box<dyn PlatformTray>,
+    tray: Tray,
+    /// Keeps the text of the last polled event alive for the caller.
+    last_text: Option<CString>,
+}
+
+/// One entry of the array passed to [`gpui_tray_set_menu`].
+#[repr(C)]
+pub struct GpuiTrayMenuItem {
+    /// `GPUI_TRAY_ITEM_ACTION` or `GPUI_TRAY_ITEM_SEPARATOR`.
+    pub kind: u32,
+    /// Reported back by `GPUI_TRAY_EVENT_MENU_SELECT`; unused for separators.
+    pub id: *const c_char,
+    /// UTF-8 text shown for the entry; unused for separators.
+    pub label: *const c_char,
+}
+
+/// An event filled in by [`gpui_tray_poll_event`].
+#[repr(C)]
+pub struct GpuiTrayEvent {
+    /// One of the `GPUI_TRAY_EVENT_*` constants.
+    pub kind: u32,
+    /// Clicked button of a click or double-click, one of the
+    /// `GPUI_TRAY_BUTTON_*` constants.
+    pub button: u32,
+    /// Pointer position of a click or double-click, in screen coordinates.
+    pub x: f32,
+    pub y: f32,
+    /// Scroll distance in wheel notches; positive is down or right.
+    pub scroll: f32,
+    /// Whether the scroll is horizontal.
+    pub horizontal: bool,
+    /// Selected menu item id or error message, valid until the next poll.
+    pub text: *const c_char,
+}
+
+#[derive(Clone)]
+enum MenuEntry {
+    Separator,
+    Action {
+        id: SharedString,
+        label: SharedString,
+    },
+}
+
+impl GpuiTray {
+    fn apply(&self) -> i32 {
+        match self.backend.set_tray(self.tray.clone()) {
+            Ok(()) => GPUI_TRAY_OK,
+            Err(err) => status(&err),
+        }
+    }
+}
+
+fn status(err: &Error) -> i32 {
+    match err {
+        Error::InvalidIcon | Error::IconTooLarge { .. } | Error::UnknownImageFormat => {
+            GPUI_TRAY_INVALID_ICON
+        }
+        Error::UnsupportedPlatform | Error::PlatformUnavailable { .. } => GPUI_TRAY_UNSUPPORTED,
+        Error::RuntimeClosed => GPUI_TRAY_CLOSED,
+        _ => GPUI_TRAY_BACKEND_ERROR,
+    }
+}
+
+/// Reads a NUL-terminated UTF-8 string, or `None` for null or invalid UTF-8.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string.
+unsafe fn read_str(ptr: *const c_char) -> Option<SharedString> {
+    if ptr.is_null() {
+        return None;
+    }
+    let text = unsafe { CStr::from_ptr(ptr) }.to_str().ok()?;
+    Some(SharedString::from(text.to_owned()))
+}
+
+/// Creates a tray backend. The icon appears once an icon is set.
+///
+/// Returns null if the platform has no tray support or the backend failed
+/// to start.
+#[unsafe(no_mangle)]
+pub extern "C" fn gpui_tray_create() -> *mut GpuiTray {
+    match platform_impl::create() {
+        Ok(backend) => Box::into_raw(Box::new(GpuiTray {
+            backend,
+            tray: Tray::new(),
+            last_text: None,
+        })),
+        Err(err) => {
+            log::error!("gpui_tray_create failed: {err}");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Removes the icon and releases the handle.
+///
+/// # Safety
+///
+/// `tray` must be null or a handle from [`gpui_tray_create`] that was not
+/// destroyed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn gpui_tray_destroy(tray: *mut GpuiTray) {
+    if tray.is_null() {
+        return;
+    }
+    let tray = unsafe { Box::from_raw(tray) };
+    let _ = tray.backend.remove_tray();
+    let _ = tray.backend.shutdown();
+}
+
+/// Sets the tooltip; null clears it.
+///
+/// # Safety
+///
+/// `tray` must be a live handle and `tooltip` null or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn gpui_tray_set_tooltip(tray: *mut GpuiTray, tooltip: *const c_char) -> i32 {
+    let Some(tray) = (unsafe { tray.as_mut() }) else {
+        return GPUI_TRAY_INVALID_ARGUMENT;
+    };
+    tray.tray.tooltip = unsafe { read_str(tooltip) };
+    tray.apply()
+}
+
+/// Sets the icon from `width * height` non-premultiplied RGBA8 pixels.
+///
+/// # Safety
+///
+/// `tray` must be a live handle and `rgba` must point to
+/// `width * height * 4` readable bytes. Dimensions whose byte count does not
+/// fit in memory fail with `GPUI_TRAY_INVALID_ICON`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn gpui_tray_set_icon_rgba(
+    tray: *mut GpuiTray,
+    rgba: *const u8,
+    width: u32,
+    height: u32,
+) -> i32 {
+    let Some(tray) = (unsafe { tray.as_mut() }) else {
+        return GPUI_TRAY_INVALID_ARGUMENT;
+    };
+    if rgba.is_null() || width == 0 || height == 0 {
+        return GPUI_TRAY_INVALID_ARGUMENT;
+    }
+
+    // Sizes no buffer can hold would otherwise wrap or overflow the slice.
+    let Some(len) = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .filter(|len| *len <= isize::MAX as usize)
+    else {
+        return GPUI_TRAY_INVALID_ICON;
+    };
+    let pixels = unsafe { std::slice::from_raw_parts(rgba, len) }.to_vec();
+    match image_from_rgba(width, height, pixels) {
+        Ok(image) => {
+            tray.tray.icon = Some(image);
+            tray.apply()
+        }
+        Err(err) => status(&err),
+    }
+}
+
+/// Replaces the context menu with `len` entries; `len == 0` removes it.
+///
+/// # Safety
+///
+/// `tray` must be a live handle and `items` must point to `len` entries whose
+/// strings are null or NUL-terminated. The strings are copied.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn gpui_tray_set_menu(
+    tray: *mut GpuiTray,
+    items: *const GpuiTrayMenuItem,
+    len: usize,
+) -> i32 {
+    let Some(tray) = (unsafe { tray.as_mut() }) else {
+        return GPUI_TRAY_INVALID_ARGUMENT;
+    };
+    if len == 0 {
+        tray.tray.menu_builder = None;
+        return tray.apply();
+    }
+    if items.is_null() {
+        return GPUI_TRAY_INVALID_ARGUMENT;
+    }
+
+    let mut entries = Vec::with_capacity(len);
+    for item in unsafe { std::slice::from_raw_parts(items, len) } {
+        let entry = match item.kind {
+            GPUI_TRAY_ITEM_SEPARATOR => MenuEntry::Separator,
+            GPUI_TRAY_ITEM_ACTION => {
+                let id = unsafe { read_str(item.id) };
+                let label = unsafe { read_str(item.label) };
+                let (Some(id), Some(label)) = (id, label) else {
+                    return GPUI_TRAY_INVALID_ARGUMENT;
+                };
+                MenuEntry::Action { id, label }
+            }
+            _ => return GPUI_TRAY_INVALID_ARGUMENT,
+        };
+        entries.push(entry);
+    }
+
+    // Each entry dispatches its own selection event, so no separate action
+    // type is needed on the C side.
+    let entries: Arc<[MenuEntry]> = entries.into();
+    tray.tray = std::mem::take(&mut tray.tray).menu(move || {
+        entries
+            .iter()
+            .map(|entry| match entry {
+                MenuEntry::Separator => TrayMenuItem::separator(),
+                MenuEntry::Action { id, label } => {
+                    TrayMenuItem::action(label.clone(), MenuSelectEvent { id: id.clone() })
+                }
+            })
+            .collect::<Vec<_>>()
+    });
+    tray.apply()
+}
+
+/// Takes the next pending event.
+///
+/// Returns 1 if `event` was filled in, 0 if no event is pending, or a
+/// negative status code. Tooltip notifications are not reported.
+///
+/// # Safety
+///
+/// `tray` must be a live handle and `event` must point to writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn gpui_tray_poll_event(
+    tray: *mut GpuiTray,
+    event: *mut GpuiTrayEvent,
+) -> i32 {
+    let Some(tray) = (unsafe { tray.as_mut() }) else {
+        return GPUI_TRAY_INVALID_ARGUMENT;
+    };
+    if event.is_null() {
+        return GPUI_TRAY_INVALID_ARGUMENT;
+    }
+
+    loop {
+        let received = match tray.backend.try_recv_event() {
+            Ok(Some(received)) => received,
+            Ok(None) => return 0,
+            Err(err) => return status(&err),
+        };
+
+        let mut out = GpuiTrayEvent {
+            kind: 0,
+            button: GPUI_TRAY_BUTTON_LEFT,
+            x: 0.0,
+            y: 0.0,
+            scroll: 0.0,
+            horizontal: false,
+            text: std::ptr::null(),
+        };
+        let text = match received {
+            RuntimeEvent::Action(action) => {
+                let action = action.as_any();
+                if let Some(click) = action.downcast_ref::<ClickEvent>() {
+                    out.kind = GPUI_TRAY_EVENT_CLICK;
+                    out.button = ffi_button(click.button);
+                    out.x = click.position.x;
+                    out.y = click.position.y;
+                    None
+                } else if let Some(click) = action.downcast_ref::<DoubleClickEvent>() {
+                    out.kind = GPUI_TRAY_EVENT_DOUBLE_CLICK;
+                    out.button = ffi_button(click.button);
+                    out.x = click.position.x;
+                    out.y = click.position.y;
+                    None
+                } else if let Some(select) = action.downcast_ref::<MenuSelectEvent>() {
+                    out.kind = GPUI_TRAY_EVENT_MENU_SELECT;
+                    Some(select.id.to_string())
+                } else if let Some(scroll) = action.downcast_ref::<ScrollEvent>() {
+                    out.kind = GPUI_TRAY_EVENT_SCROLL;
+                    out.scroll = scroll.delta.lines;
+                    out.horizontal = scroll.delta.orientation == ScrollOrientation::Horizontal;
+                    None
+                } else {
+                    continue;
+                }
+            }
+            RuntimeEvent::MenuSelection(actions) => {
+                let Some(select) = actions
+                    .iter()
+                    .find_map(|action| action.as_any().downcast_ref::<MenuSelectEvent>())
+                else {
+                    continue;
+                };
+                out.kind = GPUI_TRAY_EVENT_MENU_SELECT;
+                Some(select.id.to_string())
+            }
+            RuntimeEvent::Error(err) => {
+                out.kind = GPUI_TRAY_EVENT_ERROR;
+                Some(err.to_string())
+            }
+        };
+
+        // Text with an interior NUL cannot cross into C; report the event without it.
+        tray.last_text = text.and_then(|text| CString::new(text).ok());
+        if let Some(text) = tray.last_text.as_ref() {
+            out.text = text.as_ptr();
+        }
+        unsafe { event.write(out) };
+        return 1;
+    }
+}
+
+fn ffi_button(button: MouseButton) -> u32 {
+    match button {
+        MouseButton::Right => GPUI_TRAY_BUTTON_RIGHT,
+        MouseButton::Middle => GPUI_TRAY_BUTTON_MIDDLE,
+        _ => GPUI_TRAY_BUTTON_LEFT,
+    }
+}
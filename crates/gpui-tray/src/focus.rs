@@ -0,0 +1,104 @@
+//! Clicking the tray icon to hide a window that has focus, see
+//! `Tray::suppress_clicks_when_focused`.
+
+use crate::TrayAppContext;
+use crate::manager;
+use crate::window;
+use gpui::{Action, AnyWindowHandle, App, AppContext, Entity, Global, MouseButton, Subscription};
+use gpui_tray_core::ClickEvent;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// A click that arrives this soon after the window lost focus still counts
+/// as a click on a focused window: on Windows the click itself activates
+/// the taskbar first.
+const FOCUS_GRACE: Duration = Duration::from_millis(300);
+
+/// Owns the window's activation observer.
+struct FocusObserver;
+
+/// The window named by the current tray.
+struct WatchedWindow {
+    window: AnyWindowHandle,
+    /// When the window last lost focus.
+    deactivated_at: Rc<Cell<Option<Instant>>>,
+    _observer: Entity<FocusObserver>,
+    _subscription: Subscription,
+}
+
+impl Global for WatchedWindow {}
+
+/// Starts or stops watching the window the tray names. Called after every
+/// tray change.
+pub(crate) fn sync_with_tray(cx: &mut App) {
+    let window = cx.tray().and_then(|tray| tray.suppress_clicks_when_focused);
+    let watched = cx
+        .try_global::<WatchedWindow>()
+        .map(|watched| watched.window);
+    if window == watched {
+        return;
+    }
+    if watched.is_some() {
+        cx.remove_global::<WatchedWindow>();
+    }
+    let Some(window) = window else {
+        return;
+    };
+
+    let deactivated_at = Rc::new(Cell::new(None));
+    let deactivated = deactivated_at.clone();
+    let clock = manager::clock(cx);
+    let observer = cx.new(|_| FocusObserver);
+    let subscription = window.update(cx, |_, window, cx| {
+        observer.update(cx, |_, cx| {
+            cx.observe_window_activation(window, move |_, window, _| {
+                if !window.is_window_active() {
+                    deactivated.set(Some(clock.now()));
+                }
+            })
+        })
+    });
+    let Ok(subscription) = subscription else {
+        log::debug!("window for suppress_clicks_when_focused is closed");
+        return;
+    };
+
+    cx.set_global(WatchedWindow {
+        window,
+        deactivated_at,
+        _observer: observer,
+        _subscription: subscription,
+    });
+}
+
+/// Hides the watched window on a left click while it has focus. Returns
+/// whether the click was consumed.
+pub(crate) fn suppress_click(cx: &mut App, action: &dyn Action) -> bool {
+    let Some(click) = action.as_any().downcast_ref::<ClickEvent>() else {
+        return false;
+    };
+    if click.button != MouseButton::Left {
+        return false;
+    }
+
+    let now = manager::clock(cx).now();
+    let Some(watched) = cx.try_global::<WatchedWindow>() else {
+        return false;
+    };
+    let window = watched.window;
+    let just_deactivated = watched
+        .deactivated_at
+        .get()
+        .is_some_and(|at| now.saturating_duration_since(at) < FOCUS_GRACE);
+    let Ok(active) = window.update(cx, |_, window, _| window.is_window_active()) else {
+        return false;
+    };
+    if !active && !just_deactivated {
+        return false;
+    }
+
+    log::debug!("tray click hides the focused window");
+    window::dismiss(cx, window);
+    true
+}
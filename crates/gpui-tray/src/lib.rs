@@ -5,27 +5,70 @@
 //!
 //! # Quick Start
 //!
-//! ```rust
-//! use gpui_tray::{Tray, TrayAppContext};
+//! ```rust,no_run
+//! use gpui_tray::prelude::*;
+//! # fn run(cx: &mut gpui::App, image: Image) -> Result<()> {
 //!
 //! // Set a tray icon
 //! cx.set_tray(
 //!     Tray::new()
 //!         .tooltip("My Application")
 //!         .icon(image)
-//! );
+//! )?;
 //!
 //! // Update the tray
 //! cx.update_tray(|tray| {
 //!     tray.tooltip = Some("Updated".into());
-//! });
+//! })?;
 //!
 //! // Remove the tray
-//! cx.remove_tray();
+//! cx.remove_tray()?;
+//! # Ok(())
+//! # }
 //! ```
 
+pub use gpui::{Image, ImageFormat, MenuItem, MouseButton};
 pub use gpui_tray_core::*;
+#[cfg(feature = "derive")]
+pub use gpui_tray_derive::TrayMenu;
 
+mod activation;
+mod confirm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod focus;
 mod manager;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod popover;
+pub mod prelude;
+#[cfg(feature = "remote-control")]
+pub mod remote;
+mod shutdown;
+mod support;
+mod update;
+mod visibility;
+#[cfg(feature = "web")]
+pub mod web;
+mod window;
 
+pub use activation::activate_window;
+pub use confirm::{CONFIRM_CANCEL_KEY, CONFIRM_OK_KEY};
 pub use manager::TrayAppContext;
+pub use popover::TrayPopover;
+pub use support::TraySupport;
+pub use update::{
+    UPDATE_MENU_ITEM_ID, UPDATE_MENU_LABEL_KEY, UPDATE_NOTIFICATION_BODY_KEY,
+    UPDATE_NOTIFICATION_TITLE_KEY, UpdateNotifier,
+};
+pub use visibility::TrayVisibilityGuard;
+pub use window::{ShowManagedWindow, TrayWindowExt};
+
+#[cfg(target_os = "windows")]
+use gpui_tray_windows as platform_impl;
+
+#[cfg(target_os = "macos")]
+use gpui_tray_macos as platform_impl;
+
+#[cfg(target_os = "linux")]
+use gpui_tray_linux as platform_impl;
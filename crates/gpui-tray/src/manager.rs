@@ -1,36 +1,193 @@
-use gpui::{App, AsyncApp, Global, Task};
+use crate::activation::activate_window;
+use crate::confirm;
+use crate::focus;
+use crate::platform_impl;
+use crate::shutdown;
+use crate::update::{UpdateSurface, update_menu_item};
+use crate::visibility::{self, TrayVisibilityGuard};
+use crate::window;
+use gpui::{Action, App, AsyncApp, Global, Image, MouseButton, SharedString, Task, rgb};
+use gpui_tray_core::icon_compose::IconComposer;
 use gpui_tray_core::platform_trait::PlatformTray;
-use gpui_tray_core::{Error, Result, RuntimeEvent, Tray};
+use gpui_tray_core::{
+    AnimatedIcon, ClickEvent, ClickHandler, ConfirmMenuSelection, Error, ErrorEvent,
+    EventQueuePolicy, EventQueueStats, FallbackActivation, Localizer, MenuBuildPolicy,
+    MenuToggleEvent, Notification, OpenUrl, QuitApp, Result, RuntimeEvent, SystemClock,
+    SystemTheme, ThemeChangedEvent, Tray, TrayActionRouter, TrayBatch, TrayClock, TrayFlash,
+    TrayInteractionStats, TrayMenuItem, TrayStorage, decode_icon, image_from_rgba,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
-#[cfg(target_os = "windows")]
-use gpui_tray_windows as platform_impl;
-
-#[cfg(target_os = "macos")]
-use gpui_tray_macos as platform_impl;
-
-#[cfg(target_os = "linux")]
-use gpui_tray_linux as platform_impl;
-
 struct TrayRuntime {
     backend: Arc<dyn PlatformTray>,
     current_tray: Option<Tray>,
+    update_scheduled: bool,
+    animation: Option<RunningAnimation>,
+    flash: Option<RunningFlash>,
+    update: Option<UpdateSurface>,
+    /// X11 window of the app, exported when the tray names none.
+    main_window: Option<u32>,
+    /// Desktop theme as last reported by the backend.
+    theme: Option<SystemTheme>,
+    stats: TrayInteractionStats,
+    /// Icons with the badge drawn on, so updates that leave the icon and the
+    /// count alone skip drawing it again.
+    badges: RefCell<BadgeCache>,
     event_pump_task: Option<Task<()>>,
 }
 
+/// Badged icons for one count, keyed by the id of the icon underneath.
+#[derive(Default)]
+struct BadgeCache {
+    count: u32,
+    icons: HashMap<u64, Image>,
+}
+
+impl BadgeCache {
+    /// Icons kept at most; longer animations draw the badge every frame.
+    const MAX_ICONS: usize = 256;
+}
+
+struct RunningAnimation {
+    icon: AnimatedIcon,
+    frame: usize,
+    _task: Task<()>,
+}
+
+struct RunningFlash {
+    flash: TrayFlash,
+    _task: Task<()>,
+}
+
 impl Global for TrayRuntime {}
 
 impl TrayRuntime {
     fn new(cx: &mut App) -> Result<Self> {
-        let backend: Arc<dyn PlatformTray> = platform_impl::create()?.into();
+        if shutdown::is_done(cx) {
+            return Err(Error::RuntimeClosed);
+        }
+        shutdown::register(cx);
+        let backend = match backend_override(cx) {
+            Some(backend) => backend,
+            None => platform_impl::create()?.into(),
+        };
+        if let Some(policy) = cx.try_global::<TrayEventPolicy>() {
+            backend.set_event_policy(policy.0);
+        }
         let event_pump_task = spawn_event_pump(cx, backend.clone());
         Ok(Self {
             backend,
             current_tray: None,
+            update_scheduled: false,
+            animation: None,
+            flash: None,
+            update: None,
+            main_window: None,
+            theme: None,
+            stats: TrayInteractionStats::default(),
+            badges: RefCell::default(),
             event_pump_task: Some(event_pump_task),
         })
     }
+
+    /// Returns the tray as the backend should see it, with the icon for the
+    /// desktop theme, the current animation frame in place of the static
+    /// icon, any flash on top, the badge drawn over the icon and the
+    /// available update added.
+    fn snapshot(&self, tray: &Tray) -> Tray {
+        let mut snapshot = tray.clone();
+        snapshot.linux_window_id = tray.linux_window_id.or(self.main_window);
+        if self.theme == Some(SystemTheme::Dark)
+            && let Some(dark_icon) = tray.dark_icon.as_ref()
+        {
+            snapshot.icon = Some(dark_icon.clone());
+        }
+        if let Some(running) = self.animation.as_ref() {
+            snapshot.icon = Some(running.icon.frames()[running.frame].image.clone());
+            snapshot.icon_name = None;
+        }
+        if let Some(running) = self.flash.as_ref() {
+            if let Some(icon) = running.flash.icon.as_ref() {
+                snapshot.icon = Some(icon.clone());
+                snapshot.icon_name = None;
+            }
+            if let Some(tooltip) = running.flash.tooltip.as_ref() {
+                snapshot.tooltip = Some(tooltip.clone());
+            }
+        }
+        if let Some(count) = snapshot.badge.filter(|count| *count > 0)
+            && let Some(icon) = snapshot.icon.as_ref()
+        {
+            match self.badged_icon(icon, count) {
+                Ok(badged) => {
+                    snapshot.icon = Some(badged);
+                    snapshot.icon_name = None;
+                }
+                Err(err) => log::warn!("failed to draw tray badge {count}: {err}"),
+            }
+        }
+        if let Some(update) = self.update.as_ref() {
+            snapshot.attention |= update.attention;
+            if update.menu_entry {
+                let info = update.info.clone();
+                let label = update.menu_label.clone();
+                let menu = snapshot.menu_builder.take();
+                snapshot.menu_builder = Some(Arc::new(move || {
+                    let mut items = vec![update_menu_item(&info, label.clone())];
+                    if let Some(menu) = menu.as_ref() {
+                        items.push(TrayMenuItem::separator());
+                        items.extend(menu());
+                    }
+                    items
+                }));
+            }
+        }
+        snapshot
+    }
+
+    /// Returns `icon` with a badge showing `count`, drawn once per icon and
+    /// count.
+    fn badged_icon(&self, icon: &Image, count: u32) -> Result<Image> {
+        let mut cache = self.badges.borrow_mut();
+        if cache.count != count {
+            cache.count = count;
+            cache.icons.clear();
+        }
+        if let Some(badged) = cache.icons.get(&icon.id()) {
+            return Ok(badged.clone());
+        }
+
+        let badged = IconComposer::new(icon, BADGE_ICON_SIZE)?
+            .count_badge(count, rgb(0xdc3232), rgb(0xffffff))
+            .finish()?;
+        if cache.icons.len() < BadgeCache::MAX_ICONS {
+            cache.icons.insert(icon.id(), badged.clone());
+        }
+        Ok(badged)
+    }
+
+    /// Starts, restarts or stops the icon animation to match `tray`.
+    fn sync_animation(&mut self, cx: &mut App, tray: &Tray) {
+        let animation = tray.animation.as_ref().filter(|_| tray.visible);
+        match animation {
+            Some(animation)
+                if self
+                    .animation
+                    .as_ref()
+                    .is_some_and(|running| running.icon.same_frames(animation)) => {}
+            Some(animation) => {
+                self.animation = Some(RunningAnimation {
+                    icon: animation.clone(),
+                    frame: 0,
+                    _task: spawn_animation(cx, animation.clone()),
+                });
+            }
+            None => self.animation = None,
+        }
+    }
 }
 
 impl Drop for TrayRuntime {
@@ -40,6 +197,433 @@ impl Drop for TrayRuntime {
     }
 }
 
+/// Removes the icon and shuts the backend down, for the quit observer.
+pub(crate) fn shut_down(cx: &mut App) {
+    if !cx.has_global::<TrayRuntime>() {
+        return;
+    }
+    let runtime = cx.remove_global::<TrayRuntime>();
+    if runtime.current_tray.is_some()
+        && let Err(err) = runtime.backend.remove_tray()
+    {
+        log::warn!("failed to remove the tray on quit: {err}");
+    }
+}
+
+/// Backend used in place of the platform's, set by `MockTray::install` or
+/// `web::install`.
+#[cfg(any(feature = "mock", feature = "web"))]
+pub(crate) struct TrayBackendOverride(pub Arc<dyn PlatformTray>);
+
+#[cfg(any(feature = "mock", feature = "web"))]
+impl Global for TrayBackendOverride {}
+
+#[cfg(any(feature = "mock", feature = "web"))]
+fn backend_override(cx: &App) -> Option<Arc<dyn PlatformTray>> {
+    cx.try_global::<TrayBackendOverride>()
+        .map(|backend| backend.0.clone())
+}
+
+#[cfg(not(any(feature = "mock", feature = "web")))]
+fn backend_override(_cx: &App) -> Option<Arc<dyn PlatformTray>> {
+    None
+}
+
+/// Edge length the badge is drawn at; backends scale the result down to
+/// their icon sizes.
+const BADGE_ICON_SIZE: u32 = 64;
+
+/// Edge length of the PNG returned by `current_icon_png`, large enough to
+/// inspect a tray icon by eye.
+const SNAPSHOT_ICON_SIZE: u32 = 64;
+
+struct TrayLocalizer(Arc<dyn Localizer>);
+
+impl Global for TrayLocalizer {}
+
+struct TrayPersistence(Arc<dyn TrayStorage>);
+
+impl Global for TrayPersistence {}
+
+struct TrayRouter(TrayActionRouter);
+
+impl Global for TrayRouter {}
+
+struct TrayEventPolicy(EventQueuePolicy);
+
+impl Global for TrayEventPolicy {}
+
+struct TrayTimeSource(Arc<dyn TrayClock>);
+
+impl Global for TrayTimeSource {}
+
+/// Returns the clock the tray's timers run on.
+pub(crate) fn clock(cx: &App) -> Arc<dyn TrayClock> {
+    cx.try_global::<TrayTimeSource>()
+        .map(|clock| clock.0.clone())
+        .unwrap_or_else(|| Arc::new(SystemClock))
+}
+
+fn localize(cx: &App, tray: &mut Tray) {
+    let localizer = cx.try_global::<TrayLocalizer>();
+    tray.localize(localizer.map(|localizer| localizer.0.as_ref()));
+}
+
+/// Resolves a text of the crate's own through the app's localizer, or
+/// `fallback` when there is none or it does not know `key`.
+pub(crate) fn localize_builtin(
+    cx: &App,
+    key: &str,
+    args: &[(SharedString, SharedString)],
+    fallback: impl FnOnce() -> String,
+) -> SharedString {
+    cx.try_global::<TrayLocalizer>()
+        .and_then(|localizer| localizer.0.localize(key, args))
+        .unwrap_or_else(fallback)
+        .into()
+}
+
+/// Fills in the saved check state of the entries `tray` does not set itself.
+fn restore_checked(cx: &App, tray: &mut Tray) {
+    let Some(storage) = cx.try_global::<TrayPersistence>() else {
+        return;
+    };
+    match storage.0.load() {
+        Ok(saved) => {
+            for (id, checked) in saved {
+                tray.menu_checked.entry(id).or_insert(checked);
+            }
+        }
+        Err(err) => log::warn!("failed to load saved menu states: {err}"),
+    }
+}
+
+fn save_checked(cx: &App, checked: &HashMap<SharedString, bool>) {
+    if let Some(storage) = cx.try_global::<TrayPersistence>()
+        && let Err(err) = storage.0.save(checked)
+    {
+        log::warn!("failed to save menu states: {err}");
+    }
+}
+
+/// Applies the latest tray state once the effects that made a burst of
+/// `OnIdle` updates have been handled.
+fn apply_deferred_update(cx: &mut App) {
+    if !cx.has_global::<TrayRuntime>() {
+        return;
+    }
+
+    let mut runtime = cx.remove_global::<TrayRuntime>();
+    runtime.update_scheduled = false;
+    let result = match runtime.current_tray.clone() {
+        Some(tray) => {
+            runtime.main_window = main_x11_window(cx);
+            runtime.sync_animation(cx, &tray);
+            runtime.backend.set_tray(runtime.snapshot(&tray))
+        }
+        None => Ok(()),
+    };
+    cx.set_global(runtime);
+
+    if let Err(err) = result {
+        log::error!("deferred tray update failed: {err}");
+        cx.dispatch_action(&ErrorEvent::new(err));
+    }
+}
+
+/// Returns the X11 id of the app's first window, if it runs on X11.
+#[cfg(target_os = "linux")]
+fn main_x11_window(cx: &mut App) -> Option<u32> {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+    let window = cx.windows().into_iter().next()?;
+    window
+        .update(cx, |_, window, _| {
+            match window.window_handle().ok()?.as_raw() {
+                RawWindowHandle::Xlib(handle) => Some(handle.window as u32),
+                RawWindowHandle::Xcb(handle) => Some(handle.window.get()),
+                _ => None,
+            }
+        })
+        .ok()
+        .flatten()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main_x11_window(_cx: &mut App) -> Option<u32> {
+    None
+}
+
+fn spawn_animation(cx: &mut App, animation: AnimatedIcon) -> Task<()> {
+    let clock = clock(cx);
+    cx.spawn(move |cx: &mut AsyncApp| {
+        let cx = cx.clone();
+        async move {
+            let mut frame = 0;
+            loop {
+                let delay = animation.frames()[frame].delay;
+                clock.sleep(delay, cx.background_executor()).await;
+
+                frame = (frame + 1) % animation.frames().len();
+                if cx
+                    .update(|app: &mut App| advance_animation(app, frame))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+    })
+}
+
+fn spawn_flash_end(cx: &mut App, duration: Duration) -> Task<()> {
+    let clock = clock(cx);
+    cx.spawn(move |cx: &mut AsyncApp| {
+        let cx = cx.clone();
+        async move {
+            clock.sleep(duration, cx.background_executor()).await;
+            let _ = cx.update(end_flash);
+        }
+    })
+}
+
+/// Drops the flash and shows the tray as it is now.
+fn end_flash(cx: &mut App) {
+    if !cx.has_global::<TrayRuntime>() {
+        return;
+    }
+
+    let runtime = cx.global_mut::<TrayRuntime>();
+    // Dropping the flash also drops this task; nothing may await after this.
+    runtime.flash = None;
+    let Some(tray) = runtime.current_tray.as_ref() else {
+        return;
+    };
+    if let Err(err) = runtime.backend.set_tray(runtime.snapshot(tray)) {
+        log::warn!("failed to restore tray after flash: {err}");
+    }
+}
+
+/// Shows or, with `None`, removes the update found by an `UpdateNotifier`.
+pub(crate) fn set_update(cx: &mut App, update: Option<UpdateSurface>) -> Result<()> {
+    if cx.tray().is_none() {
+        return Err(Error::NotFound);
+    }
+
+    let runtime = cx.global_mut::<TrayRuntime>();
+    runtime.update = update;
+    let Some(tray) = runtime.current_tray.as_ref() else {
+        return Err(Error::NotFound);
+    };
+    runtime.backend.set_tray(runtime.snapshot(tray))
+}
+
+fn advance_animation(cx: &mut App, frame: usize) {
+    if !cx.has_global::<TrayRuntime>() {
+        return;
+    }
+
+    let runtime = cx.global_mut::<TrayRuntime>();
+    let Some(running) = runtime.animation.as_mut() else {
+        return;
+    };
+    running.frame = frame;
+
+    let Some(tray) = runtime.current_tray.as_ref() else {
+        return;
+    };
+    // Only the icon changes, so the backend need not rebuild the menu.
+    let snapshot = runtime.snapshot(tray);
+    let result = match runtime.backend.set_icon(snapshot.icon.clone()) {
+        Err(Error::UnsupportedPlatform) => runtime.backend.set_tray(snapshot),
+        result => result,
+    };
+    if let Err(err) = result {
+        log::warn!("failed to show animation frame {frame}: {err}");
+    }
+}
+
+/// Runs the tray's `on_left_click` handler on a left click. Returns whether
+/// there was one.
+fn run_left_click(cx: &mut App, action: &dyn Action) -> bool {
+    let Some(click) = action.as_any().downcast_ref::<ClickEvent>() else {
+        return false;
+    };
+    if click.button != MouseButton::Left {
+        return false;
+    }
+    let Some(handler) = cx.tray().and_then(|tray| tray.left_click.clone()) else {
+        return false;
+    };
+
+    match handler {
+        ClickHandler::Action(action) => cx.dispatch_action(action.as_ref()),
+        ClickHandler::Callback(callback) => callback(click, cx),
+    }
+    true
+}
+
+/// Handles a left click nobody listens for according to the tray's
+/// [`FallbackActivation`]. Returns whether the click was consumed.
+fn apply_fallback_activation(cx: &mut App, action: &dyn Action) -> bool {
+    let Some(click) = action.as_any().downcast_ref::<ClickEvent>() else {
+        return false;
+    };
+    if click.button != MouseButton::Left || cx.is_action_available(action) {
+        return false;
+    }
+
+    let Some(runtime) = cx.try_global::<TrayRuntime>() else {
+        return false;
+    };
+    let Some(tray) = runtime.current_tray.as_ref() else {
+        return false;
+    };
+
+    match tray.fallback_activation {
+        FallbackActivation::None => false,
+        FallbackActivation::ShowMenu => {
+            if tray.menu_builder.is_none() {
+                return false;
+            }
+            if let Err(err) = runtime.backend.show_menu() {
+                log::debug!("fallback menu unavailable: {err}");
+            }
+            true
+        }
+        FallbackActivation::FocusMainWindow => {
+            let token = click.activation_token.clone();
+            if window::show(cx, token.as_ref()) {
+                return true;
+            }
+            cx.activate(true);
+            if let Some(window) = cx.windows().first() {
+                let _ = window.update(cx, |_, window, cx| {
+                    activate_window(window, cx, token.as_ref());
+                });
+            }
+            true
+        }
+    }
+}
+
+/// Opens the URL of a [`TrayMenuItem::open_url`](gpui_tray_core::TrayMenuItem::open_url)
+/// entry. Returns whether the action was consumed.
+fn open_url(cx: &mut App, action: &dyn Action) -> bool {
+    let Some(OpenUrl { url }) = action.as_any().downcast_ref::<OpenUrl>() else {
+        return false;
+    };
+    log::debug!("opening menu url {url}");
+    cx.open_url(url);
+    true
+}
+
+/// Quits on a [`QuitApp`] entry the app has no handler for. Returns whether
+/// the action was consumed.
+fn quit_fallback(cx: &mut App, action: &dyn Action) -> bool {
+    if !action.as_any().is::<QuitApp>() || cx.is_action_available(action) {
+        return false;
+    }
+    log::debug!("quitting from the tray menu");
+    cx.quit();
+    true
+}
+
+/// Records the new state of a toggled menu entry. The event is still
+/// dispatched to the app afterwards.
+fn track_menu_toggle(cx: &mut App, action: &dyn Action) {
+    let Some(MenuToggleEvent { id, checked }) = action.as_any().downcast_ref::<MenuToggleEvent>()
+    else {
+        return;
+    };
+    if let Err(err) = cx.set_menu_item_checked(id.clone(), *checked) {
+        log::warn!("failed to record menu toggle {id}: {err}");
+    }
+}
+
+/// Asks for confirmation of a [`ConfirmMenuSelection`] the app has no
+/// handler for. Returns whether the action was consumed.
+fn confirm_selection(cx: &mut App, action: &dyn Action) -> bool {
+    let Some(selection) = action.as_any().downcast_ref::<ConfirmMenuSelection>() else {
+        return false;
+    };
+    if cx.is_action_available(action) {
+        return false;
+    }
+    if let Err(err) = confirm::open(cx, selection) {
+        log::error!("failed to ask for confirmation: {err}");
+    }
+    true
+}
+
+/// Records the desktop theme and shows the icon for it. Returns whether the
+/// event was consumed, which is the case for the theme a backend reports
+/// when it starts.
+fn apply_theme(cx: &mut App, action: &dyn Action) -> bool {
+    let Some(ThemeChangedEvent { theme }) = action.as_any().downcast_ref::<ThemeChangedEvent>()
+    else {
+        return false;
+    };
+    if !cx.has_global::<TrayRuntime>() {
+        return false;
+    }
+
+    let runtime = cx.global_mut::<TrayRuntime>();
+    let previous = runtime.theme.replace(*theme);
+    if previous == Some(*theme) {
+        return true;
+    }
+    if let Some(tray) = runtime.current_tray.as_ref()
+        && tray.dark_icon.is_some()
+        && let Err(err) = runtime.backend.set_tray(runtime.snapshot(tray))
+    {
+        log::warn!("failed to show the icon for the {theme:?} theme: {err}");
+    }
+    previous.is_none()
+}
+
+/// Dispatches the actions the [`TrayActionRouter`] maps `action` to. Returns
+/// whether any route matched.
+fn route_action(cx: &mut App, action: &dyn Action) -> bool {
+    let Some(router) = cx.try_global::<TrayRouter>() else {
+        return false;
+    };
+    let routed = router.0.resolve(action);
+    if routed.is_empty() {
+        return false;
+    }
+    for routed in routed {
+        log::debug!("routing {} to {}", action.name(), routed.name());
+        // Routed actions are not routed again, so a route can't loop.
+        handle_unrouted_action(cx, routed.as_ref());
+    }
+    true
+}
+
+/// Handles an action from the backend, or from a confirmed menu selection,
+/// and dispatches it to the app unless the runtime consumed it.
+pub(crate) fn handle_backend_action(cx: &mut App, action: &dyn Action) {
+    track_menu_toggle(cx, action);
+    if focus::suppress_click(cx, action) {
+        return;
+    }
+    if !apply_theme(cx, action) && !route_action(cx, action) {
+        handle_unrouted_action(cx, action);
+    }
+}
+
+fn handle_unrouted_action(cx: &mut App, action: &dyn Action) {
+    if !run_left_click(cx, action)
+        && !apply_fallback_activation(cx, action)
+        && !confirm_selection(cx, action)
+        && !open_url(cx, action)
+        && !window::show_requested(cx, action)
+        && !quit_fallback(cx, action)
+    {
+        cx.dispatch_action(action)
+    }
+}
+
 fn spawn_event_pump(cx: &mut App, backend: Arc<dyn PlatformTray>) -> Task<()> {
     cx.spawn(move |cx: &mut AsyncApp| {
         let cx = cx.clone();
@@ -50,7 +634,42 @@ fn spawn_event_pump(cx: &mut App, backend: Arc<dyn PlatformTray>) -> Task<()> {
                         Ok(Some(RuntimeEvent::Action(action))) => {
                             log::debug!("dispatching backend action {}", action.name());
                             if cx
-                                .update(|app: &mut App| app.dispatch_action(action.as_ref()))
+                                .update(|app: &mut App| {
+                                    if app.has_global::<TrayRuntime>() {
+                                        app.global_mut::<TrayRuntime>()
+                                            .stats
+                                            .record(action.as_ref());
+                                    }
+                                    handle_backend_action(app, action.as_ref());
+                                })
+                                .is_err()
+                            {
+                                return;
+                            }
+                        }
+                        Ok(Some(RuntimeEvent::MenuSelection(actions))) => {
+                            log::debug!("dispatching menu selection of {} actions", actions.len());
+                            if cx
+                                .update(|app: &mut App| {
+                                    if app.has_global::<TrayRuntime>() {
+                                        app.global_mut::<TrayRuntime>()
+                                            .stats
+                                            .record_menu_selection();
+                                    }
+                                    for action in &actions {
+                                        handle_backend_action(app, action.as_ref());
+                                    }
+                                })
+                                .is_err()
+                            {
+                                return;
+                            }
+                        }
+                        Ok(Some(RuntimeEvent::Error(err))) => {
+                            log::error!("tray backend reported: {err}");
+                            let event = ErrorEvent::new(err);
+                            if cx
+                                .update(|app: &mut App| app.dispatch_action(&event))
                                 .is_err()
                             {
                                 return;
@@ -77,11 +696,37 @@ pub trait TrayAppContext {
     fn set_tray(&mut self, tray: Tray) -> Result<()>;
     fn tray(&self) -> Option<&Tray>;
     fn update_tray(&mut self, f: impl FnOnce(&mut Tray)) -> Result<Tray>;
+    fn batch_tray(&mut self, f: impl FnOnce(&mut TrayBatch)) -> Result<()>;
     fn remove_tray(&mut self) -> Result<()>;
+    fn set_localizer(&mut self, localizer: impl Localizer + 'static);
+    fn set_tray_storage(&mut self, storage: impl TrayStorage + 'static);
+    fn set_tray_router(&mut self, router: TrayActionRouter);
+    fn relocalize(&mut self) -> Result<()>;
+    fn set_menu_item_count(&mut self, id: impl Into<SharedString>, count: u32) -> Result<()>;
+    fn set_menu_item_visible(&mut self, id: impl Into<SharedString>, visible: bool) -> Result<()>;
+    fn set_menu_item_enabled(&mut self, id: impl Into<SharedString>, enabled: bool) -> Result<()>;
+    fn set_menu_item_checked(&mut self, id: impl Into<SharedString>, checked: bool) -> Result<()>;
+    fn flush_tray(&self) -> Task<Result<()>>;
+    fn tray_notify(&self, notification: Notification) -> Result<()>;
+    fn flash_tray(&mut self, flash: TrayFlash) -> Result<()>;
+    fn start_tray_animation(&mut self, animation: AnimatedIcon) -> Result<()>;
+    fn stop_tray_animation(&mut self) -> Result<()>;
+    fn set_tray_event_policy(&mut self, policy: EventQueuePolicy);
+    fn set_tray_clock(&mut self, clock: impl TrayClock + 'static);
+    fn tray_event_stats(&self) -> Option<EventQueueStats>;
+    fn tray_interaction_stats(&self) -> Option<TrayInteractionStats>;
+    fn system_theme(&self) -> Option<SystemTheme>;
+    fn current_icon_png(&self) -> Option<Vec<u8>>;
+    fn require_tray(&mut self) -> TrayVisibilityGuard;
 }
 
 impl TrayAppContext for App {
-    fn set_tray(&mut self, tray: Tray) -> Result<()> {
+    fn set_tray(&mut self, mut tray: Tray) -> Result<()> {
+        if let Some(visible) = visibility::required(self) {
+            tray.visible = visible;
+        }
+        localize(self, &mut tray);
+        restore_checked(self, &mut tray);
         log::debug!(
             "set_tray visible={}, has_icon={}, has_menu={}",
             tray.visible,
@@ -94,10 +739,17 @@ impl TrayAppContext for App {
             TrayRuntime::new(self)?
         };
 
-        runtime.backend.set_tray(tray.clone())?;
+        runtime.main_window = main_x11_window(self);
+        runtime.sync_animation(self, &tray);
+        if let Err(err) = runtime.backend.set_tray(runtime.snapshot(&tray)) {
+            self.set_global(runtime);
+            return Err(err);
+        }
         runtime.current_tray = Some(tray);
 
         self.set_global(runtime);
+        window::sync_with_tray(self);
+        focus::sync_with_tray(self);
         Ok(())
     }
 
@@ -117,11 +769,31 @@ impl TrayAppContext for App {
             return Err(Error::NotFound);
         };
 
+        let checked = tray.menu_checked.clone();
         f(tray);
+        localize(self, tray);
+        if tray.menu_checked != checked {
+            save_checked(self, &tray.menu_checked);
+        }
         let updated = tray.clone();
-        runtime.backend.set_tray(updated.clone())?;
+        if updated.menu_build_policy == MenuBuildPolicy::OnIdle {
+            if !runtime.update_scheduled {
+                runtime.update_scheduled = true;
+                self.defer(apply_deferred_update);
+            }
+        } else {
+            runtime.main_window = main_x11_window(self);
+            runtime.sync_animation(self, &updated);
+            if let Err(err) = runtime.backend.set_tray(runtime.snapshot(&updated)) {
+                // Keep the change, so the next update sends it along.
+                self.set_global(runtime);
+                return Err(err);
+            }
+        }
 
         self.set_global(runtime);
+        window::sync_with_tray(self);
+        focus::sync_with_tray(self);
         log::debug!(
             "update_tray done visible={}, has_icon={}, has_menu={}",
             updated.visible,
@@ -131,20 +803,174 @@ impl TrayAppContext for App {
         Ok(updated)
     }
 
+    fn batch_tray(&mut self, f: impl FnOnce(&mut TrayBatch)) -> Result<()> {
+        self.update_tray(|tray| f(&mut TrayBatch::new(tray)))
+            .map(|_| ())
+    }
+
     fn remove_tray(&mut self) -> Result<()> {
         if !self.has_global::<TrayRuntime>() {
             return Err(Error::NotFound);
         }
 
-        let mut runtime = self.remove_global::<TrayRuntime>();
+        let runtime = self.remove_global::<TrayRuntime>();
         if runtime.current_tray.is_none() {
             self.set_global(runtime);
             return Err(Error::NotFound);
         }
 
-        runtime.backend.remove_tray()?;
-        runtime.current_tray = None;
-        self.set_global(runtime);
+        if let Err(err) = runtime.backend.remove_tray() {
+            self.set_global(runtime);
+            return Err(err);
+        }
+
+        // Dropping the runtime shuts the backend down, releasing the worker
+        // thread and its window or bus connection; the next `set_tray`
+        // starts a fresh one.
+        drop(runtime);
+        window::sync_with_tray(self);
+        focus::sync_with_tray(self);
         Ok(())
     }
+
+    fn set_localizer(&mut self, localizer: impl Localizer + 'static) {
+        self.set_global(TrayLocalizer(Arc::new(localizer)));
+    }
+
+    fn set_tray_storage(&mut self, storage: impl TrayStorage + 'static) {
+        self.set_global(TrayPersistence(Arc::new(storage)));
+        if self.tray().is_some() {
+            let mut saved = Tray::new();
+            restore_checked(self, &mut saved);
+            let _ = self.update_tray(|tray| {
+                for (id, checked) in saved.menu_checked {
+                    tray.menu_checked.entry(id).or_insert(checked);
+                }
+            });
+        }
+    }
+
+    fn set_tray_router(&mut self, router: TrayActionRouter) {
+        self.set_global(TrayRouter(router));
+    }
+
+    fn relocalize(&mut self) -> Result<()> {
+        match self.tray().cloned() {
+            Some(tray) => self.set_tray(tray),
+            None => Ok(()),
+        }
+    }
+
+    fn set_menu_item_count(&mut self, id: impl Into<SharedString>, count: u32) -> Result<()> {
+        let id = id.into();
+        self.update_tray(|tray| {
+            tray.menu_counts.insert(id, count);
+        })
+        .map(|_| ())
+    }
+
+    fn set_menu_item_visible(&mut self, id: impl Into<SharedString>, visible: bool) -> Result<()> {
+        let id = id.into();
+        self.update_tray(|tray| {
+            tray.menu_visibility.insert(id, visible);
+        })
+        .map(|_| ())
+    }
+
+    fn set_menu_item_enabled(&mut self, id: impl Into<SharedString>, enabled: bool) -> Result<()> {
+        let id = id.into();
+        self.update_tray(|tray| {
+            tray.menu_enabled.insert(id, enabled);
+        })
+        .map(|_| ())
+    }
+
+    fn set_menu_item_checked(&mut self, id: impl Into<SharedString>, checked: bool) -> Result<()> {
+        let id = id.into();
+        self.update_tray(|tray| tray.set_menu_item_checked(id, checked))
+            .map(|_| ())
+    }
+
+    fn flush_tray(&self) -> Task<Result<()>> {
+        let Some(runtime) = self.try_global::<TrayRuntime>() else {
+            return Task::ready(Ok(()));
+        };
+
+        let backend = runtime.backend.clone();
+        self.background_executor()
+            .spawn(async move { backend.flush() })
+    }
+
+    fn tray_notify(&self, notification: Notification) -> Result<()> {
+        let runtime = self.try_global::<TrayRuntime>().ok_or(Error::NotFound)?;
+        if runtime.current_tray.is_none() {
+            return Err(Error::NotFound);
+        }
+        runtime.backend.notify(notification)
+    }
+
+    fn flash_tray(&mut self, flash: TrayFlash) -> Result<()> {
+        if self.tray().is_none() {
+            return Err(Error::NotFound);
+        }
+
+        // Replacing a running flash drops its task, so only the newest one
+        // gets to revert the tray.
+        let task = spawn_flash_end(self, flash.duration);
+        let runtime = self.global_mut::<TrayRuntime>();
+        runtime.flash = Some(RunningFlash { flash, _task: task });
+        let Some(tray) = runtime.current_tray.as_ref() else {
+            return Err(Error::NotFound);
+        };
+        runtime.backend.set_tray(runtime.snapshot(tray))
+    }
+
+    fn start_tray_animation(&mut self, animation: AnimatedIcon) -> Result<()> {
+        self.update_tray(|tray| tray.animation = Some(animation))
+            .map(|_| ())
+    }
+
+    fn stop_tray_animation(&mut self) -> Result<()> {
+        self.update_tray(|tray| tray.animation = None).map(|_| ())
+    }
+
+    fn set_tray_event_policy(&mut self, policy: EventQueuePolicy) {
+        if let Some(runtime) = self.try_global::<TrayRuntime>() {
+            runtime.backend.set_event_policy(policy);
+        }
+        self.set_global(TrayEventPolicy(policy));
+    }
+
+    // Timers that are already running keep the clock they started with.
+    fn set_tray_clock(&mut self, clock: impl TrayClock + 'static) {
+        self.set_global(TrayTimeSource(Arc::new(clock)));
+    }
+
+    fn tray_event_stats(&self) -> Option<EventQueueStats> {
+        self.try_global::<TrayRuntime>()
+            .map(|runtime| runtime.backend.event_stats())
+    }
+
+    fn tray_interaction_stats(&self) -> Option<TrayInteractionStats> {
+        self.try_global::<TrayRuntime>()
+            .map(|runtime| runtime.stats)
+    }
+
+    fn system_theme(&self) -> Option<SystemTheme> {
+        self.try_global::<TrayRuntime>()
+            .and_then(|runtime| runtime.theme)
+    }
+
+    fn current_icon_png(&self) -> Option<Vec<u8>> {
+        let runtime = self.try_global::<TrayRuntime>()?;
+        let tray = runtime.snapshot(runtime.current_tray.as_ref()?);
+        let icon = decode_icon(tray.icon.as_ref()?, SNAPSHOT_ICON_SIZE).ok()?;
+        image_from_rgba(icon.size, icon.size, icon.rgba)
+            .ok()
+            .map(|image| image.bytes)
+    }
+
+    fn require_tray(&mut self) -> TrayVisibilityGuard {
+        visibility::require(self)
+    }
 }
@@ -0,0 +1,338 @@
+//! In-memory tray backend for tests without a desktop session.
+//!
+//! Enabled with the `mock` feature. Once installed, every tray the app sets
+//! goes to the mock instead of the platform, where tests can inspect it and
+//! inject the events a user would cause:
+//!
+//! ```rust,ignore
+//! #[gpui::test]
+//! fn quit_entry_quits(cx: &mut TestAppContext) {
+//!     let mock = cx.update(MockTray::install);
+//!     cx.update(|cx| cx.set_tray(app_tray())).unwrap();
+//!     assert_eq!(mock.tray().unwrap().tooltip, Some("Idle".into()));
+//!
+//!     mock.select_menu_item("quit").unwrap();
+//!     // Events are picked up by the runtime's next poll.
+//!     cx.executor().advance_clock(Duration::from_millis(10));
+//! }
+//! ```
+//!
+//! Animations, flashes and the popover's reopen grace period run on the
+//! tray's clock, which [`ManualClock`] lets tests move by hand:
+//!
+//! ```rust,ignore
+//! let clock = cx.update(ManualClock::install);
+//! cx.update(|cx| cx.flash_tray(TrayFlash::new(Duration::from_secs(2)).tooltip("Copied!")))
+//!     .unwrap();
+//! clock.advance(Duration::from_secs(2));
+//! cx.run_until_parked();
+//! assert_eq!(mock.tray().unwrap().tooltip, Some("Idle".into()));
+//! ```
+
+use crate::TrayAppContext;
+use crate::manager::TrayBackendOverride;
+use gpui::{Action, App, BackgroundExecutor, Image, MouseButton, Point};
+use gpui_tray_core::platform_trait::PlatformTray;
+use gpui_tray_core::{
+    ClickEvent, ClockSleep, DoubleClickEvent, Error, EventQueuePolicy, EventQueueStats,
+    EventReceiver, EventSender, MenuItemKind, MenuOpenedEvent, Notification,
+    NotificationClickedEvent, Result, RuntimeEvent, ScrollDelta, ScrollEvent, ScrollOrientation,
+    Tray, TrayClock, TrayMenuItem, event_queue,
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// Handle to the in-memory backend; clones share the same state.
+#[derive(Clone)]
+pub struct MockTray {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<MockState>,
+    event_tx: EventSender,
+    event_rx: EventReceiver,
+}
+
+#[derive(Default)]
+struct MockState {
+    tray: Option<Tray>,
+    notifications: Vec<Notification>,
+    menu_requests: usize,
+}
+
+impl MockTray {
+    /// Routes every tray set from now on to a new mock and returns it.
+    ///
+    /// Install it before the first `set_tray`; a tray that is already set
+    /// keeps its backend until it is removed.
+    pub fn install(cx: &mut App) -> Self {
+        let (event_tx, event_rx) = event_queue(EventQueuePolicy::default());
+        let mock = Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(MockState::default()),
+                event_tx,
+                event_rx,
+            }),
+        };
+        cx.set_global(TrayBackendOverride(Arc::new(mock.clone())));
+        mock
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns the tray as the backend last received it, with animation
+    /// frame, badge and update entry applied, or `None` once removed.
+    pub fn tray(&self) -> Option<Tray> {
+        self.state().tray.clone()
+    }
+
+    /// Returns the context menu the platform would show now.
+    pub fn menu(&self) -> Vec<TrayMenuItem> {
+        self.tray()
+            .map(|tray| tray.build_menu())
+            .unwrap_or_default()
+    }
+
+    /// Returns every notification shown so far, oldest first.
+    pub fn notifications(&self) -> Vec<Notification> {
+        self.state().notifications.clone()
+    }
+
+    /// Returns how often the app asked for the context menu to open. Each
+    /// request also reports a [`MenuOpenedEvent`], as on Windows.
+    pub fn menu_requests(&self) -> usize {
+        self.state().menu_requests
+    }
+
+    /// Clicks the icon with `button`.
+    pub fn click(&self, button: MouseButton) {
+        self.send_event(ClickEvent {
+            button,
+            position: Point::default(),
+            activation_token: None,
+        });
+    }
+
+    /// Double-clicks the icon with `button`, after the click of the second
+    /// press like on Windows.
+    pub fn double_click(&self, button: MouseButton) {
+        self.click(button);
+        self.send_event(DoubleClickEvent {
+            button,
+            position: Point::default(),
+        });
+    }
+
+    /// Scrolls over the icon by `lines` wheel notches; positive is down.
+    pub fn scroll(&self, lines: f32) {
+        self.send_event(ScrollEvent {
+            delta: ScrollDelta {
+                lines,
+                precise: false,
+                orientation: ScrollOrientation::Vertical,
+            },
+        });
+    }
+
+    /// Selects the menu entry `id` as a user would, including the toggle
+    /// and selection events. Disabled entries ignore the selection.
+    ///
+    /// Fails with [`Error::NotFound`] when the current menu has no visible
+    /// entry with that id.
+    pub fn select_menu_item(&self, id: &str) -> Result<()> {
+        let menu = self.menu();
+        let item = find_item(&menu, id).ok_or(Error::NotFound)?;
+        if item.enabled {
+            self.shared
+                .event_tx
+                .send(RuntimeEvent::MenuSelection(item.selection_actions()));
+        }
+        Ok(())
+    }
+
+    /// Clicks the last notification.
+    pub fn click_notification(&self) {
+        self.send_event(NotificationClickedEvent);
+    }
+
+    /// Sends `event` to the app as if the backend had reported it.
+    pub fn send_event(&self, event: impl Action) {
+        self.shared
+            .event_tx
+            .send(RuntimeEvent::Action(Box::new(event)));
+    }
+
+    /// Reports `err` as a background failure of the backend.
+    pub fn send_error(&self, err: Error) {
+        self.shared.event_tx.send(RuntimeEvent::Error(err));
+    }
+}
+
+fn find_item<'a>(items: &'a [TrayMenuItem], id: &str) -> Option<&'a TrayMenuItem> {
+    items.iter().filter(|item| item.visible).find_map(|item| {
+        if item
+            .id
+            .as_ref()
+            .is_some_and(|item_id| item_id.as_ref() == id)
+        {
+            return Some(item);
+        }
+        match &item.kind {
+            MenuItemKind::Submenu(children) => find_item(children, id),
+            _ => None,
+        }
+    })
+}
+
+impl PlatformTray for MockTray {
+    fn set_tray(&self, tray: Tray) -> Result<()> {
+        self.state().tray = Some(tray);
+        Ok(())
+    }
+
+    fn set_icon(&self, icon: Option<Image>) -> Result<()> {
+        let mut state = self.state();
+        let tray = state.tray.as_mut().ok_or(Error::NotFound)?;
+        tray.icon = icon;
+        Ok(())
+    }
+
+    fn remove_tray(&self) -> Result<()> {
+        self.state().tray = None;
+        Ok(())
+    }
+
+    fn show_menu(&self) -> Result<()> {
+        self.state().menu_requests += 1;
+        self.shared
+            .event_tx
+            .send(RuntimeEvent::Action(Box::new(MenuOpenedEvent)));
+        Ok(())
+    }
+
+    fn notify(&self, notification: Notification) -> Result<()> {
+        self.state().notifications.push(notification);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn try_recv_event(&self) -> Result<Option<RuntimeEvent>> {
+        self.shared.event_rx.try_recv()
+    }
+
+    fn set_event_policy(&self, policy: EventQueuePolicy) {
+        self.shared.event_rx.set_policy(policy);
+    }
+
+    fn event_stats(&self) -> EventQueueStats {
+        self.shared.event_rx.stats()
+    }
+
+    // The mock outlives tray runtimes, so the next `set_tray` can use it
+    // again.
+    fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Tray clock that only moves when a test advances it; clones share the
+/// same time.
+#[derive(Clone)]
+pub struct ManualClock {
+    time: Arc<Mutex<ManualTime>>,
+}
+
+struct ManualTime {
+    start: Instant,
+    elapsed: Duration,
+    /// Timers waiting for the clock to move.
+    sleepers: Vec<Waker>,
+}
+
+impl ManualClock {
+    /// Makes a clock standing still at the current time the tray's clock
+    /// and returns it.
+    ///
+    /// Install it before starting the timers it should control; running
+    /// ones keep the clock they started with.
+    pub fn install(cx: &mut App) -> Self {
+        let clock = Self {
+            time: Arc::new(Mutex::new(ManualTime {
+                start: Instant::now(),
+                elapsed: Duration::ZERO,
+                sleepers: Vec::new(),
+            })),
+        };
+        cx.set_tray_clock(clock.clone());
+        clock
+    }
+
+    fn time(&self) -> MutexGuard<'_, ManualTime> {
+        self.time
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Moves the clock forward by `duration`. The timers that expire run on
+    /// the app's next turn, such as `TestAppContext::run_until_parked`.
+    pub fn advance(&self, duration: Duration) {
+        let sleepers = {
+            let mut time = self.time();
+            time.elapsed += duration;
+            std::mem::take(&mut time.sleepers)
+        };
+        // Timers that are not due yet wait again when polled.
+        for sleeper in sleepers {
+            sleeper.wake();
+        }
+    }
+
+    /// Returns how far the clock has been advanced.
+    pub fn elapsed(&self) -> Duration {
+        self.time().elapsed
+    }
+}
+
+impl TrayClock for ManualClock {
+    fn now(&self) -> Instant {
+        let time = self.time();
+        time.start + time.elapsed
+    }
+
+    fn sleep(&self, duration: Duration, _executor: &BackgroundExecutor) -> ClockSleep {
+        Box::pin(ManualSleep {
+            clock: self.clone(),
+            deadline: self.elapsed() + duration,
+        })
+    }
+}
+
+struct ManualSleep {
+    clock: ManualClock,
+    deadline: Duration,
+}
+
+impl Future for ManualSleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut time = self.clock.time();
+        if time.elapsed >= self.deadline {
+            return Poll::Ready(());
+        }
+        time.sleepers.push(cx.waker().clone());
+        Poll::Pending
+    }
+}
@@ -0,0 +1,206 @@
+//! Borderless window that opens next to the tray icon.
+//!
+//! ```rust,ignore
+//! cx.on_action(|event: &ClickEvent, cx| {
+//!     if event.button == MouseButton::Left {
+//!         let popover = TrayPopover::new(size(px(320.), px(420.)));
+//!         if let Err(err) = popover.toggle(cx, event.position, |_, cx| cx.new(|_| Panel)) {
+//!             log::error!("popover failed: {err}");
+//!         }
+//!     }
+//! });
+//! ```
+//!
+//! The window is placed on the side of the icon away from the nearest screen
+//! edge, which is where the taskbar or panel sits, and kept within that
+//! screen. Wayland compositors do not let clients position windows, so there
+//! the compositor decides where it appears.
+
+use crate::manager;
+use gpui::{
+    AnyWindowHandle, App, Bounds, DisplayId, Entity, Global, Pixels, Point, Render, Size, Window,
+    WindowBounds, WindowKind, WindowOptions, point, px,
+};
+use gpui_tray_core::{BackendError, Error, Result};
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// A click on the icon that dismissed the popover by taking focus arrives
+/// right after the dismissal; it should not open the popover again.
+const REOPEN_GRACE: Duration = Duration::from_millis(300);
+
+/// Builder for the tray popover window.
+#[derive(Clone, Copy, Debug)]
+pub struct TrayPopover {
+    size: Size<Pixels>,
+    gap: Pixels,
+    dismiss_on_blur: bool,
+}
+
+/// The popover that is currently open, if any.
+struct OpenPopover {
+    window: AnyWindowHandle,
+    /// When the popover last closed itself after losing focus.
+    dismissed_at: Rc<Cell<Option<Instant>>>,
+}
+
+impl Global for OpenPopover {}
+
+impl TrayPopover {
+    /// Creates a popover of `size` logical pixels.
+    pub fn new(size: Size<Pixels>) -> Self {
+        Self {
+            size,
+            gap: px(8.),
+            dismiss_on_blur: true,
+        }
+    }
+
+    /// Sets the distance between the popover and the icon.
+    pub fn gap(mut self, gap: Pixels) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// Sets whether the popover closes when it loses focus. On by default.
+    pub fn dismiss_on_blur(mut self, dismiss_on_blur: bool) -> Self {
+        self.dismiss_on_blur = dismiss_on_blur;
+        self
+    }
+
+    /// Opens the popover next to `anchor`, or closes it if it is open.
+    ///
+    /// `anchor` is a position from a tray event, such as
+    /// [`ClickEvent::position`](crate::ClickEvent). `build` creates the root
+    /// view like in [`App::open_window`].
+    pub fn toggle<V: Render + 'static>(
+        &self,
+        cx: &mut App,
+        anchor: Point<f32>,
+        build: impl FnOnce(&mut Window, &mut App) -> Entity<V>,
+    ) -> Result<()> {
+        let now = manager::clock(cx).now();
+        if let Some(open) = cx.try_global::<OpenPopover>() {
+            let window = open.window;
+            let just_dismissed = open
+                .dismissed_at
+                .get()
+                .is_some_and(|at| now.saturating_duration_since(at) < REOPEN_GRACE);
+            if close(cx, window) || just_dismissed {
+                cx.remove_global::<OpenPopover>();
+                return Ok(());
+            }
+        }
+        self.open(cx, anchor, build)
+    }
+
+    /// Opens the popover next to `anchor`, closing any popover already open.
+    pub fn open<V: Render + 'static>(
+        &self,
+        cx: &mut App,
+        anchor: Point<f32>,
+        build: impl FnOnce(&mut Window, &mut App) -> Entity<V>,
+    ) -> Result<()> {
+        Self::close(cx);
+
+        #[cfg(target_os = "windows")]
+        let anchor = crate::platform_impl::logical_position(anchor);
+        let (bounds, display_id) = self.bounds(cx, point(px(anchor.x), px(anchor.y)));
+
+        let options = WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(bounds)),
+            titlebar: None,
+            focus: true,
+            show: true,
+            kind: WindowKind::PopUp,
+            is_movable: false,
+            is_resizable: false,
+            is_minimizable: false,
+            display_id,
+            ..Default::default()
+        };
+        let window = cx.open_window(options, build).map_err(|err| {
+            Error::Backend(BackendError::platform("open_window", err.to_string()))
+        })?;
+
+        let dismissed_at = Rc::new(Cell::new(None));
+        if self.dismiss_on_blur {
+            let dismissed = dismissed_at.clone();
+            let clock = manager::clock(cx);
+            let _ = window.update(cx, |_, window, cx| {
+                cx.observe_window_activation(window, move |_, window, _| {
+                    if !window.is_window_active() {
+                        dismissed.set(Some(clock.now()));
+                        window.remove_window();
+                    }
+                })
+                .detach();
+            });
+        }
+
+        cx.set_global(OpenPopover {
+            window: window.into(),
+            dismissed_at,
+        });
+        Ok(())
+    }
+
+    /// Closes the popover if it is open.
+    pub fn close(cx: &mut App) {
+        if let Some(open) = cx.try_global::<OpenPopover>() {
+            let window = open.window;
+            close(cx, window);
+            cx.remove_global::<OpenPopover>();
+        }
+    }
+
+    /// Places the popover on the side of `anchor` facing away from the
+    /// nearest edge of its screen, clamped to that screen.
+    fn bounds(&self, cx: &App, anchor: Point<Pixels>) -> (Bounds<Pixels>, Option<DisplayId>) {
+        let display = cx
+            .displays()
+            .into_iter()
+            .find(|display| display.bounds().contains(&anchor))
+            .or_else(|| cx.primary_display());
+        let Some(display) = display else {
+            return (Bounds::new(anchor, self.size), None);
+        };
+
+        let screen = display.bounds();
+        let Size { width, height } = self.size;
+        let gap = self.gap;
+        let edges = [
+            anchor.y - screen.top(),
+            screen.bottom() - anchor.y,
+            anchor.x - screen.left(),
+            screen.right() - anchor.x,
+        ];
+        let nearest = (0..edges.len())
+            .min_by(|a, b| edges[*a].partial_cmp(&edges[*b]).unwrap_or(Ordering::Equal))
+            .unwrap_or(1);
+        let origin = match nearest {
+            // Top panel: below the icon.
+            0 => point(anchor.x - width / 2., anchor.y + gap),
+            // Bottom taskbar: above the icon.
+            1 => point(anchor.x - width / 2., anchor.y - gap - height),
+            // Left taskbar: right of the icon.
+            2 => point(anchor.x + gap, anchor.y - height / 2.),
+            _ => point(anchor.x - gap - width, anchor.y - height / 2.),
+        };
+
+        let origin = point(
+            origin.x.min(screen.right() - width).max(screen.left()),
+            origin.y.min(screen.bottom() - height).max(screen.top()),
+        );
+        (Bounds::new(origin, self.size), Some(display.id()))
+    }
+}
+
+/// Closes `window`, returning whether it was still open.
+fn close(cx: &mut App, window: AnyWindowHandle) -> bool {
+    window
+        .update(cx, |_, window, _| window.remove_window())
+        .is_ok()
+}
@@ -0,0 +1,17 @@
+//! The types most tray apps need, in one import.
+//!
+//! ```rust,ignore
+//! use gpui_tray::prelude::*;
+//! ```
+
+// Also brings in `#[derive(TrayMenu)]` with the `derive` feature.
+pub use crate::{ShowManagedWindow, TrayAppContext, TrayMenu, TraySupport, TrayWindowExt};
+pub use gpui::{Image, ImageFormat, MenuItem, MouseButton};
+pub use gpui_tray_core::{
+    AnimatedIcon, ClickEvent, DoubleClickEvent, Error, ErrorEvent, FallbackActivation,
+    HelpRequestedEvent, HostRestartedEvent, MenuBuildPolicy, MenuItemKind, MenuOpenedEvent,
+    MenuRole, MenuSelectEvent, MenuTemplate, MenuToggleEvent, MenuTrigger, Notification,
+    NotificationClickedEvent, NotificationIcon, NotificationOptions, Result, ScrollDelta,
+    ScrollEvent, ScrollOrientation, TooltipHiddenEvent, TooltipShownEvent, Tray, TrayFlash,
+    TrayMenuItem, TrayPreset, TrayStatus, image_from_bytes,
+};
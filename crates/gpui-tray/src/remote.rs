@@ -0,0 +1,328 @@
+//! Local IPC channel for updating the tray from helper processes.
+//!
+//! Enabled with the `remote-control` feature. The app listens on a Unix
+//! domain socket; clients write one JSON command per line and read one JSON
+//! reply per line:
+//!
+//! ```text
+//! > {"command": "set_tooltip", "tooltip": "Syncing 3 files"}
+//! < {"ok": true}
+//! > {"command": "set_icon_state", "state": "busy"}
+//! < {"ok": false, "error": "unknown icon state `busy`"}
+//! > {"command": "notify", "title": "Backup", "body": "Backup finished"}
+//! < {"ok": true}
+//! ```
+//!
+//! ```rust,ignore
+//! RemoteControl::new("/run/user/1000/my-app-tray.sock")
+//!     .icon_state("idle", idle_icon)
+//!     .icon_state("busy", busy_icon)
+//!     .start(cx)?;
+//! ```
+//!
+//! The socket is only accessible to the current user. A command longer than
+//! 64 KiB is answered with an error and ends the connection. Windows has no
+//! Unix sockets in the standard library, so `start` fails with
+//! [`Error::UnsupportedPlatform`] there.
+
+use crate::TrayAppContext;
+use gpui::{App, AsyncApp, Global, Image, SharedString, Task};
+use gpui_tray_core::{Error, Notification, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+/// Longest command line accepted, newline included.
+const MAX_LINE_LEN: usize = 64 * 1024;
+
+/// Builder for the remote control socket.
+#[derive(Clone, Debug)]
+pub struct RemoteControl {
+    path: PathBuf,
+    icon_states: HashMap<SharedString, Image>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum RemoteCommand {
+    SetIconState { state: String },
+    SetTooltip { tooltip: Option<String> },
+    Notify { title: String, body: String },
+}
+
+#[derive(Serialize, Debug)]
+struct Reply {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+type Request = (RemoteCommand, Sender<Reply>);
+
+/// Keeps the command pump alive, and stops listening and removes the socket
+/// file with the app.
+struct RemoteControlServer {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+    _task: Task<()>,
+}
+
+impl Global for RemoteControlServer {}
+
+impl Drop for RemoteControlServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        // Wakes the accept loop so it sees `stop` and closes the socket.
+        #[cfg(unix)]
+        let _ = std::os::unix::net::UnixStream::connect(&self.path);
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl RemoteControl {
+    /// Creates a remote control listening at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            icon_states: HashMap::new(),
+        }
+    }
+
+    /// Registers an icon that `set_icon_state` can switch to by `name`.
+    pub fn icon_state(mut self, name: impl Into<SharedString>, icon: Image) -> Self {
+        self.icon_states.insert(name.into(), icon);
+        self
+    }
+
+    /// Starts listening, replacing any remote control started before.
+    pub fn start(self, cx: &mut App) -> Result<()> {
+        // Drop the previous server first, it removes its socket file on the way out.
+        if cx.has_global::<RemoteControlServer>() {
+            drop(cx.remove_global::<RemoteControlServer>());
+        }
+
+        let (request_tx, request_rx) = mpsc::channel::<Request>();
+        let stop = Arc::new(AtomicBool::new(false));
+        listen(&self.path, request_tx, stop.clone())?;
+
+        let task = spawn_command_pump(cx, request_rx, self.icon_states);
+        cx.set_global(RemoteControlServer {
+            path: self.path,
+            stop,
+            _task: task,
+        });
+        Ok(())
+    }
+}
+
+fn spawn_command_pump(
+    cx: &mut App,
+    request_rx: Receiver<Request>,
+    icon_states: HashMap<SharedString, Image>,
+) -> Task<()> {
+    cx.spawn(move |cx: &mut AsyncApp| {
+        let cx = cx.clone();
+        async move {
+            loop {
+                while let Ok((command, reply_tx)) = request_rx.try_recv() {
+                    let Ok(result) = cx.update(|app: &mut App| run(app, command, &icon_states))
+                    else {
+                        return;
+                    };
+                    let _ = reply_tx.send(match result {
+                        Ok(()) => Reply {
+                            ok: true,
+                            error: None,
+                        },
+                        Err(err) => Reply {
+                            ok: false,
+                            error: Some(err),
+                        },
+                    });
+                }
+
+                cx.background_executor()
+                    .timer(Duration::from_millis(50))
+                    .await;
+            }
+        }
+    })
+}
+
+fn run(
+    cx: &mut App,
+    command: RemoteCommand,
+    icon_states: &HashMap<SharedString, Image>,
+) -> std::result::Result<(), String> {
+    log::debug!("remote command {command:?}");
+    match command {
+        RemoteCommand::SetIconState { state } => {
+            let icon = icon_states
+                .get(state.as_str())
+                .cloned()
+                .ok_or_else(|| format!("unknown icon state `{state}`"))?;
+            cx.update_tray(|tray| tray.icon = Some(icon))
+                .map(|_| ())
+                .map_err(|err| err.to_string())
+        }
+        RemoteCommand::SetTooltip { tooltip } => cx
+            .update_tray(|tray| {
+                tray.tooltip = tooltip.map(SharedString::from);
+                tray.tooltip_text = None;
+            })
+            .map(|_| ())
+            .map_err(|err| err.to_string()),
+        RemoteCommand::Notify { title, body } => cx
+            .tray_notify(Notification::new(title, body))
+            .map_err(|err| err.to_string()),
+    }
+}
+
+#[cfg(unix)]
+fn listen(
+    path: &std::path::Path,
+    request_tx: Sender<Request>,
+    stop: Arc<AtomicBool>,
+) -> Result<()> {
+    use gpui_tray_core::BackendError;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let platform_error = |operation, err: std::io::Error| {
+        Error::Backend(BackendError::platform(operation, err.to_string()))
+    };
+
+    // A socket left behind by a crashed run would make `bind` fail. Anything
+    // else at `path` is not ours to replace.
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            return Err(platform_error(
+                "listen",
+                std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ),
+            ));
+        }
+        std::fs::remove_file(path).map_err(|err| platform_error("remove_file", err))?;
+    }
+
+    // `bind` creates the socket with the umask's permissions. Binding in a
+    // private directory first keeps other users from connecting before the
+    // mode is tightened; the socket is then moved into place.
+    let private_dir = path.with_file_name(format!(
+        ".{}.{}",
+        path.file_name().unwrap_or_default().to_string_lossy(),
+        std::process::id()
+    ));
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&private_dir)
+        .map_err(|err| platform_error("create_dir", err))?;
+    let private_path = private_dir.join("socket");
+    let bound = UnixListener::bind(&private_path)
+        .map_err(|err| platform_error("bind", err))
+        .and_then(|listener| {
+            std::fs::set_permissions(&private_path, std::fs::Permissions::from_mode(0o600))
+                .map_err(|err| platform_error("set_permissions", err))?;
+            std::fs::rename(&private_path, path).map_err(|err| platform_error("rename", err))?;
+            Ok(listener)
+        });
+    let _ = std::fs::remove_file(&private_path);
+    let _ = std::fs::remove_dir(&private_dir);
+    let listener = bound?;
+
+    fn serve(stream: UnixStream, request_tx: Sender<Request>) -> std::io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        let mut reader = BufReader::new(stream);
+        loop {
+            let mut line = Vec::new();
+            let read = (&mut reader)
+                .take(MAX_LINE_LEN as u64)
+                .read_until(b'\n', &mut line)?;
+            if read == 0 {
+                return Ok(());
+            }
+            if line.len() == MAX_LINE_LEN && !line.ends_with(b"\n") {
+                let reply = Reply {
+                    ok: false,
+                    error: Some(format!("command longer than {MAX_LINE_LEN} bytes")),
+                };
+                return write_reply(&mut writer, &reply);
+            }
+            let line = String::from_utf8_lossy(&line);
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let reply = match serde_json::from_str::<RemoteCommand>(&line) {
+                Ok(command) => {
+                    let (reply_tx, reply_rx) = mpsc::channel();
+                    if request_tx.send((command, reply_tx)).is_err() {
+                        return Ok(());
+                    }
+                    match reply_rx.recv() {
+                        Ok(reply) => reply,
+                        Err(_) => return Ok(()),
+                    }
+                }
+                Err(err) => Reply {
+                    ok: false,
+                    error: Some(format!("invalid command: {err}")),
+                },
+            };
+
+            write_reply(&mut writer, &reply)?;
+        }
+    }
+
+    fn write_reply(writer: &mut UnixStream, reply: &Reply) -> std::io::Result<()> {
+        let mut reply = serde_json::to_string(reply).map_err(std::io::Error::other)?;
+        reply.push('\n');
+        writer.write_all(reply.as_bytes())
+    }
+
+    std::thread::Builder::new()
+        .name("gpui-tray-remote".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::Acquire) {
+                    break;
+                }
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        log::error!("remote control accept failed: {err}");
+                        continue;
+                    }
+                };
+                let request_tx = request_tx.clone();
+                let spawned = std::thread::Builder::new()
+                    .name("gpui-tray-remote-client".to_string())
+                    .spawn(move || {
+                        if let Err(err) = serve(stream, request_tx) {
+                            log::debug!("remote control client disconnected: {err}");
+                        }
+                    });
+                if let Err(err) = spawned {
+                    log::error!("remote control client thread failed: {err}");
+                }
+            }
+        })
+        .map_err(|err| platform_error("spawn", err))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen(
+    _path: &std::path::Path,
+    _request_tx: Sender<Request>,
+    _stop: Arc<AtomicBool>,
+) -> Result<()> {
+    Err(Error::UnsupportedPlatform)
+}
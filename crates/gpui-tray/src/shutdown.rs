@@ -0,0 +1,48 @@
+//! Tears the tray down when the app quits.
+//!
+//! GPUI drops its globals together with the app, after the platform has
+//! quit: by then the Windows message loop and the Wayland display are gone,
+//! and on macOS so is the autorelease pool. Backends release their icons and
+//! connections in `Drop`, so a quit observer removes them first, while all
+//! of that still exists.
+
+use crate::activation;
+use crate::manager;
+use gpui::{App, Global, Subscription};
+
+/// Quit observer that removes the tray, registered with the first runtime.
+struct TrayShutdown {
+    _subscription: Subscription,
+    /// Whether the app is quitting and the tray is gone for good.
+    done: bool,
+}
+
+impl Global for TrayShutdown {}
+
+/// Makes sure the tray is torn down before the app quits.
+pub(crate) fn register(cx: &mut App) {
+    if cx.has_global::<TrayShutdown>() {
+        return;
+    }
+    let subscription = cx.on_app_quit(|cx| {
+        run(cx);
+        async {}
+    });
+    cx.set_global(TrayShutdown {
+        _subscription: subscription,
+        done: false,
+    });
+}
+
+/// Whether the app quit, after which no new tray runtime may start.
+pub(crate) fn is_done(cx: &App) -> bool {
+    cx.try_global::<TrayShutdown>()
+        .is_some_and(|shutdown| shutdown.done)
+}
+
+fn run(cx: &mut App) {
+    log::debug!("app quitting, removing the tray");
+    cx.global_mut::<TrayShutdown>().done = true;
+    manager::shut_down(cx);
+    activation::release(cx);
+}
@@ -0,0 +1,25 @@
+use crate::platform_impl;
+use gpui_tray_core::Tray;
+
+/// Adds [`Tray::is_supported`](TraySupport::is_supported), a check for a
+/// desktop that can show the icon.
+pub trait TraySupport {
+    /// Whether the desktop can show a tray icon now.
+    ///
+    /// On Linux a StatusNotifierWatcher must run on the session bus or an
+    /// X11 tray manager own the system tray selection; on Windows the
+    /// taskbar must be running. Apps check it at startup to choose between
+    /// minimizing to the tray and keeping a window or dock icon. A panel
+    /// that starts after the app changes the answer.
+    ///
+    /// ```rust,ignore
+    /// let minimize_to_tray = Tray::is_supported();
+    /// ```
+    fn is_supported() -> bool;
+}
+
+impl TraySupport for Tray {
+    fn is_supported() -> bool {
+        platform_impl::is_supported()
+    }
+}
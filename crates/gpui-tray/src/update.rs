@@ -0,0 +1,163 @@
+//! Surfaces an available update on the tray.
+//!
+//! ```rust,ignore
+//! UpdateNotifier::new()
+//!     .check(cx, async move {
+//!         let latest = fetch_latest_version().await.ok()?;
+//!         (latest != CURRENT_VERSION).then(|| UpdateInfo::new(latest).url(RELEASES_URL))
+//!     })
+//!     .detach();
+//! ```
+//!
+//! The menu entry and notification are in English unless the app's
+//! [`Localizer`](gpui_tray_core::Localizer) translates their message keys,
+//! which get the new version as the `version` argument.
+
+use crate::TrayAppContext;
+use crate::manager::{localize_builtin, set_update};
+use gpui::{App, AsyncApp, SharedString, Task};
+use gpui_tray_core::{InstallUpdate, Notification, Result, TrayMenuItem, UpdateInfo};
+use std::future::Future;
+
+/// Id of the menu entry added for an update.
+pub const UPDATE_MENU_ITEM_ID: &str = "update_available";
+
+/// Message key of the update's menu entry, "Update available: {version}".
+pub const UPDATE_MENU_LABEL_KEY: &str = "gpui-tray-update-menu-label";
+
+/// Message key of the update notification's title, "Update available".
+pub const UPDATE_NOTIFICATION_TITLE_KEY: &str = "gpui-tray-update-notification-title";
+
+/// Message key of the update notification's body, "Version {version} is
+/// ready to install."
+pub const UPDATE_NOTIFICATION_BODY_KEY: &str = "gpui-tray-update-notification-body";
+
+/// Shows an update through the tray's attention state, a menu entry with
+/// the new version and a notification, all at once.
+///
+/// The update stays on the tray across `update_tray` calls until
+/// [`UpdateNotifier::dismiss`].
+#[derive(Clone, Copy, Debug)]
+pub struct UpdateNotifier {
+    menu_entry: bool,
+    attention: bool,
+    notification: bool,
+}
+
+/// What the tray runtime adds to the tray for an update.
+pub(crate) struct UpdateSurface {
+    pub info: UpdateInfo,
+    /// Label of the menu entry, localized when the update was shown.
+    pub menu_label: SharedString,
+    pub menu_entry: bool,
+    pub attention: bool,
+}
+
+impl UpdateNotifier {
+    /// Creates a notifier using every channel.
+    pub fn new() -> Self {
+        Self {
+            menu_entry: true,
+            attention: true,
+            notification: true,
+        }
+    }
+
+    /// Sets whether the update is listed at the top of the tray menu.
+    pub fn menu_entry(mut self, menu_entry: bool) -> Self {
+        self.menu_entry = menu_entry;
+        self
+    }
+
+    /// Sets whether the tray asks for attention, see [`Tray::attention`](crate::Tray).
+    pub fn attention(mut self, attention: bool) -> Self {
+        self.attention = attention;
+        self
+    }
+
+    /// Sets whether a notification announces the update.
+    pub fn notification(mut self, notification: bool) -> Self {
+        self.notification = notification;
+        self
+    }
+
+    /// Awaits `check` and shows the update it finds, if any.
+    ///
+    /// The check is cancelled when the returned task is dropped.
+    pub fn check(
+        self,
+        cx: &mut App,
+        check: impl Future<Output = Option<UpdateInfo>> + 'static,
+    ) -> Task<()> {
+        cx.spawn(move |cx: &mut AsyncApp| {
+            let cx = cx.clone();
+            async move {
+                let Some(info) = check.await else {
+                    log::debug!("update check found nothing new");
+                    return;
+                };
+                let result = cx.update(|app: &mut App| self.show(app, info));
+                if let Ok(Err(err)) = result {
+                    log::error!("failed to show available update: {err}");
+                }
+            }
+        })
+    }
+
+    /// Shows `info` right away, for apps that run their own update check.
+    pub fn show(&self, cx: &mut App, info: UpdateInfo) -> Result<()> {
+        log::debug!("update {} available", info.version);
+        let args = [("version".into(), info.version.clone())];
+        if self.menu_entry || self.attention {
+            let menu_label = localize_builtin(cx, UPDATE_MENU_LABEL_KEY, &args, || {
+                format!("Update available: {}", info.version)
+            });
+            set_update(
+                cx,
+                Some(UpdateSurface {
+                    info: info.clone(),
+                    menu_label,
+                    menu_entry: self.menu_entry,
+                    attention: self.attention,
+                }),
+            )?;
+        }
+        if self.notification {
+            let title = localize_builtin(cx, UPDATE_NOTIFICATION_TITLE_KEY, &args, || {
+                "Update available".to_string()
+            });
+            let body = localize_builtin(cx, UPDATE_NOTIFICATION_BODY_KEY, &args, || {
+                format!("Version {} is ready to install.", info.version)
+            });
+            cx.tray_notify(Notification::new(title, body))?;
+        }
+        Ok(())
+    }
+
+    /// Removes the menu entry and attention state, e.g. once the update is
+    /// installed.
+    pub fn dismiss(cx: &mut App) -> Result<()> {
+        set_update(cx, None)
+    }
+}
+
+impl Default for UpdateNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Opens the release page when there is one, otherwise asks the app to
+/// install the update.
+pub(crate) fn update_menu_item(info: &UpdateInfo, label: SharedString) -> TrayMenuItem {
+    match info.url.clone() {
+        Some(url) => TrayMenuItem::open_url(UPDATE_MENU_ITEM_ID, label, url),
+        None => TrayMenuItem::action(
+            label,
+            InstallUpdate {
+                version: info.version.clone(),
+            },
+        )
+        .id(UPDATE_MENU_ITEM_ID),
+    }
+}
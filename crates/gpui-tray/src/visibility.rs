@@ -0,0 +1,99 @@
+//! Shows the tray only while some part of the app needs it.
+//!
+//! ```rust,ignore
+//! // Held for as long as the download runs; the icon hides again once the
+//! // last guard is dropped.
+//! let _tray = cx.require_tray();
+//! ```
+
+use crate::TrayAppContext;
+use gpui::{App, AsyncApp, Global};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Keeps the tray icon shown while it is alive, see
+/// `TrayAppContext::require_tray`.
+///
+/// The icon shows while at least one guard exists and hides when the last
+/// one is dropped. Once the app takes a guard, the guards set the tray's
+/// `visible` whenever their count crosses zero and for every tray set
+/// later. Clones count as guards of their own.
+#[must_use = "the tray hides again when the guard is dropped"]
+pub struct TrayVisibilityGuard {
+    guards: Rc<Cell<usize>>,
+    cx: AsyncApp,
+}
+
+/// Number of live guards.
+struct TrayVisibility(Rc<Cell<usize>>);
+
+impl Global for TrayVisibility {}
+
+/// Takes a guard, showing the tray if it is the first.
+pub(crate) fn require(cx: &mut App) -> TrayVisibilityGuard {
+    let guards = cx
+        .try_global::<TrayVisibility>()
+        .map(|visibility| visibility.0.clone())
+        .unwrap_or_else(|| {
+            let guards = Rc::new(Cell::new(0));
+            cx.set_global(TrayVisibility(guards.clone()));
+            guards
+        });
+    guards.set(guards.get() + 1);
+    apply(cx);
+
+    TrayVisibilityGuard {
+        guards,
+        cx: cx.to_async(),
+    }
+}
+
+/// Whether guards decide visibility, and if so whether the tray shows.
+pub(crate) fn required(cx: &App) -> Option<bool> {
+    cx.try_global::<TrayVisibility>()
+        .map(|visibility| visibility.0.get() > 0)
+}
+
+/// Brings the tray's `visible` in line with the guards, if it differs.
+fn apply(cx: &mut App) {
+    let Some(visible) = required(cx) else {
+        return;
+    };
+    if cx.tray().is_none_or(|tray| tray.visible == visible) {
+        return;
+    }
+    if let Err(err) = cx.update_tray(|tray| tray.visible = visible) {
+        log::error!("failed to apply tray visibility: {err}");
+    }
+}
+
+impl Clone for TrayVisibilityGuard {
+    fn clone(&self) -> Self {
+        self.guards.set(self.guards.get() + 1);
+        Self {
+            guards: self.guards.clone(),
+            cx: self.cx.clone(),
+        }
+    }
+}
+
+impl Drop for TrayVisibilityGuard {
+    fn drop(&mut self) {
+        let left = self.guards.get() - 1;
+        self.guards.set(left);
+        if left > 0 {
+            return;
+        }
+
+        // Hiding waits for the next turn of the event loop, so a guard that
+        // is dropped and replaced right away does not make the icon blink.
+        // It also keeps the update out of whatever update dropped the guard.
+        let cx = self.cx.clone();
+        self.cx
+            .foreground_executor()
+            .spawn(async move {
+                let _ = cx.update(apply);
+            })
+            .detach();
+    }
+}
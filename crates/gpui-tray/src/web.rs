@@ -0,0 +1,36 @@
+//! Stand-in for the tray when running in a browser.
+//!
+//! Enabled with the `web` feature. There is no system tray on the web, so
+//! the tray runtime hands every change to the app's fallback instead, which
+//! can render the tray's state in the page:
+//!
+//! ```rust,ignore
+//! web::install(cx)?;
+//! web::set_fallback(move |change| {
+//!     if let web::TrayChange::Set(tray) = change {
+//!         status.set(tray.tooltip.clone());
+//!     }
+//! });
+//! cx.set_tray(app_tray())?;
+//! ```
+//!
+//! Clicks on the page's widget go back to the app's tray handlers through
+//! [`send_event`].
+
+use crate::manager::TrayBackendOverride;
+use gpui::App;
+use gpui_tray_core::Result;
+
+pub use gpui_tray_web::{TrayChange, send_event, set_fallback};
+
+/// Routes every tray set from now on to the fallback instead of the
+/// platform.
+///
+/// gpui has no web platform to select this backend by itself yet, so apps
+/// call this on start. Install it before the first `set_tray`; a tray that
+/// is already set keeps its backend until it is removed.
+pub fn install(cx: &mut App) -> Result<()> {
+    let backend = gpui_tray_web::create()?;
+    cx.set_global(TrayBackendOverride(backend.into()));
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! Minimize to tray: the app's window hides when closed and comes back from
+//! the tray.
+//!
+//! ```rust,ignore
+//! let window = cx.open_window(options, |_, cx| cx.new(|_| Main))?;
+//! cx.set_tray(Tray::new().icon(icon).menu(|| {
+//!     vec![
+//!         TrayMenuItem::action("Show", ShowManagedWindow),
+//!         TrayMenuItem::action("Quit", QuitApp),
+//!     ]
+//! }))?;
+//! cx.manage_window(window);
+//! ```
+
+use crate::TrayAppContext;
+use crate::activation::activate_window;
+use gpui::{Action, AnyWindowHandle, App, Global, SharedString, Window};
+
+/// Brings back the window passed to [`TrayWindowExt::manage_window`].
+///
+/// The tray runtime handles it when it comes from the tray menu; elsewhere
+/// call [`TrayWindowExt::show_managed_window`].
+#[derive(Clone, PartialEq, Debug, Action)]
+#[action(namespace = gpui_tray, no_json)]
+pub struct ShowManagedWindow;
+
+/// Ties one of the app's windows to the tray.
+pub trait TrayWindowExt {
+    /// Hides `window` instead of closing it while the tray icon is shown,
+    /// and shows it again on a left click on the icon or a
+    /// [`ShowManagedWindow`] menu entry.
+    ///
+    /// A click the app handles itself, or a tray with a different
+    /// [`FallbackActivation`](gpui_tray_core::FallbackActivation) than
+    /// `FocusMainWindow`, leaves the window alone. Once the tray is removed,
+    /// hidden or made passive, the window is shown again and closing it
+    /// closes it, so the app never ends up without either. This replaces
+    /// the window's `on_window_should_close` callback.
+    ///
+    /// Windows hides the window together with its taskbar button. GPUI has
+    /// no way to hide a single window elsewhere, so it is minimized there.
+    fn manage_window(&mut self, window: impl Into<AnyWindowHandle>);
+
+    /// Shows and focuses the managed window if it was hidden.
+    fn show_managed_window(&mut self);
+}
+
+/// The window handed to `manage_window`.
+struct ManagedWindow {
+    window: AnyWindowHandle,
+    hidden: bool,
+}
+
+impl Global for ManagedWindow {}
+
+impl TrayWindowExt for App {
+    fn manage_window(&mut self, window: impl Into<AnyWindowHandle>) {
+        let window = window.into();
+        self.set_global(ManagedWindow {
+            window,
+            hidden: false,
+        });
+
+        let _ = window.update(self, |_, window, cx| {
+            window.on_window_should_close(cx, |window, cx| {
+                if !tray_shown(cx) {
+                    forget(cx, window);
+                    return true;
+                }
+                hide(window);
+                if is_managed(cx, window) {
+                    cx.global_mut::<ManagedWindow>().hidden = true;
+                }
+                false
+            });
+        });
+    }
+
+    fn show_managed_window(&mut self) {
+        show(self, None);
+    }
+}
+
+/// Whether the user can reach the app through the tray icon.
+fn tray_shown(cx: &App) -> bool {
+    cx.tray().is_some_and(|tray| tray.visible && !tray.passive)
+}
+
+/// Whether `window` is still the managed one; `manage_window` may have
+/// been called again with another.
+fn is_managed(cx: &App, window: &Window) -> bool {
+    cx.try_global::<ManagedWindow>()
+        .is_some_and(|managed| managed.window == window.window_handle())
+}
+
+fn forget(cx: &mut App, window: &Window) {
+    if is_managed(cx, window) {
+        cx.remove_global::<ManagedWindow>();
+    }
+}
+
+/// Shows and focuses the managed window, with the activation token of the
+/// tray click if any. Returns `false` when there is no managed window.
+pub(crate) fn show(cx: &mut App, token: Option<&SharedString>) -> bool {
+    let Some(managed) = cx.try_global::<ManagedWindow>() else {
+        return false;
+    };
+    let window = managed.window;
+    cx.global_mut::<ManagedWindow>().hidden = false;
+
+    cx.activate(true);
+    if window
+        .update(cx, |_, window, cx| {
+            unhide(window);
+            activate_window(window, cx, token);
+        })
+        .is_err()
+    {
+        // The window was closed some other way.
+        cx.remove_global::<ManagedWindow>();
+        return false;
+    }
+    true
+}
+
+/// Shows the managed window on a [`ShowManagedWindow`] menu entry. Returns
+/// whether the action was consumed.
+pub(crate) fn show_requested(cx: &mut App, action: &dyn Action) -> bool {
+    if !action.as_any().is::<ShowManagedWindow>() {
+        return false;
+    }
+    if !show(cx, None) {
+        log::debug!("no managed window to show");
+    }
+    true
+}
+
+/// Hides `window` like closing it would if it is the managed window, and
+/// minimizes it otherwise.
+pub(crate) fn dismiss(cx: &mut App, window: AnyWindowHandle) {
+    let managed = cx
+        .try_global::<ManagedWindow>()
+        .is_some_and(|managed| managed.window == window)
+        && tray_shown(cx);
+    let _ = window.update(cx, |_, window, _| {
+        if managed {
+            hide(window);
+        } else {
+            window.minimize_window();
+        }
+    });
+    if managed {
+        cx.global_mut::<ManagedWindow>().hidden = true;
+    }
+}
+
+/// Shows the managed window again once the tray can no longer bring it
+/// back. Called after every tray change.
+pub(crate) fn sync_with_tray(cx: &mut App) {
+    if cx
+        .try_global::<ManagedWindow>()
+        .is_some_and(|managed| managed.hidden)
+        && !tray_shown(cx)
+    {
+        show(cx, None);
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn hide(window: &Window) {
+    set_visible(window, false);
+}
+
+#[cfg(not(target_os = "windows"))]
+fn hide(window: &Window) {
+    window.minimize_window();
+}
+
+#[cfg(target_os = "windows")]
+fn unhide(window: &Window) {
+    set_visible(window, true);
+}
+
+// Activation restores a minimized window.
+#[cfg(not(target_os = "windows"))]
+fn unhide(_window: &Window) {}
+
+#[cfg(target_os = "windows")]
+fn set_visible(window: &Window, visible: bool) {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+    if let Ok(handle) = HasWindowHandle::window_handle(window)
+        && let RawWindowHandle::Win32(handle) = handle.as_raw()
+    {
+        crate::platform_impl::set_window_visible(handle.hwnd.get(), visible);
+    }
+}
@@ -0,0 +1,162 @@
+//! Drives the tray runtime through [`MockTray`], as an app's own tests
+//! would.
+
+use gpui::{MouseButton, TestAppContext};
+use gpui_tray::mock::MockTray;
+use gpui_tray::{
+    FallbackActivation, MenuItemKind, MenuSelectEvent, Tray, TrayAppContext, TrayMenuItem,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+gpui::actions!(test_app, [Discard, ToggleSync, Open]);
+
+fn app_tray() -> Tray {
+    Tray::new().tooltip("Idle").menu(|| {
+        vec![
+            TrayMenuItem::checkbox("Pause syncing", false, ToggleSync).id("pause"),
+            TrayMenuItem::open_url("docs", "Documentation", "https://example.com/docs"),
+            TrayMenuItem::action("Discard changes", Discard)
+                .id("discard")
+                .confirm("Discard all local changes?"),
+            TrayMenuItem::action("Open", Open).id("open"),
+        ]
+    })
+}
+
+/// Lets the runtime's event pump pick up what the mock sent.
+fn pump(cx: &mut TestAppContext) {
+    cx.executor().advance_clock(Duration::from_millis(10));
+    cx.run_until_parked();
+}
+
+/// Records the name of every action of type `A` the app receives.
+fn record<A: gpui::Action>(cx: &mut TestAppContext, log: &Rc<RefCell<Vec<String>>>) {
+    let log = log.clone();
+    cx.update(|cx| {
+        cx.on_action(move |action: &A, _| log.borrow_mut().push(action.name().to_string()))
+    });
+}
+
+fn checked(mock: &MockTray, id: &str) -> Option<bool> {
+    mock.menu().into_iter().find_map(|item| match item.kind {
+        MenuItemKind::Checkbox { checked, .. }
+            if item
+                .id
+                .as_ref()
+                .is_some_and(|item_id| item_id.as_ref() == id) =>
+        {
+            Some(checked)
+        }
+        _ => None,
+    })
+}
+
+#[gpui::test]
+fn set_update_and_remove(cx: &mut TestAppContext) {
+    let mock = cx.update(MockTray::install);
+    cx.update(|cx| cx.set_tray(app_tray())).unwrap();
+    assert_eq!(mock.tray().unwrap().tooltip, Some("Idle".into()));
+
+    cx.update(|cx| cx.update_tray(|tray| tray.tooltip = Some("Syncing".into())))
+        .unwrap();
+    assert_eq!(mock.tray().unwrap().tooltip, Some("Syncing".into()));
+
+    cx.update(|cx| cx.remove_tray()).unwrap();
+    assert!(mock.tray().is_none());
+    assert!(cx.update(|cx| cx.tray().is_none()));
+    assert!(cx.update(|cx| cx.remove_tray()).is_err());
+}
+
+#[gpui::test]
+fn menu_selection_dispatches_its_actions(cx: &mut TestAppContext) {
+    let mock = cx.update(MockTray::install);
+    let log = Rc::new(RefCell::new(Vec::new()));
+    record::<MenuSelectEvent>(cx, &log);
+    record::<Open>(cx, &log);
+    cx.update(|cx| cx.set_tray(app_tray())).unwrap();
+
+    mock.select_menu_item("open").unwrap();
+    pump(cx);
+    assert_eq!(
+        *log.borrow(),
+        ["gpui_tray::MenuSelectEvent", "test_app::Open"]
+    );
+    assert!(mock.select_menu_item("missing").is_err());
+}
+
+#[gpui::test]
+fn toggling_an_entry_updates_the_menu(cx: &mut TestAppContext) {
+    let mock = cx.update(MockTray::install);
+    let log = Rc::new(RefCell::new(Vec::new()));
+    record::<ToggleSync>(cx, &log);
+    cx.update(|cx| cx.set_tray(app_tray())).unwrap();
+    assert_eq!(checked(&mock, "pause"), Some(false));
+
+    mock.select_menu_item("pause").unwrap();
+    pump(cx);
+    assert_eq!(checked(&mock, "pause"), Some(true));
+    assert_eq!(log.borrow().len(), 1);
+
+    mock.select_menu_item("pause").unwrap();
+    pump(cx);
+    assert_eq!(checked(&mock, "pause"), Some(false));
+}
+
+#[gpui::test]
+fn url_entries_open_without_a_handler(cx: &mut TestAppContext) {
+    let mock = cx.update(MockTray::install);
+    cx.update(|cx| cx.set_tray(app_tray())).unwrap();
+
+    mock.select_menu_item("docs").unwrap();
+    pump(cx);
+    assert_eq!(cx.opened_url().as_deref(), Some("https://example.com/docs"));
+}
+
+#[gpui::test]
+fn confirm_entries_ask_first_and_default_to_cancel(cx: &mut TestAppContext) {
+    let mock = cx.update(MockTray::install);
+    let log = Rc::new(RefCell::new(Vec::new()));
+    record::<Discard>(cx, &log);
+    cx.update(|cx| cx.set_tray(app_tray())).unwrap();
+
+    mock.select_menu_item("discard").unwrap();
+    pump(cx);
+    let dialog = *cx.windows().last().expect("a confirmation window");
+    cx.simulate_keystrokes(dialog, "enter");
+    pump(cx);
+    assert!(cx.windows().is_empty());
+    assert!(log.borrow().is_empty());
+
+    mock.select_menu_item("discard").unwrap();
+    pump(cx);
+    let dialog = *cx.windows().last().expect("a confirmation window");
+    cx.simulate_keystrokes(dialog, "tab enter");
+    pump(cx);
+    assert!(cx.windows().is_empty());
+    assert_eq!(*log.borrow(), ["test_app::Discard"]);
+}
+
+#[gpui::test]
+fn unhandled_left_clicks_use_the_fallback(cx: &mut TestAppContext) {
+    let mock = cx.update(MockTray::install);
+    let tray = app_tray().fallback_activation(FallbackActivation::ShowMenu);
+    cx.update(|cx| cx.set_tray(tray)).unwrap();
+
+    mock.click(MouseButton::Left);
+    pump(cx);
+    assert_eq!(mock.menu_requests(), 1);
+
+    // A handler takes the click instead.
+    let log = Rc::new(RefCell::new(Vec::new()));
+    record::<Open>(cx, &log);
+    let tray = app_tray()
+        .fallback_activation(FallbackActivation::ShowMenu)
+        .on_left_click(Open);
+    cx.update(|cx| cx.set_tray(tray)).unwrap();
+    mock.click(MouseButton::Left);
+    pump(cx);
+    assert_eq!(*log.borrow(), ["test_app::Open"]);
+    assert_eq!(mock.menu_requests(), 1);
+}
@@ -1,5 +1,8 @@
+use gpui_tray_core::{RawPlatformEvent, SystemTheme, TrayMenuItem, TrayStatus};
 use log::debug;
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use zbus::zvariant::Value;
 use zbus::{blocking::Connection, interface};
@@ -21,33 +24,151 @@ pub(crate) type LayoutResult = (u32, LayoutItem);
 
 #[derive(Debug, Clone)]
 pub(crate) enum TrayEvent {
-    Activate { x: i32, y: i32 },
-    SecondaryActivate { x: i32, y: i32 },
-    ContextMenu { x: i32, y: i32 },
-    MenuClicked { id: i32 },
+    Activate {
+        x: i32,
+        y: i32,
+    },
+    SecondaryActivate {
+        x: i32,
+        y: i32,
+    },
+    ContextMenu {
+        x: i32,
+        y: i32,
+    },
+    /// xdg-activation token for the activation that follows.
+    ActivationToken(String),
+    /// The host opened the root menu.
+    MenuOpened,
+    /// The host closed the root menu.
+    MenuClosed,
+    /// Second click of a double-click, only reported by the XEmbed icon.
+    DoubleClick {
+        button: gpui::MouseButton,
+        x: i32,
+        y: i32,
+    },
+    Scroll {
+        delta: i32,
+        orientation: String,
+    },
+    MenuClicked {
+        id: i32,
+    },
+    NotificationClicked,
+    /// The desktop color scheme, sent once at startup and on every change.
+    ThemeChanged(SystemTheme),
+    WatcherRegistered,
+    WatcherLost,
+    /// A restarted XEmbed tray manager took the icon in again.
+    XEmbedDocked,
+    /// A host call as it arrived, for `Tray::on_raw_platform_event`.
+    Raw(RawPlatformEvent),
 }
 
+/// Passes a host call on untranslated, ahead of the event it turns into.
+///
+/// Skipped unless `raw_events` is set, which the worker does while the tray
+/// has a raw event handler, so hosts polling the menu cost nothing extra.
+fn forward_call(
+    event_sender: &std::sync::mpsc::Sender<TrayEvent>,
+    raw_events: &AtomicBool,
+    interface: &'static str,
+    member: &'static str,
+    args: impl fmt::Debug,
+) {
+    if !raw_events.load(Ordering::Relaxed) {
+        return;
+    }
+    let _ = event_sender.send(TrayEvent::Raw(RawPlatformEvent::DBusCall {
+        interface,
+        member,
+        args: format!("{args:?}"),
+    }));
+}
+
+/// Item properties as last set by the app.
+///
+/// Property reads from the host are answered from here without calling back
+/// into the app; the worker refreshes it on every tray update.
 pub(crate) struct ItemState {
     pub title: String,
     pub tooltip: String,
+    pub tooltip_body: String,
     pub icon: Option<Vec<Pixmap>>,
+    /// Themed icon hosts show in place of `icon`, or empty.
+    pub icon_name: String,
+    /// Shown in place of `icon` while the status asks for attention.
+    pub attention_icon: Option<Vec<Pixmap>>,
+    /// Themed icon shown in place of `attention_icon`, or empty.
+    pub attention_icon_name: String,
+    /// Icon name or path of an animation for the attention status, or
+    /// empty.
+    pub attention_movie_name: String,
+    pub status: TrayStatus,
+    /// Whether the menu opens on a left click, so hosts should show it
+    /// instead of activating the item.
+    pub item_is_menu: bool,
+    /// X11 window of the app, or 0 when there is none.
+    pub window_id: i32,
+}
+
+/// Which item properties differ from what the host last saw.
+#[derive(Clone, Copy, Default, Debug)]
+pub(crate) struct ItemChange {
+    pub icon: bool,
+    pub attention_icon: bool,
+    pub tooltip: bool,
+    pub title: bool,
+    pub status: bool,
+}
+
+impl ItemState {
+    pub fn status(&self) -> &'static str {
+        match self.status {
+            TrayStatus::Passive => "Passive",
+            TrayStatus::Active => "Active",
+            TrayStatus::Attention => "NeedsAttention",
+        }
+    }
+}
+
+fn pixmap_tuples(pixmaps: Option<&[Pixmap]>) -> Vec<PixmapTuple> {
+    pixmaps
+        .unwrap_or_default()
+        .iter()
+        .map(|p| (p.width, p.height, p.data.clone()))
+        .collect()
 }
 
 pub(crate) struct StatusNotifierItem {
     state: Arc<Mutex<ItemState>>,
     event_sender: std::sync::mpsc::Sender<TrayEvent>,
+    raw_events: Arc<AtomicBool>,
 }
 
 impl StatusNotifierItem {
     pub fn new(
         state: Arc<Mutex<ItemState>>,
         event_sender: std::sync::mpsc::Sender<TrayEvent>,
+        raw_events: Arc<AtomicBool>,
     ) -> Self {
         Self {
             state,
             event_sender,
+            raw_events,
         }
     }
+
+    fn forward(&self, member: &'static str, args: impl fmt::Debug) {
+        forward_call(
+            &self.event_sender,
+            &self.raw_events,
+            STATUS_NOTIFIER_ITEM_IFACE,
+            member,
+            args,
+        );
+    }
 }
 
 #[interface(name = "org.kde.StatusNotifierItem")]
@@ -75,27 +196,46 @@ impl StatusNotifierItem {
 
     #[zbus(property)]
     fn status(&self) -> &str {
-        "Active"
+        self.state.lock().map(|s| s.status()).unwrap_or("Active")
     }
 
     #[zbus(property, name = "IconName")]
-    fn icon_name(&self) -> &str {
-        ""
+    fn icon_name(&self) -> String {
+        self.state
+            .lock()
+            .map(|s| s.icon_name.clone())
+            .unwrap_or_default()
     }
 
     #[zbus(property, name = "IconPixmap")]
     fn icon_pixmap(&self) -> Vec<PixmapTuple> {
         self.state
             .lock()
-            .ok()
-            .and_then(|s| {
-                s.icon.as_ref().map(|pixmaps| {
-                    pixmaps
-                        .iter()
-                        .map(|p| (p.width, p.height, p.data.clone()))
-                        .collect()
-                })
-            })
+            .map(|s| pixmap_tuples(s.icon.as_deref()))
+            .unwrap_or_default()
+    }
+
+    #[zbus(property, name = "AttentionIconName")]
+    fn attention_icon_name(&self) -> String {
+        self.state
+            .lock()
+            .map(|s| s.attention_icon_name.clone())
+            .unwrap_or_default()
+    }
+
+    #[zbus(property, name = "AttentionIconPixmap")]
+    fn attention_icon_pixmap(&self) -> Vec<PixmapTuple> {
+        self.state
+            .lock()
+            .map(|s| pixmap_tuples(s.attention_icon.as_deref()))
+            .unwrap_or_default()
+    }
+
+    #[zbus(property, name = "AttentionMovieName")]
+    fn attention_movie_name(&self) -> String {
+        self.state
+            .lock()
+            .map(|s| s.attention_movie_name.clone())
             .unwrap_or_default()
     }
 
@@ -106,7 +246,7 @@ impl StatusNotifierItem {
             String::new(),
             Vec::new(),
             state.tooltip.clone(),
-            String::new(),
+            state.tooltip_body.clone(),
         )
     }
 
@@ -118,15 +258,29 @@ impl StatusNotifierItem {
 
     #[zbus(property)]
     fn item_is_menu(&self) -> bool {
-        false
+        self.state.lock().is_ok_and(|s| s.item_is_menu)
+    }
+
+    #[zbus(property)]
+    fn window_id(&self) -> i32 {
+        self.state.lock().map(|s| s.window_id).unwrap_or(0)
+    }
+
+    /// Called by hosts right before `Activate` on Wayland.
+    fn provide_xdg_activation_token(&self, token: String) {
+        self.forward("ProvideXdgActivationToken", (&token,));
+        debug!("Received xdg activation token");
+        let _ = self.event_sender.send(TrayEvent::ActivationToken(token));
     }
 
     fn activate(&self, x: i32, y: i32) {
+        self.forward("Activate", (x, y));
         debug!("Received activate with position=({}, {})", x, y);
         let _ = self.event_sender.send(TrayEvent::Activate { x, y });
     }
 
     fn secondary_activate(&self, x: i32, y: i32) {
+        self.forward("SecondaryActivate", (x, y));
         debug!("Received secondary_activate with position=({}, {})", x, y);
         let _ = self
             .event_sender
@@ -134,29 +288,65 @@ impl StatusNotifierItem {
     }
 
     fn context_menu(&self, x: i32, y: i32) {
+        self.forward("ContextMenu", (x, y));
         debug!("Received context_menu with position=({}, {})", x, y);
         let _ = self.event_sender.send(TrayEvent::ContextMenu { x, y });
     }
 
-    fn scroll(&self, _delta: i32, _orientation: &str) {}
+    fn scroll(&self, delta: i32, orientation: &str) {
+        self.forward("Scroll", (delta, orientation));
+        debug!("Received scroll with delta={delta}, orientation={orientation}");
+        let _ = self.event_sender.send(TrayEvent::Scroll {
+            delta,
+            orientation: orientation.to_string(),
+        });
+    }
 }
 
+#[derive(Clone)]
 struct MenuItem {
     id: i32,
     label: String,
     enabled: bool,
     visible: bool,
     item_type: MenuItemType,
+    toggle_type: ToggleType,
+    toggle_state: bool,
+    shortcut: Vec<Vec<String>>,
     children: Vec<i32>,
 }
 
+#[derive(Clone, PartialEq)]
 enum MenuItemType {
     Standard,
     Separator,
 }
 
+/// Check mark style of a menu item, the dbusmenu `toggle-type` property.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum ToggleType {
+    None,
+    Checkmark,
+    Radio,
+}
+
+/// What changed between two menu builds, and therefore which signal to emit.
+pub(crate) enum MenuChange {
+    Unchanged,
+    Properties(Vec<(i32, HashMap<String, Value<'static>>)>),
+    Layout(u32),
+}
+
+/// Rebuilds the menu from the app's builder, see [`MenuState::refresh`].
+pub(crate) type MenuRefresh = Box<dyn Fn(&mut MenuState) -> MenuChange + Send>;
+
 pub(crate) struct MenuState {
     items: HashMap<i32, MenuItem>,
+    /// Entries to dispatch, keyed by item id.
+    actions: HashMap<i32, TrayMenuItem>,
+    refresh: Option<MenuRefresh>,
+    /// Whether the host reported the root menu as shown.
+    open: bool,
     next_id: i32,
     revision: u32,
 }
@@ -165,6 +355,9 @@ impl MenuState {
     pub fn new() -> Self {
         let mut state = Self {
             items: HashMap::new(),
+            actions: HashMap::new(),
+            refresh: None,
+            open: false,
             next_id: 1,
             revision: 1,
         };
@@ -182,6 +375,9 @@ impl MenuState {
                 enabled: true,
                 visible: true,
                 item_type: MenuItemType::Standard,
+                toggle_type: ToggleType::None,
+                toggle_state: false,
+                shortcut: Vec::new(),
                 children: Vec::new(),
             },
         );
@@ -198,6 +394,9 @@ impl MenuState {
             enabled: true,
             visible: true,
             item_type: MenuItemType::Standard,
+            toggle_type: ToggleType::None,
+            toggle_state: false,
+            shortcut: Vec::new(),
             children: Vec::new(),
         };
 
@@ -220,6 +419,9 @@ impl MenuState {
             enabled: false,
             visible: true,
             item_type: MenuItemType::Separator,
+            toggle_type: ToggleType::None,
+            toggle_state: false,
+            shortcut: Vec::new(),
             children: Vec::new(),
         };
 
@@ -232,6 +434,118 @@ impl MenuState {
         id
     }
 
+    pub fn set_visible(&mut self, id: i32, visible: bool) {
+        if let Some(item) = self.items.get_mut(&id) {
+            item.visible = visible;
+        }
+    }
+
+    pub fn action(&self, id: i32) -> Option<&TrayMenuItem> {
+        self.actions.get(&id)
+    }
+
+    pub fn set_actions(&mut self, actions: HashMap<i32, TrayMenuItem>) {
+        self.actions = actions;
+    }
+
+    pub fn set_refresh(&mut self, refresh: Option<MenuRefresh>) {
+        self.refresh = refresh;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Forgets that the menu is shown. Returns whether it was.
+    ///
+    /// Not every host reports `closed`, so this also runs when an entry is
+    /// clicked and when the host goes away or comes back.
+    pub fn mark_closed(&mut self) -> bool {
+        std::mem::take(&mut self.open)
+    }
+
+    /// Runs the app's menu builder again. Returns whether anything changed.
+    pub fn refresh(&mut self) -> bool {
+        let Some(refresh) = self.refresh.take() else {
+            return false;
+        };
+        let change = refresh(self);
+        self.refresh = Some(refresh);
+        !matches!(change, MenuChange::Unchanged)
+    }
+
+    pub fn set_enabled(&mut self, id: i32, enabled: bool) {
+        if let Some(item) = self.items.get_mut(&id) {
+            item.enabled = enabled;
+        }
+    }
+
+    pub fn set_toggle(&mut self, id: i32, toggle_type: ToggleType, checked: bool) {
+        if let Some(item) = self.items.get_mut(&id) {
+            item.toggle_type = toggle_type;
+            item.toggle_state = checked;
+        }
+    }
+
+    /// Sets the `shortcut` property from an accelerator like "Ctrl+Shift+Q".
+    pub fn set_shortcut(&mut self, id: i32, accelerator: &str) {
+        let keys: Vec<String> = accelerator
+            .split('+')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| match key.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => "Control".to_string(),
+                "alt" | "option" => "Alt".to_string(),
+                "shift" => "Shift".to_string(),
+                "super" | "win" | "meta" | "cmd" | "command" => "Super".to_string(),
+                _ => key.to_string(),
+            })
+            .collect();
+        if keys.is_empty() {
+            return;
+        }
+        if let Some(item) = self.items.get_mut(&id) {
+            item.shortcut = vec![keys];
+        }
+    }
+
+    /// Rebuilds the menu and diffs it against the previous build.
+    ///
+    /// Item ids are assigned in build order, so an unchanged tree shape keeps
+    /// its ids and only the changed properties need to be announced.
+    pub fn rebuild(&mut self, build: impl FnOnce(&mut Self)) -> MenuChange {
+        let previous = std::mem::take(&mut self.items);
+        self.clear();
+        build(self);
+
+        let same_layout = self.items.len() == previous.len()
+            && self.items.values().all(|item| {
+                previous.get(&item.id).is_some_and(|old| {
+                    old.item_type == item.item_type && old.children == item.children
+                })
+            });
+        if !same_layout {
+            self.mark_updated();
+            return MenuChange::Layout(self.revision);
+        }
+
+        let updated: Vec<_> = self
+            .items
+            .values()
+            .filter_map(|item| {
+                let props = self.item_to_properties(item, &[]);
+                let old = previous.get(&item.id)?;
+                (self.item_to_properties(old, &[]) != props).then_some((item.id, props))
+            })
+            .collect();
+
+        if updated.is_empty() {
+            MenuChange::Unchanged
+        } else {
+            MenuChange::Properties(updated)
+        }
+    }
+
     pub fn mark_updated(&mut self) {
         self.revision = self.revision.wrapping_add(1);
     }
@@ -268,6 +582,30 @@ impl MenuState {
             props.insert("type".to_string(), Value::from(type_str));
         }
 
+        let toggle_type = match item.toggle_type {
+            ToggleType::None => None,
+            ToggleType::Checkmark => Some("checkmark"),
+            ToggleType::Radio => Some("radio"),
+        };
+        if let Some(toggle_type) = toggle_type {
+            if include_all || property_names.iter().any(|p| p == "toggle-type") {
+                props.insert("toggle-type".to_string(), Value::from(toggle_type));
+            }
+
+            if include_all || property_names.iter().any(|p| p == "toggle-state") {
+                props.insert(
+                    "toggle-state".to_string(),
+                    Value::from(i32::from(item.toggle_state)),
+                );
+            }
+        }
+
+        if !item.shortcut.is_empty()
+            && (include_all || property_names.iter().any(|p| p == "shortcut"))
+        {
+            props.insert("shortcut".to_string(), Value::from(item.shortcut.clone()));
+        }
+
         if !item.children.is_empty()
             && (include_all || property_names.iter().any(|p| p == "children-display"))
         {
@@ -311,18 +649,59 @@ impl MenuState {
 pub(crate) struct DBusMenu {
     state: Arc<Mutex<MenuState>>,
     event_sender: std::sync::mpsc::Sender<TrayEvent>,
+    raw_events: Arc<AtomicBool>,
 }
 
 impl DBusMenu {
     pub fn new(
         state: Arc<Mutex<MenuState>>,
         event_sender: std::sync::mpsc::Sender<TrayEvent>,
+        raw_events: Arc<AtomicBool>,
     ) -> Self {
         Self {
             state,
             event_sender,
+            raw_events,
+        }
+    }
+
+    fn forward(&self, member: &'static str, args: impl fmt::Debug) {
+        forward_call(
+            &self.event_sender,
+            &self.raw_events,
+            DBUS_MENU_IFACE,
+            member,
+            args,
+        );
+    }
+
+    fn handle_event(&self, id: i32, event_id: &str) {
+        debug!("Received menu_event with id={}, event_id={}", id, event_id);
+        match (id, event_id) {
+            (_, "clicked") => {
+                let was_open = self.state.lock().unwrap().mark_closed();
+                let _ = self.event_sender.send(TrayEvent::MenuClicked { id });
+                if was_open {
+                    let _ = self.event_sender.send(TrayEvent::MenuClosed);
+                }
+            }
+            (0, "opened") => {
+                self.state.lock().unwrap().open = true;
+                let _ = self.event_sender.send(TrayEvent::MenuOpened);
+            }
+            (0, "closed") => {
+                self.state.lock().unwrap().mark_closed();
+                let _ = self.event_sender.send(TrayEvent::MenuClosed);
+            }
+            _ => {}
         }
     }
+
+    fn refresh_root(&self) -> bool {
+        let updated = self.state.lock().unwrap().refresh();
+        debug!("DBusMenu::about_to_show: updated={updated}");
+        updated
+    }
 }
 
 #[interface(name = "com.canonical.dbusmenu")]
@@ -343,6 +722,7 @@ impl DBusMenu {
         recursion_depth: i32,
         property_names: Vec<String>,
     ) -> LayoutResult {
+        self.forward("GetLayout", (parent_id, recursion_depth, &property_names));
         debug!(
             "DBusMenu::get_layout called: parent_id={}, recursion_depth={}",
             parent_id, recursion_depth
@@ -359,6 +739,7 @@ impl DBusMenu {
         ids: Vec<i32>,
         property_names: Vec<String>,
     ) -> Vec<(i32, HashMap<String, Value<'static>>)> {
+        self.forward("GetGroupProperties", (&ids, &property_names));
         let state = self.state.lock().unwrap();
         ids.into_iter()
             .filter_map(|id| {
@@ -371,6 +752,7 @@ impl DBusMenu {
     }
 
     fn get_property(&self, id: i32, name: String) -> Value<'_> {
+        self.forward("GetProperty", (id, &name));
         let state = self.state.lock().unwrap();
         state
             .items
@@ -385,28 +767,39 @@ impl DBusMenu {
             .unwrap_or_else(|| Value::from(""))
     }
 
-    fn event(&self, id: i32, event_id: String, _data: Value<'_>, _timestamp: u32) {
-        debug!("Received menu_event with id={}, event_id={}", id, event_id);
-        if event_id == "clicked" {
-            let _ = self.event_sender.send(TrayEvent::MenuClicked { id });
-        }
+    fn event(&self, id: i32, event_id: String, data: Value<'_>, timestamp: u32) {
+        self.forward("Event", (id, &event_id, &data, timestamp));
+        self.handle_event(id, &event_id);
     }
 
-    fn event_group(&self, _events: Vec<(i32, String, Value<'_>, u32)>) -> Vec<i32> {
+    fn event_group(&self, events: Vec<(i32, String, Value<'_>, u32)>) -> Vec<i32> {
+        self.forward("EventGroup", (&events,));
+        for (id, event_id, _, _) in &events {
+            self.handle_event(*id, event_id);
+        }
         Vec::new()
     }
 
-    fn about_to_show(&self, _id: i32) -> bool {
-        false
+    /// Rebuilds the menu as the host opens it, so it reflects the app's
+    /// current state. Submenus are left alone so ids stay stable while the
+    /// menu is open.
+    fn about_to_show(&self, id: i32) -> bool {
+        self.forward("AboutToShow", (id,));
+        id == 0 && self.refresh_root()
     }
 
-    fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
-        (Vec::new(), Vec::new())
+    fn about_to_show_group(&self, ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+        self.forward("AboutToShowGroup", (&ids,));
+        if !ids.contains(&0) || !self.refresh_root() {
+            return (Vec::new(), Vec::new());
+        }
+        (ids, Vec::new())
     }
 }
 
 pub(crate) struct DbusService {
     connection: Arc<Connection>,
+    service_name: String,
 }
 
 impl DbusService {
@@ -414,6 +807,7 @@ impl DbusService {
         item_state: Arc<Mutex<ItemState>>,
         menu_state: Arc<Mutex<MenuState>>,
         event_sender: std::sync::mpsc::Sender<TrayEvent>,
+        raw_events: Arc<AtomicBool>,
     ) -> Result<Self, zbus::Error> {
         let service_name = format!(
             "org.freedesktop.StatusNotifierItem-GPUITRAY-{}",
@@ -425,8 +819,8 @@ impl DbusService {
         let connection = Arc::new(Connection::session()?);
         connection.request_name(service_name.as_str())?;
 
-        let item = StatusNotifierItem::new(item_state, event_sender.clone());
-        let menu = DBusMenu::new(menu_state, event_sender);
+        let item = StatusNotifierItem::new(item_state, event_sender.clone(), raw_events.clone());
+        let menu = DBusMenu::new(menu_state, event_sender, raw_events);
 
         connection
             .object_server()
@@ -435,43 +829,76 @@ impl DbusService {
 
         register_status_notifier_item(&connection, service_name.as_str())?;
 
-        Ok(Self { connection })
+        Ok(Self {
+            connection,
+            service_name,
+        })
     }
 
-    pub fn notify_updated(&self, menu_revision: u32) -> Result<(), zbus::Error> {
-        self.connection.emit_signal(
-            None::<&str>,
-            STATUS_NOTIFIER_ITEM_PATH,
-            STATUS_NOTIFIER_ITEM_IFACE,
-            "NewIcon",
-            &(),
-        )?;
+    pub fn register(&self) -> Result<(), zbus::Error> {
+        register_status_notifier_item(&self.connection, self.service_name.as_str())
+    }
 
-        self.connection.emit_signal(
-            None::<&str>,
-            STATUS_NOTIFIER_ITEM_PATH,
-            STATUS_NOTIFIER_ITEM_IFACE,
-            "NewToolTip",
-            &(),
-        )?;
+    /// Emits a signal for each changed property, so hosts only re-fetch what
+    /// actually changed.
+    pub fn notify_updated(
+        &self,
+        item_change: ItemChange,
+        menu_change: &MenuChange,
+        status: &str,
+    ) -> Result<(), zbus::Error> {
+        let item_signals = [
+            (item_change.icon, "NewIcon"),
+            (item_change.attention_icon, "NewAttentionIcon"),
+            (item_change.tooltip, "NewToolTip"),
+            (item_change.title, "NewTitle"),
+        ];
+        for (_, signal) in item_signals.iter().filter(|(changed, _)| *changed) {
+            self.connection.emit_signal(
+                None::<&str>,
+                STATUS_NOTIFIER_ITEM_PATH,
+                STATUS_NOTIFIER_ITEM_IFACE,
+                *signal,
+                &(),
+            )?;
+        }
 
-        self.connection.emit_signal(
-            None::<&str>,
-            STATUS_NOTIFIER_ITEM_PATH,
-            STATUS_NOTIFIER_ITEM_IFACE,
-            "NewTitle",
-            &(),
-        )?;
+        if item_change.status {
+            self.connection.emit_signal(
+                None::<&str>,
+                STATUS_NOTIFIER_ITEM_PATH,
+                STATUS_NOTIFIER_ITEM_IFACE,
+                "NewStatus",
+                &(status,),
+            )?;
+        }
 
-        self.connection.emit_signal(
-            None::<&str>,
-            DBUS_MENU_PATH,
-            DBUS_MENU_IFACE,
-            "LayoutUpdated",
-            &(menu_revision, 0i32),
-        )?;
+        match menu_change {
+            MenuChange::Unchanged => {}
+            MenuChange::Properties(updated) => {
+                let removed: Vec<(i32, Vec<String>)> = Vec::new();
+                self.connection.emit_signal(
+                    None::<&str>,
+                    DBUS_MENU_PATH,
+                    DBUS_MENU_IFACE,
+                    "ItemsPropertiesUpdated",
+                    &(updated, removed),
+                )?;
+                debug!("dbus notify_updated: menu items updated={}", updated.len());
+            }
+            MenuChange::Layout(revision) => {
+                self.connection.emit_signal(
+                    None::<&str>,
+                    DBUS_MENU_PATH,
+                    DBUS_MENU_IFACE,
+                    "LayoutUpdated",
+                    &(*revision, 0i32),
+                )?;
+                debug!("dbus notify_updated: menu_revision={revision}");
+            }
+        }
 
-        debug!("dbus notify_updated: menu_revision={menu_revision}");
+        debug!("dbus notify_updated: {item_change:?}, status={status}");
         Ok(())
     }
 }
@@ -495,3 +922,51 @@ fn register_status_notifier_item(
 
     Ok(())
 }
+
+/// Whether a StatusNotifierWatcher is running on the session bus.
+pub(crate) fn watcher_available() -> bool {
+    let has_owner = || -> Result<bool, zbus::Error> {
+        let connection = Connection::session()?;
+        let proxy = zbus::blocking::fdo::DBusProxy::new(&connection)?;
+        let name = zbus::names::BusName::try_from(STATUS_NOTIFIER_WATCHER)?;
+        Ok(proxy.name_has_owner(name)?)
+    };
+    has_owner().unwrap_or_else(|err| {
+        debug!("could not look up StatusNotifierWatcher: {err}");
+        false
+    })
+}
+
+/// Follows ownership of the StatusNotifierWatcher name on a dedicated
+/// connection, so the worker learns when the tray host goes away or returns.
+pub(crate) fn spawn_watcher_monitor(
+    event_sender: std::sync::mpsc::Sender<TrayEvent>,
+) -> Result<(), zbus::Error> {
+    let connection = Connection::session()?;
+    let proxy = zbus::blocking::fdo::DBusProxy::new(&connection)?;
+    let changes = proxy.receive_name_owner_changed_with_args(&[(0, STATUS_NOTIFIER_WATCHER)])?;
+
+    std::thread::Builder::new()
+        .name("gpui-tray-linux-watcher".to_string())
+        .spawn(move || {
+            let _connection = connection;
+            for signal in changes {
+                let Ok(args) = signal.args() else {
+                    continue;
+                };
+
+                let event = if args.new_owner().is_some() {
+                    TrayEvent::WatcherRegistered
+                } else {
+                    TrayEvent::WatcherLost
+                };
+                debug!("StatusNotifierWatcher owner changed: {event:?}");
+
+                if event_sender.send(event).is_err() {
+                    break;
+                }
+            }
+        })?;
+
+    Ok(())
+}
@@ -1,9 +1,8 @@
-use gpui_tray_core::Error;
+use gpui_tray_core::{Error, IconTarget, decode_icon_for};
+use std::collections::HashMap;
 use std::sync::Arc;
 use zbus::zvariant::{Structure, StructureBuilder, Type};
 
-const ICON_SIZES: [u32; 4] = [16, 24, 32, 48];
-
 #[derive(Debug, Clone, Type)]
 pub(crate) struct Pixmap {
     pub width: i32,
@@ -39,17 +38,13 @@ pub(crate) struct Icon {
 
 impl Icon {
     pub fn from_image(image: &gpui::Image) -> Result<Self, Error> {
-        let img = image::load_from_memory(&image.bytes).map_err(|_| Error::InvalidIcon)?;
-
-        let mut pixmaps = Vec::with_capacity(ICON_SIZES.len());
-
-        for size in ICON_SIZES {
-            let resized = img.resize_to_fill(size, size, image::imageops::FilterType::Lanczos3);
-            let rgba = resized.to_rgba8();
-            let argb = Self::rgba_to_argb_bytes(&rgba);
-
-            pixmaps.push(Pixmap::new(size as i32, size as i32, argb));
-        }
+        let pixmaps = decode_icon_for(image, IconTarget::StatusNotifierItem)?
+            .into_iter()
+            .map(|icon| {
+                let argb = Self::rgba_to_argb_bytes(&icon.rgba);
+                Pixmap::new(icon.size as i32, icon.size as i32, argb)
+            })
+            .collect();
 
         Ok(Self {
             pixmaps: Arc::new(pixmaps),
@@ -73,3 +68,33 @@ impl Icon {
         argb
     }
 }
+
+/// Pixmaps of the frames of a running animation, so each frame is decoded
+/// once rather than every time it comes round.
+#[derive(Default)]
+pub(crate) struct FrameCache {
+    frames: HashMap<u64, Icon>,
+}
+
+impl FrameCache {
+    /// Frames kept at most; longer animations decode the rest every time.
+    const MAX_FRAMES: usize = 256;
+
+    /// Decodes `image`, or reuses its pixmaps from an earlier frame. Only
+    /// frames of an animation are kept.
+    pub fn pixmaps(&mut self, image: &gpui::Image, animated: bool) -> Result<Vec<Pixmap>, Error> {
+        if let Some(icon) = self.frames.get(&image.id()) {
+            return Ok(icon.as_pixmaps().to_vec());
+        }
+        let icon = Icon::from_image(image)?;
+        let pixmaps = icon.as_pixmaps().to_vec();
+        if animated && self.frames.len() < Self::MAX_FRAMES {
+            self.frames.insert(image.id(), icon);
+        }
+        Ok(pixmaps)
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
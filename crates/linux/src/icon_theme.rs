@@ -0,0 +1,149 @@
+//! Lookup of themed icon names, for the debug-build check of
+//! `Tray::icon_name`.
+//!
+//! This follows the Icon Theme Specification far enough to catch a typo or
+//! an icon that only some themes ship: the active theme, the themes it
+//! inherits from and `hicolor`, in every icon directory. It does not match
+//! sizes or scales like a host does.
+
+use crate::portal;
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use zbus::blocking::Connection;
+use zbus::zvariant::{OwnedValue, Value};
+
+const FALLBACK_THEME: &str = "hicolor";
+const EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+/// Themes keep icons in `<size>/<context>` or `<context>/<size>`.
+const MAX_DEPTH: usize = 3;
+
+/// Whether the active icon theme, or a theme it falls back to, has an icon
+/// named `name`. Without a known active theme, every installed theme counts.
+pub(crate) fn has_icon(name: &str) -> bool {
+    let bases = base_dirs();
+    let themes = match active_theme() {
+        Some(theme) => theme_chain(&theme, &bases),
+        None => installed_themes(&bases),
+    };
+
+    themes
+        .iter()
+        .flat_map(|theme| bases.iter().map(move |base| base.join(theme)))
+        .any(|dir| contains_icon(&dir, name, MAX_DEPTH))
+        || contains_icon(Path::new("/usr/share/pixmaps"), name, 0)
+}
+
+/// Directories icon themes are installed in, most specific first.
+fn base_dirs() -> Vec<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| home.join(".local/share")));
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    let mut bases = Vec::new();
+    bases.extend(home.map(|home| home.join(".icons")));
+    bases.extend(data_home.map(|dir| dir.join("icons")));
+    bases.extend(
+        data_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| Path::new(dir).join("icons")),
+    );
+    bases
+}
+
+/// The icon theme the desktop uses, as the settings portal reports it.
+fn active_theme() -> Option<String> {
+    let connection = Connection::session().ok()?;
+    let proxy = portal::settings(&connection).ok()?;
+    let value: OwnedValue = proxy
+        .call("Read", &("org.gnome.desktop.interface", "icon-theme"))
+        .ok()?;
+    theme_name(&value)
+}
+
+/// The theme name in the portal's reply, if one is set.
+fn theme_name(value: &Value) -> Option<String> {
+    match portal::setting(value) {
+        Value::Str(name) if !name.is_empty() => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+/// `theme` followed by the themes it inherits from, ending with `hicolor`.
+fn theme_chain(theme: &str, bases: &[PathBuf]) -> Vec<String> {
+    let mut chain: Vec<String> = Vec::new();
+    let mut queue = VecDeque::from([theme.to_string()]);
+    while let Some(theme) = queue.pop_front() {
+        if chain.contains(&theme) {
+            continue;
+        }
+        queue.extend(inherited_themes(&theme, bases));
+        chain.push(theme);
+    }
+    if !chain.iter().any(|theme| theme == FALLBACK_THEME) {
+        chain.push(FALLBACK_THEME.to_string());
+    }
+    chain
+}
+
+/// The `Inherits` key of the first `index.theme` found for `theme`.
+fn inherited_themes(theme: &str, bases: &[PathBuf]) -> Vec<String> {
+    let Some(index) = bases
+        .iter()
+        .find_map(|base| fs::read_to_string(base.join(theme).join("index.theme")).ok())
+    else {
+        return Vec::new();
+    };
+    index
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Inherits="))
+        .map(|themes| {
+            themes
+                .split(',')
+                .map(str::trim)
+                .filter(|theme| !theme.is_empty())
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn installed_themes(bases: &[PathBuf]) -> Vec<String> {
+    let mut themes: Vec<String> = bases
+        .iter()
+        .filter_map(|base| fs::read_dir(base).ok())
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    themes.sort();
+    themes.dedup();
+    themes
+}
+
+/// Whether `dir`, or a directory up to `depth` levels below it, holds an
+/// icon file named `name`.
+fn contains_icon(dir: &Path, name: &str, depth: usize) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let path = entry.path();
+        if path.is_dir() {
+            return depth > 0 && contains_icon(&path, name, depth - 1);
+        }
+        path.file_stem().is_some_and(|stem| stem == name)
+            && path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| EXTENSIONS.contains(&extension))
+    })
+}
@@ -5,8 +5,19 @@ use gpui_tray_core::platform_trait::PlatformTray;
 
 mod dbus;
 mod icon;
+#[cfg(debug_assertions)]
+mod icon_theme;
+mod notify;
+mod portal;
+mod theme;
 mod tray;
+mod xembed;
 
 pub fn create() -> Result<Box<dyn PlatformTray>> {
     tray::create()
 }
+
+/// Whether a StatusNotifier host or an X11 tray manager can show an icon.
+pub fn is_supported() -> bool {
+    dbus::watcher_available() || xembed::manager_available()
+}
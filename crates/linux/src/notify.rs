@@ -0,0 +1,158 @@
+use crate::dbus::TrayEvent;
+use gpui_tray_core::{Notification, NotificationIcon, NotificationUrgency, decode_icon};
+use log::debug;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{StructureBuilder, Value};
+
+const NOTIFICATIONS: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+/// Action invoked when the notification body itself is clicked.
+const DEFAULT_ACTION: &str = "default";
+/// Edge length of the `image-data` sent to the server.
+const IMAGE_SIZE: u32 = 128;
+
+/// Client for the freedesktop notification server.
+pub(crate) struct Notifier {
+    connection: Connection,
+    /// Ids of notifications sent from here, so that clicks on other apps'
+    /// notifications are ignored.
+    sent: Arc<Mutex<HashSet<u32>>>,
+}
+
+impl Notifier {
+    pub fn new(event_sender: Sender<TrayEvent>) -> Result<Self, zbus::Error> {
+        let connection = Connection::session()?;
+        let proxy = Proxy::new(
+            &connection,
+            NOTIFICATIONS,
+            NOTIFICATIONS_PATH,
+            NOTIFICATIONS,
+        )?;
+        let invoked = proxy.receive_signal("ActionInvoked")?;
+        let closed = proxy.receive_signal("NotificationClosed")?;
+        let sent = Arc::new(Mutex::new(HashSet::new()));
+
+        let clicked_ids = sent.clone();
+        std::thread::Builder::new()
+            .name("gpui-tray-linux-notify".to_string())
+            .spawn(move || {
+                for message in invoked {
+                    let Ok((id, action)) = message.body().deserialize::<(u32, String)>() else {
+                        continue;
+                    };
+                    let ours = clicked_ids.lock().is_ok_and(|sent| sent.contains(&id));
+                    if !ours || action != DEFAULT_ACTION {
+                        continue;
+                    }
+
+                    debug!("linux notification {id} clicked");
+                    if event_sender.send(TrayEvent::NotificationClicked).is_err() {
+                        break;
+                    }
+                }
+            })?;
+
+        let closed_ids = sent.clone();
+        std::thread::Builder::new()
+            .name("gpui-tray-linux-notify-closed".to_string())
+            .spawn(move || {
+                for message in closed {
+                    if let Ok((id, _reason)) = message.body().deserialize::<(u32, u32)>()
+                        && let Ok(mut sent) = closed_ids.lock()
+                    {
+                        sent.remove(&id);
+                    }
+                }
+            })?;
+
+        Ok(Self { connection, sent })
+    }
+
+    pub fn notify(&self, app_name: &str, notification: &Notification) -> Result<(), zbus::Error> {
+        let options = &notification.options;
+        let app_icon = match options.icon {
+            NotificationIcon::Info => "dialog-information",
+            NotificationIcon::Warning => "dialog-warning",
+            NotificationIcon::Error => "dialog-error",
+            NotificationIcon::None | NotificationIcon::Custom(_) => "",
+        };
+
+        let mut hints: HashMap<&str, Value<'_>> = HashMap::new();
+        let urgency: u8 = match options.urgency {
+            NotificationUrgency::Low => 0,
+            NotificationUrgency::Normal => 1,
+            NotificationUrgency::Critical => 2,
+        };
+        hints.insert("urgency", Value::from(urgency));
+        if let Some(category) = options.category.as_ref() {
+            hints.insert("category", Value::from(category.as_ref()));
+        }
+        if let Some(sound_name) = options.sound_name.as_ref() {
+            hints.insert("sound-name", Value::from(sound_name.as_ref()));
+        }
+        if options.silent {
+            hints.insert("suppress-sound", Value::from(true));
+        }
+        if options.resident {
+            hints.insert("resident", Value::from(true));
+        }
+        if options.transient {
+            hints.insert("transient", Value::from(true));
+        }
+        if let NotificationIcon::Custom(image) = &options.icon {
+            match image_data(image) {
+                Some(data) => {
+                    hints.insert("image-data", data);
+                }
+                None => debug!("linux notification image could not be decoded"),
+            }
+        }
+
+        let proxy = Proxy::new(
+            &self.connection,
+            NOTIFICATIONS,
+            NOTIFICATIONS_PATH,
+            NOTIFICATIONS,
+        )?;
+        let id: u32 = proxy.call(
+            "Notify",
+            &(
+                app_name,
+                0u32,
+                app_icon,
+                notification.title.as_ref(),
+                notification.body.as_ref(),
+                vec![DEFAULT_ACTION, ""],
+                hints,
+                -1i32,
+            ),
+        )?;
+
+        debug!("linux notification {id} shown");
+        if let Ok(mut sent) = self.sent.lock() {
+            sent.insert(id);
+        }
+        Ok(())
+    }
+}
+
+/// Encodes an image as the `(iiibiiay)` structure of the `image-data` hint.
+fn image_data(image: &gpui::Image) -> Option<Value<'static>> {
+    let icon = decode_icon(image, IMAGE_SIZE).ok()?;
+    let size = icon.size as i32;
+
+    let structure = StructureBuilder::new()
+        .add_field(size)
+        .add_field(size)
+        .add_field(size * 4)
+        .add_field(true)
+        .add_field(8i32)
+        .add_field(4i32)
+        .add_field(icon.rgba)
+        .build()
+        .ok()?;
+    Some(Value::from(structure))
+}
@@ -0,0 +1,23 @@
+//! The settings portal of `xdg-desktop-portal`, where desktops publish
+//! settings such as the color scheme and the icon theme.
+
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::Value;
+
+const PORTAL: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SETTINGS: &str = "org.freedesktop.portal.Settings";
+
+/// Proxy for `Read` calls and `SettingChanged` signals.
+pub(crate) fn settings(connection: &Connection) -> Result<Proxy<'static>, zbus::Error> {
+    Proxy::new(connection, PORTAL, PORTAL_PATH, SETTINGS)
+}
+
+/// Returns the setting inside `value`. `Read` wraps each setting in one more
+/// variant than `SettingChanged` does.
+pub(crate) fn setting<'a>(value: &'a Value<'a>) -> &'a Value<'a> {
+    match value {
+        Value::Value(inner) => setting(inner),
+        value => value,
+    }
+}
@@ -0,0 +1,59 @@
+use crate::dbus::TrayEvent;
+use crate::portal;
+use gpui_tray_core::SystemTheme;
+use log::debug;
+use std::sync::mpsc::Sender;
+use zbus::blocking::Connection;
+use zbus::zvariant::{OwnedValue, Value};
+
+const APPEARANCE: &str = "org.freedesktop.appearance";
+const COLOR_SCHEME: &str = "color-scheme";
+
+/// Reports the desktop color scheme from the settings portal, first as it is
+/// now and then on every change.
+pub(crate) fn spawn_theme_monitor(event_sender: Sender<TrayEvent>) -> Result<(), zbus::Error> {
+    let connection = Connection::session()?;
+    let proxy = portal::settings(&connection)?;
+    let changes = proxy.receive_signal("SettingChanged")?;
+
+    let current: OwnedValue = proxy.call("Read", &(APPEARANCE, COLOR_SCHEME))?;
+    if let Some(theme) = system_theme(&current) {
+        let _ = event_sender.send(TrayEvent::ThemeChanged(theme));
+    }
+
+    std::thread::Builder::new()
+        .name("gpui-tray-linux-theme".to_string())
+        .spawn(move || {
+            let _connection = connection;
+            for message in changes {
+                let Ok((namespace, key, value)) =
+                    message.body().deserialize::<(String, String, OwnedValue)>()
+                else {
+                    continue;
+                };
+                if namespace != APPEARANCE || key != COLOR_SCHEME {
+                    continue;
+                }
+                let Some(theme) = system_theme(&value) else {
+                    continue;
+                };
+
+                debug!("linux color scheme changed to {theme:?}");
+                if event_sender.send(TrayEvent::ThemeChanged(theme)).is_err() {
+                    break;
+                }
+            }
+        })?;
+
+    Ok(())
+}
+
+/// `color-scheme` is 1 for dark and 2 for light; 0, no preference, is shown
+/// as light like the desktops do.
+fn system_theme(value: &Value) -> Option<SystemTheme> {
+    match portal::setting(value) {
+        Value::U32(1) => Some(SystemTheme::Dark),
+        Value::U32(_) => Some(SystemTheme::Light),
+        _ => None,
+    }
+}
@@ -1,29 +1,57 @@
-use crate::dbus::{DbusService, ItemState, MenuState, TrayEvent};
-use crate::icon::Icon;
-use gpui::{Action, MenuItem, MouseButton, Point};
+use crate::dbus::{
+    DbusService, ItemChange, ItemState, MenuChange, MenuState, ToggleType, TrayEvent,
+    spawn_watcher_monitor, watcher_available,
+};
+use crate::icon::{FrameCache, Icon};
+#[cfg(debug_assertions)]
+use crate::icon_theme;
+use crate::notify::Notifier;
+use crate::theme::spawn_theme_monitor;
+use crate::xembed::XEmbedTray;
+use gpui::{Image, MouseButton, Point};
 use gpui_tray_core::platform_trait::PlatformTray;
-use gpui_tray_core::{BackendError, ClickEvent, Error, Result, RuntimeEvent, Tray};
+use gpui_tray_core::{
+    BackendError, ClickEvent, DoubleClickEvent, Error, EventQueuePolicy, EventQueueStats,
+    EventReceiver, EventSender, HostRestartedEvent, LinuxTrayBackend, MenuItemKind,
+    MenuOpenedEvent, Notification, NotificationClickedEvent, Result, RuntimeEvent, ScrollDelta,
+    ScrollEvent, ScrollOrientation, ThemeChangedEvent, Tray, TrayMenuItem, TrayStatus,
+    UpdateDuringMenuPolicy, event_queue,
+};
 use log::{debug, error};
 use std::collections::HashMap;
-use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
-use std::time::Duration;
 
 enum BackendCommand {
     SetTray {
-        tray: Tray,
+        tray: Box<Tray>,
+        response: Sender<Result<()>>,
+    },
+    SetIcon {
+        icon: Option<Image>,
         response: Sender<Result<()>>,
     },
     RemoveTray {
         response: Sender<Result<()>>,
     },
+    Notify {
+        notification: Box<Notification>,
+        response: Sender<Result<()>>,
+    },
+    Flush {
+        response: Sender<Result<()>>,
+    },
+    /// A call from the host or the notification server, forwarded so the
+    /// worker can block on a single channel.
+    TrayEvent(TrayEvent),
     Shutdown,
 }
 
 pub(crate) struct LinuxBackend {
     command_tx: Sender<BackendCommand>,
-    event_rx: Mutex<Receiver<RuntimeEvent>>,
+    event_rx: EventReceiver,
 }
 
 impl LinuxBackend {
@@ -40,20 +68,46 @@ impl LinuxBackend {
 
 impl PlatformTray for LinuxBackend {
     fn set_tray(&self, tray: Tray) -> Result<()> {
-        self.send_and_wait(|response| BackendCommand::SetTray { tray, response })
+        self.send_and_wait(|response| BackendCommand::SetTray {
+            tray: Box::new(tray),
+            response,
+        })
+    }
+
+    fn set_icon(&self, icon: Option<Image>) -> Result<()> {
+        self.send_and_wait(|response| BackendCommand::SetIcon { icon, response })
     }
 
     fn remove_tray(&self) -> Result<()> {
         self.send_and_wait(|response| BackendCommand::RemoveTray { response })
     }
 
+    // The SNI host owns the menu and opens it itself.
+    fn show_menu(&self) -> Result<()> {
+        Err(Error::UnsupportedPlatform)
+    }
+
+    fn notify(&self, notification: Notification) -> Result<()> {
+        self.send_and_wait(|response| BackendCommand::Notify {
+            notification: Box::new(notification),
+            response,
+        })
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.send_and_wait(|response| BackendCommand::Flush { response })
+    }
+
     fn try_recv_event(&self) -> Result<Option<RuntimeEvent>> {
-        let rx = self.event_rx.lock().map_err(|_| Error::RuntimeClosed)?;
-        match rx.try_recv() {
-            Ok(event) => Ok(Some(event)),
-            Err(TryRecvError::Empty) => Ok(None),
-            Err(TryRecvError::Disconnected) => Err(Error::RuntimeClosed),
-        }
+        self.event_rx.try_recv()
+    }
+
+    fn set_event_policy(&self, policy: EventQueuePolicy) {
+        self.event_rx.set_policy(policy);
+    }
+
+    fn event_stats(&self) -> EventQueueStats {
+        self.event_rx.stats()
     }
 
     fn shutdown(&self) -> Result<()> {
@@ -64,28 +118,68 @@ impl PlatformTray for LinuxBackend {
     }
 }
 
+// The worker keeps a sender of its own for forwarded host calls, so it never
+// sees the channel disconnect and has to be told to stop.
+impl Drop for LinuxBackend {
+    fn drop(&mut self) {
+        let _ = self.command_tx.send(BackendCommand::Shutdown);
+    }
+}
+
 struct WorkerState {
     service: Option<DbusService>,
+    /// Docked icon when the legacy X11 tray is used instead of `service`.
+    xembed: Option<XEmbedTray>,
+    /// Connected on the first notification.
+    notifier: Option<Notifier>,
     item_state: Arc<Mutex<ItemState>>,
     menu_state: Arc<Mutex<MenuState>>,
-    menu_actions: HashMap<i32, Box<dyn Action>>,
+    /// Id of the image `item_state.icon` was decoded from.
+    icon_id: Option<u64>,
+    /// Id of the image `item_state.attention_icon` was decoded from.
+    attention_icon_id: Option<u64>,
+    frame_cache: FrameCache,
     current_tray: Option<Tray>,
+    /// A menu change was held back while the menu was open.
+    menu_pending: bool,
+    /// Token from the host for the next activation.
+    activation_token: Option<String>,
     tray_event_tx: Sender<TrayEvent>,
+    /// Whether the tray has a raw event handler, so host calls are
+    /// forwarded to it.
+    raw_events: Arc<AtomicBool>,
+    watcher_monitor_started: bool,
 }
 
 impl WorkerState {
     fn new(tray_event_tx: Sender<TrayEvent>) -> Self {
         Self {
             service: None,
+            xembed: None,
+            notifier: None,
             item_state: Arc::new(Mutex::new(ItemState {
                 title: String::new(),
                 tooltip: String::new(),
+                tooltip_body: String::new(),
                 icon: None,
+                icon_name: String::new(),
+                attention_icon: None,
+                attention_icon_name: String::new(),
+                attention_movie_name: String::new(),
+                status: TrayStatus::Active,
+                item_is_menu: false,
+                window_id: 0,
             })),
             menu_state: Arc::new(Mutex::new(MenuState::new())),
-            menu_actions: HashMap::new(),
+            icon_id: None,
+            attention_icon_id: None,
+            frame_cache: FrameCache::default(),
             current_tray: None,
+            menu_pending: false,
+            activation_token: None,
             tray_event_tx,
+            raw_events: Arc::new(AtomicBool::new(false)),
+            watcher_monitor_started: false,
         }
     }
 
@@ -98,7 +192,12 @@ impl WorkerState {
             tray.menu_builder.is_some()
         );
 
+        self.raw_events
+            .store(tray.raw_event_handler.is_some(), Ordering::Relaxed);
         self.current_tray = Some(tray.clone());
+        if tray.animation.is_none() {
+            self.frame_cache.clear();
+        }
 
         if !tray.visible {
             self.hide_tray();
@@ -108,21 +207,77 @@ impl WorkerState {
         let had_service = self.service.is_some();
 
         // Build state first, then publish service. This avoids register/query races.
-        self.update_item_state(&tray)?;
-        let menu_revision = self.rebuild_menu(&tray)?;
+        let (item_change, status) = self.update_item_state(&tray)?;
+        let menu_change = self.rebuild_menu(&tray)?;
+
+        if self.wants_xembed(&tray) {
+            match self.show_xembed(&tray) {
+                Ok(()) => return Ok(()),
+                Err(err) if tray.linux_backend == LinuxTrayBackend::XEmbed => return Err(err),
+                Err(err) => debug!("linux XEmbed fallback unavailable, using SNI: {err}"),
+            }
+        }
+        self.xembed = None;
         self.ensure_service()?;
 
         if had_service {
             let service = self.service.as_ref().ok_or(Error::RuntimeClosed)?;
-            service.notify_updated(menu_revision).map_err(|err| {
+            service
+                .notify_updated(item_change, &menu_change, status)
+                .map_err(|err| {
+                    Error::Backend(BackendError::platform(
+                        "DbusService::notify_updated",
+                        err.to_string(),
+                    ))
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Swaps only the icon, without rebuilding the menu.
+    fn apply_set_icon(&mut self, icon: Option<Image>) -> Result<()> {
+        let tray = self.current_tray.as_mut().ok_or(Error::NotFound)?;
+        tray.icon = icon;
+        if !tray.visible {
+            return Ok(());
+        }
+        if self.xembed.is_some() {
+            let tray = tray.clone();
+            return self.show_xembed(&tray);
+        }
+
+        let icon_id = tray.icon.as_ref().map(|image| image.id());
+        if icon_id == self.icon_id {
+            return Ok(());
+        }
+        let animated = tray.animation.is_some();
+        let pixmaps = match tray.icon.as_ref() {
+            Some(image) => Some(self.frame_cache.pixmaps(image, animated)?),
+            None => None,
+        };
+        self.icon_id = icon_id;
+        let status = {
+            let mut state = lock_mutex(&self.item_state)?;
+            state.icon = pixmaps;
+            state.status()
+        };
+
+        let Some(service) = self.service.as_ref() else {
+            return Ok(());
+        };
+        let change = ItemChange {
+            icon: true,
+            ..ItemChange::default()
+        };
+        service
+            .notify_updated(change, &MenuChange::Unchanged, status)
+            .map_err(|err| {
                 Error::Backend(BackendError::platform(
                     "DbusService::notify_updated",
                     err.to_string(),
                 ))
-            })?;
-        }
-
-        Ok(())
+            })
     }
 
     fn apply_remove_tray(&mut self) -> Result<()> {
@@ -131,116 +286,259 @@ impl WorkerState {
         }
 
         self.current_tray = None;
+        self.raw_events.store(false, Ordering::Relaxed);
         self.hide_tray();
         Ok(())
     }
 
+    fn apply_notify(&mut self, notification: &Notification) -> Result<()> {
+        if self.notifier.is_none() {
+            let notifier = Notifier::new(self.tray_event_tx.clone()).map_err(|err| {
+                Error::Backend(BackendError::platform("Notifier::new", err.to_string()))
+            })?;
+            self.notifier = Some(notifier);
+        }
+        let notifier = self.notifier.as_ref().ok_or(Error::RuntimeClosed)?;
+
+        let app_name = lock_mutex(&self.item_state)?.title.clone();
+        notifier.notify(&app_name, notification).map_err(|err| {
+            Error::Backend(BackendError::platform(
+                "org.freedesktop.Notifications.Notify",
+                err.to_string(),
+            ))
+        })
+    }
+
     fn hide_tray(&mut self) {
         self.service = None;
-        self.menu_actions.clear();
+        self.xembed = None;
+        self.icon_id = None;
+        self.attention_icon_id = None;
 
         if let Ok(mut item_state) = self.item_state.lock() {
             item_state.icon = None;
+            item_state.attention_icon = None;
         }
 
         if let Ok(mut menu_state) = self.menu_state.lock() {
             menu_state.clear();
+            menu_state.set_actions(HashMap::new());
+            menu_state.set_refresh(None);
+        }
+    }
+
+    fn wants_xembed(&self, tray: &Tray) -> bool {
+        match tray.linux_backend {
+            LinuxTrayBackend::Sni => false,
+            LinuxTrayBackend::XEmbed => true,
+            // Keep the protocol picked when the icon was first shown.
+            LinuxTrayBackend::Auto if self.service.is_some() => false,
+            LinuxTrayBackend::Auto if self.xembed.is_some() => true,
+            LinuxTrayBackend::Auto => !watcher_available(),
         }
     }
 
+    fn show_xembed(&mut self, tray: &Tray) -> Result<()> {
+        let xembed_error = |operation, err: crate::xembed::XEmbedError| {
+            BackendError::platform(operation, err.to_string())
+        };
+
+        if self.xembed.is_none() {
+            let xembed = XEmbedTray::new(self.tray_event_tx.clone())
+                .map_err(|err| xembed_error("XEmbedTray::new", err))?;
+            self.service = None;
+            self.xembed = Some(xembed);
+        }
+
+        let title = lock_mutex(&self.item_state)?.title.clone();
+        let xembed = self.xembed.as_mut().ok_or(Error::RuntimeClosed)?;
+        xembed
+            .update(
+                tray.icon.as_ref(),
+                &title,
+                tray.current_status() == TrayStatus::Passive,
+            )
+            .map_err(|err| xembed_error("XEmbedTray::update", err).into())
+    }
+
     fn ensure_service(&mut self) -> Result<()> {
         if self.service.is_some() {
             return Ok(());
         }
 
+        if !self.watcher_monitor_started {
+            match spawn_watcher_monitor(self.tray_event_tx.clone()) {
+                Ok(()) => self.watcher_monitor_started = true,
+                Err(err) => error!("failed to monitor StatusNotifierWatcher: {err}"),
+            }
+        }
+
         let service = DbusService::new(
             self.item_state.clone(),
             self.menu_state.clone(),
             self.tray_event_tx.clone(),
+            self.raw_events.clone(),
         )
         .map_err(|err| {
-            Error::Backend(BackendError::platform("DbusService::new", err.to_string()))
+            if watcher_available() {
+                Error::Backend(BackendError::platform("DbusService::new", err.to_string()))
+            } else {
+                Error::PlatformUnavailable {
+                    reason: format!("no StatusNotifierWatcher is running ({err})"),
+                }
+            }
         })?;
         self.service = Some(service);
         Ok(())
     }
 
-    fn update_item_state(&mut self, tray: &Tray) -> Result<()> {
+    fn update_item_state(&mut self, tray: &Tray) -> Result<(ItemChange, &'static str)> {
         let mut state = lock_mutex(&self.item_state)?;
 
-        state.tooltip = tray
+        let tooltip = tray
             .tooltip
             .as_ref()
             .map(ToString::to_string)
             .unwrap_or_default();
 
-        state.title = tray
-            .title
+        let title = tray.resolved_sni_title().to_string();
+        let tooltip_body = tray
+            .tooltip_body
             .as_ref()
             .map(ToString::to_string)
-            .or_else(|| {
-                if state.tooltip.is_empty() {
-                    None
-                } else {
-                    Some(state.tooltip.clone())
-                }
-            })
-            .unwrap_or_else(|| "gpui-tray".to_string());
+            .unwrap_or_default();
 
-        state.icon = match tray.icon.as_ref() {
-            Some(image) => Some(Icon::from_image(image)?.as_pixmaps().to_vec()),
-            None => None,
+        let icon_id = tray.icon.as_ref().map(|image| image.id());
+        let icon_name = tray
+            .icon_name
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        let icon_name_changed = icon_name != state.icon_name;
+        let attention_icon_id = tray.attention_icon.as_ref().map(|image| image.id());
+        let attention_icon_name = tray
+            .attention_icon_name
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        let attention_movie_name = tray
+            .attention_movie_name
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        let attention_icon_name_changed = attention_icon_name != state.attention_icon_name;
+        let change = ItemChange {
+            icon: icon_id != self.icon_id || icon_name_changed,
+            attention_icon: attention_icon_id != self.attention_icon_id
+                || attention_icon_name_changed
+                || attention_movie_name != state.attention_movie_name,
+            tooltip: tooltip != state.tooltip || tooltip_body != state.tooltip_body,
+            title: title != state.title,
+            status: tray.current_status() != state.status,
         };
 
+        #[cfg(debug_assertions)]
+        if icon_name_changed && !icon_name.is_empty() && !icon_theme::has_icon(&icon_name) {
+            log::warn!("no installed icon theme has an icon named '{icon_name}'");
+        }
+        #[cfg(debug_assertions)]
+        if attention_icon_name_changed
+            && !attention_icon_name.is_empty()
+            && !icon_theme::has_icon(&attention_icon_name)
+        {
+            log::warn!("no installed icon theme has an icon named '{attention_icon_name}'");
+        }
+        state.icon_name = icon_name;
+        state.attention_icon_name = attention_icon_name;
+        state.attention_movie_name = attention_movie_name;
+
+        // Decoding dominates the cost of an update, so only redo it for a
+        // different image.
+        if icon_id != self.icon_id {
+            state.icon = match tray.icon.as_ref() {
+                Some(image) => Some(self.frame_cache.pixmaps(image, tray.animation.is_some())?),
+                None => None,
+            };
+            self.icon_id = icon_id;
+        }
+        if attention_icon_id != self.attention_icon_id {
+            state.attention_icon = match tray.attention_icon.as_ref() {
+                Some(image) => Some(Icon::from_image(image)?.as_pixmaps().to_vec()),
+                None => None,
+            };
+            self.attention_icon_id = attention_icon_id;
+        }
+        state.tooltip = tooltip;
+        state.tooltip_body = tooltip_body;
+        state.title = title;
+        state.status = tray.current_status();
+        state.item_is_menu = tray.menu_trigger.opens_on(MouseButton::Left);
+        state.window_id = tray.linux_window_id.map_or(0, |id| id as i32);
+
         debug!(
-            "linux item state updated: title='{}', tooltip_len={}, has_icon={}",
+            "linux item state updated: title='{}', tooltip_len={}, has_icon={}, status={}",
             state.title,
             state.tooltip.len(),
-            state.icon.is_some()
+            state.icon.is_some(),
+            state.status()
         );
 
-        Ok(())
+        Ok((change, state.status()))
     }
 
-    fn rebuild_menu(&mut self, tray: &Tray) -> Result<u32> {
-        let mut actions = HashMap::new();
-        let revision;
-        {
-            let mut menu_state = lock_mutex(&self.menu_state)?;
-            menu_state.clear();
+    fn rebuild_menu(&mut self, tray: &Tray) -> Result<MenuChange> {
+        let mut menu_state = lock_mutex(&self.menu_state)?;
 
-            if let Some(builder) = tray.menu_builder.as_ref() {
-                let items = builder();
-                debug!("linux menu rebuild: top-level-items={}", items.len());
+        // Hosts ask for a fresh menu right before opening it.
+        let refresh_tray = tray.clone();
+        menu_state.set_refresh(Some(Box::new(move |menu_state| {
+            rebuild_menu_state(menu_state, &refresh_tray)
+        })));
 
-                for item in &items {
-                    add_menu_item(&mut menu_state, &mut actions, item, 0);
-                }
-            }
+        if menu_state.is_open() && tray.update_during_menu == UpdateDuringMenuPolicy::Defer {
+            debug!("linux menu is open, deferring menu update until it closes");
+            self.menu_pending = true;
+            return Ok(MenuChange::Unchanged);
+        }
+        self.menu_pending = false;
+        Ok(rebuild_menu_state(&mut menu_state, tray))
+    }
 
-            menu_state.mark_updated();
-            revision = menu_state.revision();
+    /// Applies the menu change held back while the menu was open.
+    fn apply_deferred_menu(&mut self) -> Result<()> {
+        if !std::mem::take(&mut self.menu_pending) {
+            return Ok(());
         }
+        let Some(tray) = self.current_tray.clone() else {
+            return Ok(());
+        };
 
-        debug!(
-            "linux menu actions={}, revision={}",
-            actions.len(),
-            revision
-        );
-        self.menu_actions = actions;
-        Ok(revision)
+        let menu_change = self.rebuild_menu(&tray)?;
+        let status = lock_mutex(&self.item_state)?.status();
+        if let Some(service) = self.service.as_ref() {
+            service
+                .notify_updated(ItemChange::default(), &menu_change, status)
+                .map_err(|err| {
+                    Error::Backend(BackendError::platform(
+                        "DbusService::notify_updated",
+                        err.to_string(),
+                    ))
+                })?;
+        }
+        Ok(())
     }
 }
 
 pub fn create() -> Result<Box<dyn PlatformTray>> {
     let (command_tx, command_rx) = mpsc::channel::<BackendCommand>();
-    let (runtime_event_tx, runtime_event_rx) = mpsc::channel::<RuntimeEvent>();
+    let (runtime_event_tx, runtime_event_rx) = event_queue(EventQueuePolicy::default());
     let (boot_tx, boot_rx) = mpsc::channel::<Result<()>>();
 
+    let worker_tx = command_tx.clone();
     thread::Builder::new()
         .name("gpui-tray-linux".to_string())
         .spawn(move || {
-            backend_thread_main(command_rx, runtime_event_tx, boot_tx);
+            backend_thread_main(command_rx, worker_tx, runtime_event_tx, boot_tx);
         })
         .map_err(|err| Error::Backend(BackendError::platform("spawn", err.to_string())))?;
 
@@ -250,119 +548,320 @@ pub fn create() -> Result<Box<dyn PlatformTray>> {
 
     Ok(Box::new(LinuxBackend {
         command_tx,
-        event_rx: Mutex::new(runtime_event_rx),
+        event_rx: runtime_event_rx,
     }))
 }
 
 fn backend_thread_main(
     command_rx: Receiver<BackendCommand>,
-    runtime_event_tx: Sender<RuntimeEvent>,
+    command_tx: Sender<BackendCommand>,
+    runtime_event_tx: EventSender,
     boot_tx: Sender<Result<()>>,
 ) {
     let (tray_event_tx, tray_event_rx) = mpsc::channel::<TrayEvent>();
+    if let Err(err) = spawn_event_forwarder(tray_event_rx, command_tx) {
+        let _ = boot_tx.send(Err(err));
+        return;
+    }
     let mut state = WorkerState::new(tray_event_tx);
 
     let _ = boot_tx.send(Ok(()));
 
-    let mut running = true;
-    while running {
-        match command_rx.recv_timeout(Duration::from_millis(12)) {
-            Ok(command) => {
-                running = handle_command(&mut state, command);
-
-                while let Ok(command) = command_rx.try_recv() {
-                    if !handle_command(&mut state, command) {
-                        running = false;
-                        break;
-                    }
-                }
-            }
-            Err(mpsc::RecvTimeoutError::Timeout) => {}
-            Err(mpsc::RecvTimeoutError::Disconnected) => {
-                running = false;
-            }
-        }
+    if let Err(err) = spawn_theme_monitor(state.tray_event_tx.clone()) {
+        debug!("linux color scheme unavailable: {err}");
+    }
 
-        while let Ok(event) = tray_event_rx.try_recv() {
-            handle_tray_event(&state, event, &runtime_event_tx);
+    // Nothing is polled: the worker sleeps until the app or the host calls in.
+    while let Ok(command) = command_rx.recv() {
+        if !handle_command(&mut state, command, &runtime_event_tx) {
+            break;
         }
     }
 
     state.hide_tray();
 }
 
-fn handle_command(state: &mut WorkerState, command: BackendCommand) -> bool {
+/// Moves host calls into the command channel.
+///
+/// Ends once the worker state, and with it every sender of host calls, is
+/// dropped.
+fn spawn_event_forwarder(
+    tray_event_rx: Receiver<TrayEvent>,
+    command_tx: Sender<BackendCommand>,
+) -> Result<()> {
+    thread::Builder::new()
+        .name("gpui-tray-linux-events".to_string())
+        .spawn(move || {
+            for event in tray_event_rx {
+                if command_tx.send(BackendCommand::TrayEvent(event)).is_err() {
+                    break;
+                }
+            }
+        })
+        .map(|_| ())
+        .map_err(|err| Error::Backend(BackendError::platform("spawn", err.to_string())))
+}
+
+fn handle_command(
+    state: &mut WorkerState,
+    command: BackendCommand,
+    runtime_event_tx: &EventSender,
+) -> bool {
     match command {
         BackendCommand::SetTray { tray, response } => {
-            let _ = response.send(state.apply_set_tray(tray));
+            let _ = response.send(state.apply_set_tray(*tray));
+            true
+        }
+        BackendCommand::SetIcon { icon, response } => {
+            let _ = response.send(state.apply_set_icon(icon));
             true
         }
         BackendCommand::RemoveTray { response } => {
             let _ = response.send(state.apply_remove_tray());
             true
         }
+        // Every update is applied synchronously, so reaching this command
+        // means all earlier ones are done.
+        BackendCommand::Notify {
+            notification,
+            response,
+        } => {
+            let _ = response.send(state.apply_notify(&notification));
+            true
+        }
+        BackendCommand::Flush { response } => {
+            let _ = response.send(Ok(()));
+            true
+        }
+        BackendCommand::TrayEvent(event) => {
+            handle_tray_event(state, event, runtime_event_tx);
+            true
+        }
         BackendCommand::Shutdown => false,
     }
 }
 
-fn handle_tray_event(
-    state: &WorkerState,
-    event: TrayEvent,
-    runtime_event_tx: &Sender<RuntimeEvent>,
-) {
+fn handle_tray_event(state: &mut WorkerState, event: TrayEvent, runtime_event_tx: &EventSender) {
     match event {
+        TrayEvent::ActivationToken(token) => {
+            state.activation_token = Some(token);
+        }
         TrayEvent::Activate { x, y } => {
-            dispatch_click(runtime_event_tx, MouseButton::Left, x, y);
+            let token = state.activation_token.take();
+            dispatch_click(runtime_event_tx, MouseButton::Left, x, y, token);
         }
         TrayEvent::SecondaryActivate { x, y } => {
-            dispatch_click(runtime_event_tx, MouseButton::Middle, x, y);
+            let token = state.activation_token.take();
+            dispatch_click(runtime_event_tx, MouseButton::Middle, x, y, token);
         }
         TrayEvent::ContextMenu { x, y } => {
-            dispatch_click(runtime_event_tx, MouseButton::Right, x, y);
+            dispatch_click(runtime_event_tx, MouseButton::Right, x, y, None);
+        }
+        TrayEvent::DoubleClick { button, x, y } => {
+            dispatch_click(runtime_event_tx, button, x, y, None);
+            runtime_event_tx.send(RuntimeEvent::Action(Box::new(DoubleClickEvent {
+                button,
+                position: Point::new(x as f32, y as f32),
+            })));
+        }
+        TrayEvent::Scroll { delta, orientation } => {
+            runtime_event_tx.send(RuntimeEvent::Action(Box::new(ScrollEvent {
+                delta: scroll_delta(delta, &orientation),
+            })));
         }
         TrayEvent::MenuClicked { id } => {
-            if let Some(action) = state.menu_actions.get(&id) {
+            let item = state
+                .menu_state
+                .lock()
+                .ok()
+                .and_then(|menu_state| menu_state.action(id).cloned());
+            if let Some(item) = item {
                 debug!("linux menu click id={id}");
-                let _ = runtime_event_tx.send(RuntimeEvent::Action(action.boxed_clone()));
+                dispatch_menu_selection(runtime_event_tx, &item);
             } else {
                 error!("linux menu click id={id} had no mapped action");
             }
         }
+        TrayEvent::MenuOpened => {
+            runtime_event_tx.send(RuntimeEvent::Action(Box::new(MenuOpenedEvent)));
+        }
+        TrayEvent::MenuClosed => {
+            if let Err(err) = state.apply_deferred_menu() {
+                runtime_event_tx.send(RuntimeEvent::Error(err));
+            }
+        }
+        TrayEvent::NotificationClicked => {
+            runtime_event_tx.send(RuntimeEvent::Action(Box::new(NotificationClickedEvent)));
+        }
+        TrayEvent::ThemeChanged(theme) => {
+            runtime_event_tx.send(RuntimeEvent::Action(Box::new(ThemeChangedEvent { theme })));
+        }
+        TrayEvent::WatcherLost => {
+            // A host that went away never reports its menu closing.
+            close_menu(state, runtime_event_tx);
+            if state.service.is_some() {
+                let err = BackendError::platform(
+                    "StatusNotifierWatcher",
+                    "tray host vanished; waiting for it to come back",
+                );
+                runtime_event_tx.send(RuntimeEvent::Error(err.into()));
+            }
+        }
+        TrayEvent::Raw(event) => {
+            if let Some(handler) = state
+                .current_tray
+                .as_ref()
+                .and_then(|tray| tray.raw_event_handler.as_ref())
+            {
+                handler(&event);
+            }
+        }
+        TrayEvent::XEmbedDocked => {
+            runtime_event_tx.send(RuntimeEvent::Action(Box::new(HostRestartedEvent)));
+        }
+        TrayEvent::WatcherRegistered => {
+            close_menu(state, runtime_event_tx);
+            let Some(service) = state.service.as_ref() else {
+                return;
+            };
+
+            debug!("linux StatusNotifierWatcher returned, registering item again");
+            match service.register() {
+                Ok(()) => runtime_event_tx.send(RuntimeEvent::Action(Box::new(HostRestartedEvent))),
+                Err(err) => {
+                    let err = BackendError::platform("RegisterStatusNotifierItem", err.to_string());
+                    runtime_event_tx.send(RuntimeEvent::Error(err.into()));
+                }
+            }
+        }
     }
 }
 
-fn dispatch_click(runtime_event_tx: &Sender<RuntimeEvent>, button: MouseButton, x: i32, y: i32) {
+/// Treats the menu as closed and applies any change held back while it was
+/// open.
+fn close_menu(state: &mut WorkerState, runtime_event_tx: &EventSender) {
+    if let Ok(mut menu_state) = state.menu_state.lock() {
+        menu_state.mark_closed();
+    }
+    if let Err(err) = state.apply_deferred_menu() {
+        runtime_event_tx.send(RuntimeEvent::Error(err));
+    }
+}
+
+fn dispatch_click(
+    runtime_event_tx: &EventSender,
+    button: MouseButton,
+    x: i32,
+    y: i32,
+    activation_token: Option<String>,
+) {
     debug!("linux click button={:?}, x={}, y={}", button, x, y);
 
     let event = ClickEvent {
         button,
         position: Point::new(x as f32, y as f32),
+        activation_token: activation_token.map(Into::into),
+    };
+
+    runtime_event_tx.send(RuntimeEvent::Action(Box::new(event)));
+}
+
+fn dispatch_menu_selection(runtime_event_tx: &EventSender, item: &TrayMenuItem) {
+    // Hosts should not deliver clicks on disabled items, but not all of them honor it.
+    if !item.enabled {
+        return;
+    }
+    runtime_event_tx.send(RuntimeEvent::MenuSelection(item.selection_actions()));
+}
+
+/// Converts an SNI scroll into wheel notches.
+///
+/// Hosts disagree on units: KDE forwards raw wheel angles (120 per notch),
+/// most others send one step per notch. Multiples of 120 are treated as the
+/// former.
+fn scroll_delta(delta: i32, orientation: &str) -> ScrollDelta {
+    const WHEEL_DELTA: i32 = 120;
+
+    let lines = if delta != 0 && delta % WHEEL_DELTA == 0 {
+        (delta / WHEEL_DELTA) as f32
+    } else {
+        delta as f32
     };
+    let orientation = if orientation.eq_ignore_ascii_case("horizontal") {
+        ScrollOrientation::Horizontal
+    } else {
+        ScrollOrientation::Vertical
+    };
+
+    ScrollDelta {
+        lines,
+        precise: false,
+        orientation,
+    }
+}
+
+fn rebuild_menu_state(menu_state: &mut MenuState, tray: &Tray) -> MenuChange {
+    let mut actions = HashMap::new();
+    let items = tray.build_menu();
+    debug!("linux menu rebuild: top-level-items={}", items.len());
 
-    let _ = runtime_event_tx.send(RuntimeEvent::Action(Box::new(event)));
+    let change = menu_state.rebuild(|menu_state| {
+        for item in &items {
+            add_menu_item(menu_state, &mut actions, item, 0);
+        }
+    });
+
+    debug!(
+        "linux menu actions={}, layout_changed={}",
+        actions.len(),
+        matches!(change, MenuChange::Layout(_))
+    );
+    menu_state.set_actions(actions);
+    change
 }
 
 fn add_menu_item(
     menu_state: &mut MenuState,
-    actions: &mut HashMap<i32, Box<dyn Action>>,
-    item: &MenuItem,
+    actions: &mut HashMap<i32, TrayMenuItem>,
+    item: &TrayMenuItem,
     parent_id: i32,
 ) {
-    match item {
-        MenuItem::Separator => {
-            menu_state.add_separator(parent_id);
+    // Hidden items stay in the layout so toggling them keeps ids stable and
+    // only needs a property update.
+    let id = match &item.kind {
+        MenuItemKind::Separator => menu_state.add_separator(parent_id),
+        MenuItemKind::Action(_) => {
+            let id = menu_state.add_item(item.display_label().to_string(), parent_id);
+            actions.insert(id, item.clone());
+            id
         }
-        MenuItem::Action { name, action, .. } => {
-            let id = menu_state.add_item(name.to_string(), parent_id);
-            actions.insert(id, action.boxed_clone());
+        MenuItemKind::Checkbox { checked, .. } | MenuItemKind::Radio { checked, .. } => {
+            let id = menu_state.add_item(item.display_label().to_string(), parent_id);
+            let toggle_type = match item.kind {
+                MenuItemKind::Radio { .. } => ToggleType::Radio,
+                _ => ToggleType::Checkmark,
+            };
+            menu_state.set_toggle(id, toggle_type, *checked);
+            actions.insert(id, item.clone());
+            id
         }
-        MenuItem::Submenu(submenu) => {
-            let id = menu_state.add_item(submenu.name.to_string(), parent_id);
-            for child in &submenu.items {
+        MenuItemKind::Submenu(children) => {
+            let id = menu_state.add_item(item.display_label().to_string(), parent_id);
+            for child in children {
                 add_menu_item(menu_state, actions, child, id);
             }
+            id
         }
-        _ => {}
+    };
+
+    if !item.visible {
+        menu_state.set_visible(id, false);
+    }
+    if !item.enabled {
+        menu_state.set_enabled(id, false);
+    }
+    if let Some(accelerator) = &item.accelerator {
+        menu_state.set_shortcut(id, accelerator);
     }
 }
 
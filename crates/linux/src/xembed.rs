@@ -0,0 +1,494 @@
+//! Legacy XEmbed system tray, for X11 desktops without a
+//! StatusNotifierWatcher.
+//!
+//! The icon is a small window docked into the tray manager that owns the
+//! `_NET_SYSTEM_TRAY_S<screen>` selection. The protocol has no menus or
+//! tooltips: clicks are reported like SNI activations and the title is only
+//! set as the window name.
+
+use crate::dbus::TrayEvent;
+use gpui::{Image, MouseButton};
+use gpui_tray_core::{RgbaIcon, decode_icon};
+use log::debug;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use x11rb::connection::Connection;
+use x11rb::errors::{ConnectError, ConnectionError, ReplyError, ReplyOrIdError};
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, BackPixmap, ChangeWindowAttributesAux, ClientMessageEvent, ColormapAlloc,
+    ConnectionExt as _, CreateGCAux, CreateWindowAux, EventMask, ImageFormat, ImageOrder, PropMode,
+    Visualid, Window, WindowClass,
+};
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::{COPY_DEPTH_FROM_PARENT, CURRENT_TIME};
+
+const SYSTEM_TRAY_REQUEST_DOCK: u32 = 0;
+const XEMBED_VERSION: u32 = 0;
+const XEMBED_MAPPED: u32 = 1;
+/// Size used until the tray manager assigns one.
+const DEFAULT_SIZE: u16 = 22;
+/// Longest gap between two clicks of a double-click, X11 has no system setting.
+const DOUBLE_CLICK_MS: u32 = 400;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        MANAGER,
+        UTF8_STRING,
+        _NET_WM_NAME,
+        _NET_SYSTEM_TRAY_OPCODE,
+        _NET_SYSTEM_TRAY_VISUAL,
+        _XEMBED_INFO,
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum XEmbedError {
+    #[error("no X11 display: {0}")]
+    Connect(#[from] ConnectError),
+    #[error("X11 connection failed: {0}")]
+    Connection(#[from] ConnectionError),
+    #[error("X11 request failed: {0}")]
+    Reply(#[from] ReplyError),
+    #[error("X11 request failed: {0}")]
+    ReplyOrId(#[from] ReplyOrIdError),
+    #[error("no system tray manager owns the {0} selection")]
+    NoManager(String),
+}
+
+/// What the event thread needs to repaint the icon.
+struct Paint {
+    image: Option<Image>,
+    /// `image` decoded for the current window size.
+    decoded: Option<RgbaIcon>,
+    width: u16,
+    height: u16,
+}
+
+struct Shared {
+    connection: RustConnection,
+    atoms: Atoms,
+    window: Window,
+    depth: u8,
+    /// Whether the window has an alpha channel the tray composites.
+    argb: bool,
+    selection: Atom,
+    root: Window,
+    paint: Mutex<Paint>,
+}
+
+/// Whether an X11 tray manager owns the system tray selection.
+pub(crate) fn manager_available() -> bool {
+    let has_owner = || -> Result<bool, XEmbedError> {
+        let (connection, screen_num) = x11rb::connect(None)?;
+        let selection_name = format!("_NET_SYSTEM_TRAY_S{screen_num}");
+        let selection = connection
+            .intern_atom(false, selection_name.as_bytes())?
+            .reply()?
+            .atom;
+        let owner = connection.get_selection_owner(selection)?.reply()?.owner;
+        Ok(owner != x11rb::NONE)
+    };
+    has_owner().unwrap_or_else(|err| {
+        debug!("could not look up the X11 tray manager: {err}");
+        false
+    })
+}
+
+/// An icon docked into the XEmbed system tray.
+pub(crate) struct XEmbedTray {
+    shared: Arc<Shared>,
+    mapped: bool,
+}
+
+impl XEmbedTray {
+    /// Docks a new icon, failing if no tray manager is running.
+    pub fn new(event_sender: Sender<TrayEvent>) -> Result<Self, XEmbedError> {
+        let (connection, screen_num) = x11rb::connect(None)?;
+        let atoms = Atoms::new(&connection)?.reply()?;
+        let selection_name = format!("_NET_SYSTEM_TRAY_S{screen_num}");
+        let selection = connection
+            .intern_atom(false, selection_name.as_bytes())?
+            .reply()?
+            .atom;
+
+        let screen = &connection.setup().roots[screen_num];
+        let root = screen.root;
+        let manager = connection.get_selection_owner(selection)?.reply()?.owner;
+        if manager == x11rb::NONE {
+            return Err(XEmbedError::NoManager(selection_name));
+        }
+
+        // Managers that composite icons advertise a 32-bit visual.
+        let argb_visual = tray_visual(&connection, &atoms, manager)?
+            .filter(|visual| visual_depth(&connection, screen_num, *visual) == Some(32));
+
+        let window = connection.generate_id()?;
+        let mut aux = CreateWindowAux::new().event_mask(
+            EventMask::EXPOSURE
+                | EventMask::STRUCTURE_NOTIFY
+                | EventMask::BUTTON_PRESS
+                | EventMask::BUTTON_RELEASE,
+        );
+        let (depth, visual) = match argb_visual {
+            Some(visual) => {
+                let colormap = connection.generate_id()?;
+                connection.create_colormap(ColormapAlloc::NONE, colormap, root, visual)?;
+                aux = aux.colormap(colormap).background_pixel(0).border_pixel(0);
+                (32, visual)
+            }
+            None => {
+                aux = aux.background_pixmap(BackPixmap::PARENT_RELATIVE);
+                (COPY_DEPTH_FROM_PARENT, screen.root_visual)
+            }
+        };
+        connection.create_window(
+            depth,
+            window,
+            root,
+            0,
+            0,
+            DEFAULT_SIZE,
+            DEFAULT_SIZE,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            visual,
+            &aux,
+        )?;
+        let depth = if depth == COPY_DEPTH_FROM_PARENT {
+            screen.root_depth
+        } else {
+            depth
+        };
+
+        // Learn about tray managers that start after a crash.
+        connection.change_window_attributes(
+            root,
+            &ChangeWindowAttributesAux::new().event_mask(EventMask::STRUCTURE_NOTIFY),
+        )?;
+
+        let shared = Arc::new(Shared {
+            connection,
+            atoms,
+            window,
+            depth,
+            argb: argb_visual.is_some(),
+            selection,
+            root,
+            paint: Mutex::new(Paint {
+                image: None,
+                decoded: None,
+                width: DEFAULT_SIZE,
+                height: DEFAULT_SIZE,
+            }),
+        });
+        shared.set_mapped(true)?;
+        shared.dock(manager)?;
+
+        let thread_shared = shared.clone();
+        std::thread::Builder::new()
+            .name("gpui-tray-linux-xembed".to_string())
+            .spawn(move || run_events(&thread_shared, &event_sender))
+            .map_err(ConnectionError::IoError)?;
+
+        debug!(
+            "xembed icon 0x{window:x} docked into 0x{manager:x}, argb={}",
+            shared.argb
+        );
+        Ok(Self {
+            shared,
+            mapped: true,
+        })
+    }
+
+    /// Shows `image` and `title`, or hides the icon while keeping it docked.
+    pub fn update(
+        &mut self,
+        image: Option<&Image>,
+        title: &str,
+        passive: bool,
+    ) -> Result<(), XEmbedError> {
+        let connection = &self.shared.connection;
+        connection.change_property8(
+            PropMode::REPLACE,
+            self.shared.window,
+            self.shared.atoms._NET_WM_NAME,
+            self.shared.atoms.UTF8_STRING,
+            title.as_bytes(),
+        )?;
+
+        if self.mapped == passive {
+            self.shared.set_mapped(!passive)?;
+            self.mapped = !passive;
+        }
+
+        {
+            let mut paint = self.shared.paint.lock().unwrap();
+            if paint.image.as_ref().map(Image::id) != image.map(Image::id) {
+                paint.image = image.cloned();
+                paint.decoded = None;
+            }
+        }
+        self.shared.repaint()?;
+        Ok(())
+    }
+}
+
+impl Drop for XEmbedTray {
+    fn drop(&mut self) {
+        // The event thread stops on the window's DestroyNotify.
+        let _ = self.shared.connection.destroy_window(self.shared.window);
+        let _ = self.shared.connection.flush();
+    }
+}
+
+impl Shared {
+    fn dock(&self, manager: Window) -> Result<(), XEmbedError> {
+        let message = ClientMessageEvent::new(
+            32,
+            manager,
+            self.atoms._NET_SYSTEM_TRAY_OPCODE,
+            [CURRENT_TIME, SYSTEM_TRAY_REQUEST_DOCK, self.window, 0, 0],
+        );
+        self.connection
+            .send_event(false, manager, EventMask::NO_EVENT, message)?;
+        self.connection.flush()?;
+        Ok(())
+    }
+
+    /// Tells the embedder whether to show the window, via `_XEMBED_INFO`.
+    fn set_mapped(&self, mapped: bool) -> Result<(), XEmbedError> {
+        let flags = if mapped { XEMBED_MAPPED } else { 0 };
+        self.connection.change_property32(
+            PropMode::REPLACE,
+            self.window,
+            self.atoms._XEMBED_INFO,
+            self.atoms._XEMBED_INFO,
+            &[XEMBED_VERSION, flags],
+        )?;
+        self.connection.flush()?;
+        Ok(())
+    }
+
+    fn repaint(&self) -> Result<(), XEmbedError> {
+        let mut paint = self.paint.lock().unwrap();
+        let size = u32::from(paint.width.min(paint.height));
+        if paint.decoded.as_ref().map(|icon| icon.size) != Some(size) {
+            paint.decoded = match paint.image.as_ref() {
+                Some(image) => match decode_icon(image, size) {
+                    Ok(icon) => Some(icon),
+                    Err(err) => {
+                        debug!("xembed icon could not be decoded: {err}");
+                        None
+                    }
+                },
+                None => None,
+            };
+        }
+
+        self.connection.clear_area(false, self.window, 0, 0, 0, 0)?;
+        if let Some(icon) = paint.decoded.as_ref() {
+            let x = (paint.width - icon.size as u16) / 2;
+            let y = (paint.height - icon.size as u16) / 2;
+            self.draw(icon, x as i16, y as i16)?;
+        }
+        self.connection.flush()?;
+        Ok(())
+    }
+
+    fn draw(&self, icon: &RgbaIcon, x: i16, y: i16) -> Result<(), XEmbedError> {
+        let gc = self.connection.generate_id()?;
+        self.connection
+            .create_gc(gc, self.window, &CreateGCAux::new())?;
+
+        let msb_first = self.connection.setup().image_byte_order == ImageOrder::MSB_FIRST;
+        let size = icon.size as usize;
+        let pixel = |rgba: &[u8]| -> [u8; 4] {
+            let [r, g, b, a] = [rgba[0], rgba[1], rgba[2], rgba[3]];
+            // Compositing managers expect premultiplied alpha.
+            let premultiply = |c: u8| (u16::from(c) * u16::from(a) / 255) as u8;
+            let (r, g, b) = (premultiply(r), premultiply(g), premultiply(b));
+            if msb_first {
+                [a, r, g, b]
+            } else {
+                [b, g, r, a]
+            }
+        };
+
+        if self.argb {
+            let data: Vec<u8> = icon.rgba.chunks_exact(4).flat_map(pixel).collect();
+            self.connection.put_image(
+                ImageFormat::Z_PIXMAP,
+                self.window,
+                gc,
+                icon.size as u16,
+                icon.size as u16,
+                x,
+                y,
+                0,
+                self.depth,
+                &data,
+            )?;
+        } else {
+            // Without an alpha channel, only draw the opaque runs of each row
+            // and let the parent-relative background show through the rest.
+            for (row, line) in icon.rgba.chunks_exact(size * 4).enumerate() {
+                let mut column = 0;
+                while column < size {
+                    let opaque = |c: usize| line[c * 4 + 3] >= 128;
+                    if !opaque(column) {
+                        column += 1;
+                        continue;
+                    }
+                    let start = column;
+                    while column < size && opaque(column) {
+                        column += 1;
+                    }
+                    let data: Vec<u8> = line[start * 4..column * 4]
+                        .chunks_exact(4)
+                        .flat_map(|rgba| pixel(&[rgba[0], rgba[1], rgba[2], 255]))
+                        .collect();
+                    self.connection.put_image(
+                        ImageFormat::Z_PIXMAP,
+                        self.window,
+                        gc,
+                        (column - start) as u16,
+                        1,
+                        x + start as i16,
+                        y + row as i16,
+                        0,
+                        self.depth,
+                        &data,
+                    )?;
+                }
+            }
+        }
+
+        self.connection.free_gc(gc)?;
+        Ok(())
+    }
+}
+
+fn run_events(shared: &Shared, event_sender: &Sender<TrayEvent>) {
+    // Button and server time of the last click that did not end a double-click.
+    let mut last_click: Option<(u8, u32)> = None;
+    loop {
+        let event = match shared.connection.wait_for_event() {
+            Ok(event) => event,
+            Err(err) => {
+                debug!("xembed connection closed: {err}");
+                return;
+            }
+        };
+
+        let tray_event = match event {
+            Event::Expose(event) if event.count == 0 => {
+                if let Err(err) = shared.repaint() {
+                    debug!("xembed repaint failed: {err}");
+                }
+                None
+            }
+            Event::ConfigureNotify(event) if event.window == shared.window => {
+                let mut paint = shared.paint.lock().unwrap();
+                let resized = (paint.width, paint.height) != (event.width, event.height);
+                paint.width = event.width;
+                paint.height = event.height;
+                drop(paint);
+                if resized && let Err(err) = shared.repaint() {
+                    debug!("xembed repaint failed: {err}");
+                }
+                None
+            }
+            Event::DestroyNotify(event) if event.window == shared.window => return,
+            // A tray manager took over the selection, e.g. after the panel restarted.
+            Event::ClientMessage(event)
+                if event.window == shared.root
+                    && event.type_ == shared.atoms.MANAGER
+                    && event.data.as_data32()[1] == shared.selection =>
+            {
+                let manager = event.data.as_data32()[2];
+                debug!("xembed tray manager 0x{manager:x} appeared, docking again");
+                match shared.dock(manager) {
+                    Ok(()) => Some(TrayEvent::XEmbedDocked),
+                    Err(err) => {
+                        debug!("xembed dock failed: {err}");
+                        None
+                    }
+                }
+            }
+            Event::ButtonRelease(event) => {
+                let (x, y) = (i32::from(event.root_x), i32::from(event.root_y));
+                let double = last_click.take().is_some_and(|(button, time)| {
+                    button == event.detail && event.time.wrapping_sub(time) <= DOUBLE_CLICK_MS
+                });
+                if !double {
+                    last_click = Some((event.detail, event.time));
+                }
+                match (event.detail, double) {
+                    (1, false) => Some(TrayEvent::Activate { x, y }),
+                    (1, true) => Some(TrayEvent::DoubleClick {
+                        button: MouseButton::Left,
+                        x,
+                        y,
+                    }),
+                    (2, false) => Some(TrayEvent::SecondaryActivate { x, y }),
+                    (2, true) => Some(TrayEvent::DoubleClick {
+                        button: MouseButton::Middle,
+                        x,
+                        y,
+                    }),
+                    (3, _) => Some(TrayEvent::ContextMenu { x, y }),
+                    _ => None,
+                }
+            }
+            // Wheel buttons only report presses worth counting.
+            Event::ButtonPress(event) => {
+                let (delta, orientation) = match event.detail {
+                    4 => (-1, "vertical"),
+                    5 => (1, "vertical"),
+                    6 => (-1, "horizontal"),
+                    7 => (1, "horizontal"),
+                    _ => continue,
+                };
+                Some(TrayEvent::Scroll {
+                    delta,
+                    orientation: orientation.to_string(),
+                })
+            }
+            _ => None,
+        };
+
+        if let Some(tray_event) = tray_event
+            && event_sender.send(tray_event).is_err()
+        {
+            return;
+        }
+    }
+}
+
+fn tray_visual(
+    connection: &RustConnection,
+    atoms: &Atoms,
+    manager: Window,
+) -> Result<Option<Visualid>, XEmbedError> {
+    let reply = connection
+        .get_property(
+            false,
+            manager,
+            atoms._NET_SYSTEM_TRAY_VISUAL,
+            AtomEnum::VISUALID,
+            0,
+            1,
+        )?
+        .reply()?;
+    Ok(reply.value32().and_then(|mut values| values.next()))
+}
+
+fn visual_depth(connection: &RustConnection, screen_num: usize, visual: Visualid) -> Option<u8> {
+    connection.setup().roots[screen_num]
+        .allowed_depths
+        .iter()
+        .find(|depth| depth.visuals.iter().any(|v| v.visual_id == visual))
+        .map(|depth| depth.depth)
+}
@@ -0,0 +1,394 @@
+//! Runs the StatusNotifierItem backend against a private D-Bus daemon and a
+//! stand-in StatusNotifierWatcher, checking what a tray host would see.
+//!
+//! Needs `dbus-daemon` on the `PATH`; the test passes without checking
+//! anything when it is missing.
+
+#![cfg(target_os = "linux")]
+
+use gpui::MouseButton;
+use gpui_tray_core::platform_trait::PlatformTray;
+use gpui_tray_core::{
+    ClickEvent, LinuxTrayBackend, MenuOpenedEvent, MenuSelectEvent, RawPlatformEvent, RuntimeEvent,
+    ScrollEvent, ScrollOrientation, Tray, TrayMenuItem, image_from_rgba,
+};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zbus::blocking::{Connection, Proxy};
+use zbus::interface;
+use zbus::message::Header;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+gpui::actions!(test_app, [Quit, ToggleSync]);
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const ITEM_IFACE: &str = "org.kde.StatusNotifierItem";
+const MENU_IFACE: &str = "com.canonical.dbusmenu";
+
+type PixmapTuple = (i32, i32, Vec<u8>);
+type LayoutItem = (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>);
+
+/// A `dbus-daemon` of its own, stopped when dropped.
+struct PrivateBus {
+    daemon: Child,
+    dir: PathBuf,
+    address: String,
+}
+
+impl PrivateBus {
+    fn start() -> Option<Self> {
+        let dir = std::env::temp_dir().join(format!("gpui-tray-bus-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).ok()?;
+        let config = dir.join("bus.conf");
+        std::fs::write(
+            &config,
+            format!(
+                r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>unix:dir={}</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>
+"#,
+                dir.display()
+            ),
+        )
+        .ok()?;
+
+        let mut daemon = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .args(["--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+        Some(Self {
+            daemon,
+            dir,
+            address: address.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Stand-in for the panel's StatusNotifierWatcher, recording registrations
+/// as (sender, service or path).
+#[derive(Clone, Default)]
+struct Watcher {
+    items: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+#[interface(name = "org.kde.StatusNotifierWatcher")]
+impl Watcher {
+    fn register_status_notifier_item(&self, service: String, #[zbus(header)] header: Header<'_>) {
+        let sender = header.sender().map(ToString::to_string).unwrap_or_default();
+        self.items.lock().unwrap().push((sender, service));
+    }
+
+    fn register_status_notifier_host(&self, _service: String) {}
+
+    #[zbus(property)]
+    fn registered_status_notifier_items(&self) -> Vec<String> {
+        let items = self.items.lock().unwrap();
+        items.iter().map(|(_, service)| service.clone()).collect()
+    }
+
+    #[zbus(property)]
+    fn is_status_notifier_host_registered(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn protocol_version(&self) -> i32 {
+        0
+    }
+}
+
+fn wait_for<T>(what: &str, mut poll: impl FnMut() -> Option<T>) -> T {
+    let deadline = Instant::now() + TIMEOUT;
+    loop {
+        if let Some(value) = poll() {
+            return value;
+        }
+        assert!(Instant::now() < deadline, "timed out waiting for {what}");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Waits for the next action the backend reports, skipping others such as
+/// theme changes.
+fn next_event<T: Clone + 'static>(backend: &dyn PlatformTray) -> T {
+    wait_for(std::any::type_name::<T>(), || {
+        match backend.try_recv_event().unwrap()? {
+            RuntimeEvent::Action(action) => action.as_any().downcast_ref::<T>().cloned(),
+            _ => None,
+        }
+    })
+}
+
+fn menu_selection(backend: &dyn PlatformTray) -> Vec<Box<dyn gpui::Action>> {
+    wait_for("a menu selection", || {
+        match backend.try_recv_event().unwrap()? {
+            RuntimeEvent::MenuSelection(actions) => Some(actions),
+            _ => None,
+        }
+    })
+}
+
+fn app_tray(tooltip: &str) -> Tray {
+    let icon = image_from_rgba(2, 2, [255, 0, 0, 255].repeat(4)).unwrap();
+    Tray::new()
+        .title("Sync")
+        .tooltip(tooltip.to_string())
+        .tooltip_body("All files up to date")
+        .icon(icon)
+        .linux_backend(LinuxTrayBackend::Auto)
+        .menu(|| {
+            vec![
+                TrayMenuItem::checkbox("Pause syncing", true, ToggleSync).id("pause"),
+                TrayMenuItem::action("Open folder", Quit).enabled(false),
+                TrayMenuItem::separator(),
+                TrayMenuItem::action("Quit", Quit).id("quit"),
+            ]
+        })
+}
+
+/// Items announce changes with their own signals rather than
+/// `PropertiesChanged`, so properties are read afresh every time.
+fn proxy<'a>(host: &Connection, sender: &'a str, path: &'a str, interface: &'a str) -> Proxy<'a> {
+    zbus::blocking::proxy::Builder::new(host)
+        .destination(sender)
+        .unwrap()
+        .path(path)
+        .unwrap()
+        .interface(interface)
+        .unwrap()
+        .cache_properties(CacheProperties::No)
+        .build()
+        .unwrap()
+}
+
+fn label(item: &LayoutItem) -> String {
+    item.1
+        .get("label")
+        .and_then(|label| String::try_from(label.clone()).ok())
+        .unwrap_or_default()
+}
+
+#[test]
+fn status_notifier_item_on_a_private_bus() {
+    let Some(bus) = PrivateBus::start() else {
+        eprintln!("dbus-daemon unavailable, skipping");
+        return;
+    };
+    // Safety: this is the only test in the binary, and the backend reads the
+    // address only once it is created below.
+    unsafe { std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &bus.address) };
+
+    let watcher = Watcher::default();
+    let _watcher_connection = zbus::blocking::connection::Builder::session()
+        .unwrap()
+        .name("org.kde.StatusNotifierWatcher")
+        .unwrap()
+        .serve_at("/StatusNotifierWatcher", watcher.clone())
+        .unwrap()
+        .build()
+        .unwrap();
+    let host = Connection::session().unwrap();
+
+    let backend = gpui_tray_linux::create().unwrap();
+    backend.set_tray(app_tray("Sync")).unwrap();
+
+    // Registration: by object path, from the connection owning the item.
+    let (sender, registered) = wait_for("the item to register", || {
+        watcher.items.lock().unwrap().first().cloned()
+    });
+    assert_eq!(registered, ITEM_PATH);
+    let dbus = zbus::blocking::fdo::DBusProxy::new(&host).unwrap();
+    let well_known = format!(
+        "org.freedesktop.StatusNotifierItem-GPUITRAY-{}",
+        std::process::id()
+    );
+    let owner = dbus
+        .get_name_owner(well_known.as_str().try_into().unwrap())
+        .unwrap();
+    assert_eq!(owner.as_str(), sender);
+
+    // Properties, as a host reads them after registration.
+    let item = proxy(&host, &sender, ITEM_PATH, ITEM_IFACE);
+    assert_eq!(item.get_property::<String>("Title").unwrap(), "Sync");
+    assert_eq!(item.get_property::<String>("Status").unwrap(), "Active");
+    assert_eq!(
+        item.get_property::<String>("Category").unwrap(),
+        "ApplicationStatus"
+    );
+    let (_, _, tooltip, body) = item
+        .get_property::<(String, Vec<PixmapTuple>, String, String)>("ToolTip")
+        .unwrap();
+    assert_eq!(
+        (tooltip.as_str(), body.as_str()),
+        ("Sync", "All files up to date")
+    );
+    let pixmaps = item.get_property::<Vec<PixmapTuple>>("IconPixmap").unwrap();
+    let sizes: Vec<_> = pixmaps
+        .iter()
+        .map(|(width, height, data)| {
+            assert_eq!(data.len(), (width * height * 4) as usize);
+            *width
+        })
+        .collect();
+    assert_eq!(sizes, [16, 22, 24, 32, 48]);
+    // ARGB in network byte order: opaque red.
+    assert_eq!(&pixmaps[0].2[..4], &[255, 255, 0, 0]);
+    let menu_path = item.get_property::<OwnedObjectPath>("Menu").unwrap();
+
+    // Updates reach the properties without registering again.
+    backend.set_tray(app_tray("Syncing 3 files")).unwrap();
+    let (_, _, tooltip, _) = item
+        .get_property::<(String, Vec<PixmapTuple>, String, String)>("ToolTip")
+        .unwrap();
+    assert_eq!(tooltip, "Syncing 3 files");
+    assert_eq!(watcher.items.lock().unwrap().len(), 1);
+
+    // Menu layout, as the host fetches it before opening the menu.
+    let menu = proxy(&host, &sender, menu_path.as_str(), MENU_IFACE);
+    let (_, (root_id, _, children)): (u32, LayoutItem) = menu
+        .call("GetLayout", &(0i32, -1i32, Vec::<String>::new()))
+        .unwrap();
+    assert_eq!(root_id, 0);
+    let children: Vec<LayoutItem> = children
+        .into_iter()
+        .map(|child| LayoutItem::try_from(child).unwrap())
+        .collect();
+    let labels: Vec<_> = children.iter().map(label).collect();
+    assert_eq!(labels, ["Pause syncing", "Open folder", "", "Quit"]);
+    let property = |item: &LayoutItem, name: &str| item.1.get(name).cloned();
+    assert_eq!(
+        property(&children[0], "toggle-type").map(|value| String::try_from(value).unwrap()),
+        Some("checkmark".to_string())
+    );
+    assert_eq!(
+        property(&children[0], "toggle-state").map(|value| i32::try_from(value).unwrap()),
+        Some(1)
+    );
+    assert_eq!(
+        property(&children[1], "enabled").map(|value| bool::try_from(value).unwrap()),
+        Some(false)
+    );
+    assert_eq!(
+        property(&children[2], "type").map(|value| String::try_from(value).unwrap()),
+        Some("separator".to_string())
+    );
+
+    // Host calls come back as tray events.
+    let _: () = item.call("Activate", &(10i32, 20i32)).unwrap();
+    let click = next_event::<ClickEvent>(backend.as_ref());
+    assert_eq!(click.button, MouseButton::Left);
+    assert_eq!((click.position.x, click.position.y), (10.0, 20.0));
+
+    let _: () = item.call("ContextMenu", &(5i32, 6i32)).unwrap();
+    assert_eq!(
+        next_event::<ClickEvent>(backend.as_ref()).button,
+        MouseButton::Right
+    );
+
+    let _: () = item.call("Scroll", &(-240i32, "vertical")).unwrap();
+    let scroll = next_event::<ScrollEvent>(backend.as_ref());
+    assert_eq!(scroll.delta.lines, -2.0);
+    assert_eq!(scroll.delta.orientation, ScrollOrientation::Vertical);
+
+    let _: () = item.call("Scroll", &(1i32, "horizontal")).unwrap();
+    let scroll = next_event::<ScrollEvent>(backend.as_ref());
+    assert_eq!(scroll.delta.lines, 1.0);
+    assert_eq!(scroll.delta.orientation, ScrollOrientation::Horizontal);
+
+    let quit_id = children[3].0;
+    let _: () = menu
+        .call("Event", &(quit_id, "clicked", Value::from(0i32), 0u32))
+        .unwrap();
+    let actions = menu_selection(backend.as_ref());
+    let select = actions[0]
+        .as_any()
+        .downcast_ref::<MenuSelectEvent>()
+        .expect("selection starts with MenuSelectEvent");
+    assert_eq!(select.id, "quit");
+    assert!(actions[1].as_any().is::<Quit>());
+
+    // Menu changes wait while the menu is open, and a click closes it even
+    // when the host never reports `closed`.
+    let layout_labels = || -> Vec<String> {
+        let (_, (_, _, children)): (u32, LayoutItem) = menu
+            .call("GetLayout", &(0i32, -1i32, Vec::<String>::new()))
+            .unwrap();
+        children
+            .into_iter()
+            .map(|child| label(&LayoutItem::try_from(child).unwrap()))
+            .collect()
+    };
+    let _: () = menu
+        .call("Event", &(0i32, "opened", Value::from(0i32), 0u32))
+        .unwrap();
+    next_event::<MenuOpenedEvent>(backend.as_ref());
+    backend
+        .set_tray(app_tray("Paused").menu(|| vec![TrayMenuItem::action("Resume", ToggleSync)]))
+        .unwrap();
+    assert_eq!(
+        layout_labels(),
+        ["Pause syncing", "Open folder", "", "Quit"]
+    );
+    let _: () = menu
+        .call("Event", &(quit_id, "clicked", Value::from(0i32), 0u32))
+        .unwrap();
+    menu_selection(backend.as_ref());
+    wait_for("the deferred menu", || {
+        (layout_labels() == ["Resume"]).then_some(())
+    });
+
+    // Host calls reach a raw event handler once one is installed.
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let tray = app_tray("Sync").on_raw_platform_event({
+        let calls = calls.clone();
+        move |event| {
+            if let RawPlatformEvent::DBusCall { member, .. } = event {
+                calls.lock().unwrap().push(*member);
+            }
+        }
+    });
+    backend.set_tray(tray).unwrap();
+    let _: () = item.call("Activate", &(1i32, 2i32)).unwrap();
+    next_event::<ClickEvent>(backend.as_ref());
+    assert_eq!(*calls.lock().unwrap(), ["Activate"]);
+
+    // Removing the tray takes the item off the bus.
+    backend.remove_tray().unwrap();
+    wait_for("the item to leave the bus", || {
+        (!dbus
+            .name_has_owner(well_known.as_str().try_into().unwrap())
+            .unwrap())
+        .then_some(())
+    });
+
+    backend.shutdown().unwrap();
+}
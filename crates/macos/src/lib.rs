@@ -6,3 +6,9 @@ use gpui_tray_core::platform_trait::PlatformTray;
 pub fn create() -> Result<Box<dyn PlatformTray>> {
     Err(gpui_tray_core::Error::UnsupportedPlatform)
 }
+
+/// The menu bar always takes status items, but `create` cannot make one
+/// yet, so an app should not count on it.
+pub fn is_supported() -> bool {
+    false
+}
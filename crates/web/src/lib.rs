@@ -0,0 +1,163 @@
+//! Tray backend for the web, where there is no system tray.
+//!
+//! App code shared with the desktop can still call `set_tray`: every change
+//! goes to the fallback registered with [`set_fallback`], which can show the
+//! tray's state in the page instead, and the page reports clicks on it back
+//! with [`send_event`].
+//!
+//! gpui has no web platform yet, so nothing picks this backend on its own;
+//! apps install it through the `web` feature of `gpui-tray`.
+
+use gpui::{Action, Image};
+use gpui_tray_core::platform_trait::PlatformTray;
+use gpui_tray_core::{
+    Error, EventQueuePolicy, EventQueueStats, EventReceiver, EventSender, Notification, Result,
+    RuntimeEvent, Tray, event_queue,
+};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A change the app made to the tray, as handed to the fallback.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TrayChange<'a> {
+    /// The tray was set or updated; this is the full snapshot, with
+    /// animation frame, badge and update entry applied.
+    Set(&'a Tray),
+    /// The tray was removed.
+    Removed,
+    /// The app asked for the context menu to open.
+    ShowMenu,
+    /// The app showed a notification from the tray.
+    Notify(&'a Notification),
+}
+
+type Fallback = Arc<dyn Fn(TrayChange<'_>) + Send + Sync>;
+
+/// Shared with the free functions, which the app calls without a handle
+/// on the backend.
+struct State {
+    fallback: Option<Fallback>,
+    tray: Option<Tray>,
+    events: Option<EventSender>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    fallback: None,
+    tray: None,
+    events: None,
+});
+
+fn state() -> MutexGuard<'static, State> {
+    STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Calls `fallback` with every change the app makes to the tray from now
+/// on, replacing the previous fallback.
+///
+/// When a tray is already set, `fallback` is called with it right away.
+pub fn set_fallback(fallback: impl Fn(TrayChange<'_>) + Send + Sync + 'static) {
+    let fallback: Fallback = Arc::new(fallback);
+    let tray = {
+        let mut state = state();
+        state.fallback = Some(fallback.clone());
+        state.tray.clone()
+    };
+    if let Some(tray) = tray {
+        fallback(TrayChange::Set(&tray));
+    }
+}
+
+/// Sends `event` to the app as if the user had caused it on a tray icon,
+/// such as a [`ClickEvent`](gpui_tray_core::ClickEvent) from the page's
+/// status widget. Dropped while no tray runtime is running.
+pub fn send_event(event: impl Action) {
+    if let Some(events) = state().events.as_ref() {
+        events.send(RuntimeEvent::Action(Box::new(event)));
+    }
+}
+
+/// Calls the fallback outside the lock, so it may call back into this
+/// module.
+fn report(change: TrayChange<'_>) {
+    let fallback = state().fallback.clone();
+    match fallback {
+        Some(fallback) => fallback(change),
+        None => log::trace!("no web tray fallback for {change:?}"),
+    }
+}
+
+pub fn create() -> Result<Box<dyn PlatformTray>> {
+    let (event_tx, event_rx) = event_queue(EventQueuePolicy::default());
+    state().events = Some(event_tx);
+    Ok(Box::new(WebTray { event_rx }))
+}
+
+/// A browser has no system tray; the fallback stands in for it.
+pub fn is_supported() -> bool {
+    false
+}
+
+struct WebTray {
+    event_rx: EventReceiver,
+}
+
+impl PlatformTray for WebTray {
+    fn set_tray(&self, tray: Tray) -> Result<()> {
+        state().tray = Some(tray.clone());
+        report(TrayChange::Set(&tray));
+        Ok(())
+    }
+
+    fn set_icon(&self, icon: Option<Image>) -> Result<()> {
+        let tray = {
+            let mut state = state();
+            let tray = state.tray.as_mut().ok_or(Error::NotFound)?;
+            tray.icon = icon;
+            tray.clone()
+        };
+        report(TrayChange::Set(&tray));
+        Ok(())
+    }
+
+    fn remove_tray(&self) -> Result<()> {
+        state().tray = None;
+        report(TrayChange::Removed);
+        Ok(())
+    }
+
+    fn show_menu(&self) -> Result<()> {
+        report(TrayChange::ShowMenu);
+        Ok(())
+    }
+
+    fn notify(&self, notification: Notification) -> Result<()> {
+        report(TrayChange::Notify(&notification));
+        Ok(())
+    }
+
+    // Every change reaches the fallback before `set_tray` returns.
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn try_recv_event(&self) -> Result<Option<RuntimeEvent>> {
+        self.event_rx.try_recv()
+    }
+
+    fn set_event_policy(&self, policy: EventQueuePolicy) {
+        self.event_rx.set_policy(policy);
+    }
+
+    fn event_stats(&self) -> EventQueueStats {
+        self.event_rx.stats()
+    }
+
+    // The backend outlives tray runtimes, so events sent after the next
+    // `set_tray` still arrive.
+    fn shutdown(&self) -> Result<()> {
+        state().tray = None;
+        Ok(())
+    }
+}
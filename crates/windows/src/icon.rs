@@ -1,4 +1,4 @@
-use gpui_tray_core::{BackendError, Error, Result};
+use gpui_tray_core::{BackendError, Error, IconTarget, Result, RgbaIcon, decode_icon_for};
 use log::debug;
 use windows::Win32::Graphics::Gdi::{
     BITMAPINFO, BITMAPINFOHEADER, CreateBitmap, CreateDIBSection, DIB_RGB_COLORS, DeleteObject,
@@ -6,10 +6,13 @@ use windows::Win32::Graphics::Gdi::{
 };
 use windows::Win32::UI::WindowsAndMessaging::{CreateIconIndirect, DestroyIcon, HICON, ICONINFO};
 
-pub(crate) struct DecodedIcon {
-    pub rgba: Vec<u8>,
-    pub width: u32,
-    pub height: u32,
+/// Which Windows icon an image is decoded for.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum IconSize {
+    /// Notification area icons.
+    Small,
+    /// Large notification icons.
+    Large,
 }
 
 pub(crate) struct OwnedIcon(pub(crate) HICON);
@@ -24,27 +27,28 @@ impl Drop for OwnedIcon {
     }
 }
 
-pub(crate) fn decode_icon(image: &gpui::Image) -> Result<DecodedIcon> {
+/// Decodes `image` at the icon size of `dpi`.
+pub(crate) fn decode_icon(image: &gpui::Image, size: IconSize, dpi: u32) -> Result<RgbaIcon> {
     let start = std::time::Instant::now();
+    let target = match size {
+        IconSize::Small => IconTarget::WindowsTray { dpi },
+        IconSize::Large => IconTarget::WindowsNotification { dpi },
+    };
     debug!(
-        "decode start, bytes={}, format={:?}",
+        "decode start, bytes={}, format={:?}, target={:?}",
         image.bytes.len(),
-        image.format
+        image.format,
+        target
     );
-    let decoded = image::load_from_memory(&image.bytes).map_err(|_| Error::InvalidIcon)?;
-    let resized = decoded.resize_to_fill(32, 32, image::imageops::FilterType::Lanczos3);
-    let rgba = resized.to_rgba8().into_raw();
+    let decoded = decode_icon_for(image, target)?.remove(0);
     debug!("windows icon: decode finish in {:?}", start.elapsed());
-    Ok(DecodedIcon {
-        rgba,
-        width: 32,
-        height: 32,
-    })
+    Ok(decoded)
 }
 
-pub(crate) fn create_hicon(decoded: &DecodedIcon) -> Result<OwnedIcon> {
+pub(crate) fn create_hicon(decoded: &RgbaIcon) -> Result<OwnedIcon> {
     let start = std::time::Instant::now();
-    debug!("create_hicon start, {}x{}", decoded.width, decoded.height);
+    let (width, height) = (decoded.size, decoded.size);
+    debug!("create_hicon start, {width}x{height}");
     unsafe {
         let hdc = GetDC(None);
         if hdc.is_invalid() {
@@ -54,8 +58,8 @@ pub(crate) fn create_hicon(decoded: &DecodedIcon) -> Result<OwnedIcon> {
         let bmi = BITMAPINFO {
             bmiHeader: BITMAPINFOHEADER {
                 biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
-                biWidth: decoded.width as i32,
-                biHeight: -(decoded.height as i32),
+                biWidth: width as i32,
+                biHeight: -(height as i32),
                 biPlanes: 1,
                 biBitCount: 32,
                 biCompression: 0,
@@ -88,21 +92,21 @@ pub(crate) fn create_hicon(decoded: &DecodedIcon) -> Result<OwnedIcon> {
 
         let _ = ReleaseDC(None, hdc);
 
-        let mut and_mask = vec![0xFFu8; (decoded.width.div_ceil(8) * decoded.height) as usize];
+        let mut and_mask = vec![0xFFu8; (width.div_ceil(8) * height) as usize];
         for (i, chunk) in decoded.rgba.chunks_exact(4).enumerate() {
             let alpha = chunk[3];
             if alpha < 128 {
-                let x = (i % decoded.width as usize) as u32;
-                let y = (i / decoded.width as usize) as u32;
-                let byte_index = (y * decoded.width.div_ceil(8) + (x / 8)) as usize;
+                let x = (i % width as usize) as u32;
+                let y = (i / width as usize) as u32;
+                let byte_index = (y * width.div_ceil(8) + (x / 8)) as usize;
                 let bit_index = x % 8;
                 and_mask[byte_index] &= !(1 << (7 - bit_index));
             }
         }
 
         let hmask = CreateBitmap(
-            decoded.width as i32,
-            decoded.height as i32,
+            width as i32,
+            height as i32,
             1,
             1,
             Some(and_mask.as_ptr() as *const _),
@@ -6,12 +6,50 @@
 //! Windows Shell API (Shell_NotifyIconW).
 
 mod icon;
+mod menu_id;
 mod tray;
+mod wheel;
 
+use gpui::Point;
 use gpui_tray_core::Result;
 use gpui_tray_core::platform_trait::PlatformTray;
+use windows::Win32::Foundation::{HWND, POINT};
+use windows::Win32::Graphics::Gdi::{MONITOR_DEFAULTTONEAREST, MonitorFromPoint};
+use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+use windows::Win32::UI::WindowsAndMessaging::{SW_HIDE, SW_SHOW, ShowWindow};
 
 /// Creates a new Windows platform tray implementation.
 pub fn create() -> Result<Box<dyn PlatformTray>> {
     tray::create()
 }
+
+/// Whether the taskbar, which hosts the notification area, is running.
+pub fn is_supported() -> bool {
+    tray::taskbar_running()
+}
+
+/// Shows or hides the top-level window `hwnd`, taking its taskbar button
+/// with it.
+pub fn set_window_visible(hwnd: isize, visible: bool) {
+    let command = if visible { SW_SHOW } else { SW_HIDE };
+    unsafe {
+        let _ = ShowWindow(HWND(hwnd as *mut _), command);
+    }
+}
+
+/// Converts a position reported in tray events, which is in physical pixels,
+/// to the logical pixels GPUI places windows with.
+pub fn logical_position(position: Point<f32>) -> Point<f32> {
+    let point = POINT {
+        x: position.x as i32,
+        y: position.y as i32,
+    };
+    let monitor = unsafe { MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST) };
+    let (mut dpi_x, mut dpi_y) = (0, 0);
+    let scale =
+        match unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) } {
+            Ok(()) if dpi_x > 0 => dpi_x as f32 / 96.0,
+            _ => 1.0,
+        };
+    Point::new(position.x / scale, position.y / scale)
+}
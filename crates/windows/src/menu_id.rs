@@ -0,0 +1,83 @@
+//! Command ids of popup menu entries.
+//!
+//! `TrackPopupMenu` reports the selection as the command id the entry was
+//! appended with. The popup is rebuilt every time it opens, and the menu may
+//! have changed in between, so ids are not derived from an entry's position:
+//! an entry with an id keeps its command id for as long as the backend runs,
+//! wherever it moves to, and the selection maps back to that entry.
+
+use gpui::SharedString;
+use gpui_tray_core::TrayMenuItem;
+use std::collections::HashMap;
+
+/// Largest command id handed out. `WM_COMMAND` carries command ids in 16
+/// bits, and menus never come close to this many distinct entries.
+const MAX_COMMAND_ID: u16 = u16::MAX;
+
+/// What an entry's command id is tied to.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum MenuKey {
+    /// The entry's [`TrayMenuItem::id`].
+    Id(SharedString),
+    /// Indices from the top-level menu down to an entry without an id, or
+    /// whose id an earlier entry of the same build already took.
+    Path(Vec<usize>),
+}
+
+/// Maps command ids to menu entries across rebuilds.
+#[derive(Default)]
+pub(crate) struct MenuIds {
+    ids: HashMap<MenuKey, u16>,
+    /// Entries of the last build, by command id.
+    entries: HashMap<u16, TrayMenuItem>,
+    last_id: u16,
+}
+
+impl MenuIds {
+    /// Forgets the entries of the previous build, keeping their ids.
+    pub fn begin_build(&mut self) {
+        self.entries.clear();
+        if self.last_id == MAX_COMMAND_ID {
+            // Only reachable after tens of thousands of distinct ids; start
+            // over rather than hand out one twice.
+            self.ids.clear();
+            self.last_id = 0;
+        }
+    }
+
+    /// Returns the command id for `item` at `path` and records it as part
+    /// of the current build. Returns `None` once every id is taken.
+    pub fn assign(&mut self, item: &TrayMenuItem, path: &[usize]) -> Option<u16> {
+        let key = match &item.id {
+            Some(id) if !self.taken(&MenuKey::Id(id.clone())) => MenuKey::Id(id.clone()),
+            _ => MenuKey::Path(path.to_vec()),
+        };
+        let id = match self.ids.get(&key) {
+            Some(&id) => id,
+            None => {
+                if self.last_id == MAX_COMMAND_ID {
+                    return None;
+                }
+                self.last_id += 1;
+                self.ids.insert(key, self.last_id);
+                self.last_id
+            }
+        };
+        self.entries.insert(id, item.clone());
+        Some(id)
+    }
+
+    /// Whether `key` already has an entry in the current build.
+    fn taken(&self, key: &MenuKey) -> bool {
+        self.ids
+            .get(key)
+            .is_some_and(|id| self.entries.contains_key(id))
+    }
+
+    /// Returns the entry of the current build `TrackPopupMenu` returned
+    /// `command` for.
+    pub fn entry(&self, command: u32) -> Option<&TrayMenuItem> {
+        let id = u16::try_from(command).ok()?;
+        self.entries.get(&id)
+    }
+}
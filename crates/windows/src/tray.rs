@@ -1,50 +1,102 @@
-use crate::icon::{DecodedIcon, OwnedIcon, create_hicon, decode_icon};
-use gpui::{Action, MenuItem, MouseButton, Point};
+use crate::icon::{IconSize, OwnedIcon, create_hicon, decode_icon};
+use crate::menu_id::MenuIds;
+use crate::wheel::{WM_TRAY_WHEEL, WheelHook, WheelInput};
+use gpui::{MouseButton, Point, SharedString};
 use gpui_tray_core::platform_trait::PlatformTray;
 use gpui_tray_core::{
-    BackendError, ClickEvent, DoubleClickEvent, Error, Result, RuntimeEvent, Tray,
+    BackendError, ClickEvent, DoubleClickEvent, Error, EventQueuePolicy, EventQueueStats,
+    EventReceiver, EventSender, HelpRequestedEvent, HostRestartedEvent, MenuItemKind,
+    MenuOpenedEvent, Notification, NotificationClickedEvent, NotificationIcon, RawPlatformEvent,
+    Result, RgbaIcon, RuntimeEvent, ScrollDelta, ScrollEvent, ScrollOrientation, SystemTheme,
+    ThemeChangedEvent, TooltipHiddenEvent, TooltipOverflow, TooltipShownEvent, Tray, TrayMenuItem,
+    TrayStatus, WINDOWS_TOOLTIP_MAX_LEN, event_queue, truncate_utf16,
 };
 use log::debug;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::hash::{DefaultHasher, Hash, Hasher};
 use std::os::windows::ffi::OsStrExt;
-use std::sync::Mutex;
 use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Mutex, PoisonError};
 use std::thread;
 use std::time::Duration;
-use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, TRUE, WPARAM};
+use windows::Win32::Foundation::{
+    ERROR_CLASS_ALREADY_EXISTS, ERROR_SUCCESS, GetLastError, HWND, LPARAM, LRESULT, POINT, TRUE,
+    WPARAM,
+};
+use windows::Win32::System::Registry::{HKEY_CURRENT_USER, RRF_RT_REG_DWORD, RegGetValueW};
+use windows::Win32::UI::HiDpi::GetDpiForWindow;
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_DOWN, VK_SHIFT};
 use windows::Win32::UI::Shell::{
-    NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NIM_MODIFY, NOTIFYICONDATAW,
-    Shell_NotifyIconW,
+    HELPINFO, HELPINFO_MENUITEM, NIF_GUID, NIF_ICON, NIF_INFO, NIF_MESSAGE, NIF_REALTIME,
+    NIF_SHOWTIP, NIF_STATE, NIF_TIP, NIIF_ERROR, NIIF_INFO, NIIF_LARGE_ICON, NIIF_NONE,
+    NIIF_NOSOUND, NIIF_USER, NIIF_WARNING, NIM_ADD, NIM_DELETE, NIM_MODIFY, NIM_SETVERSION,
+    NIN_BALLOONUSERCLICK, NIN_POPUPCLOSE, NIN_POPUPOPEN, NIS_HIDDEN, NOTIFY_ICON_STATE,
+    NOTIFYICON_VERSION_4, NOTIFYICONDATAW, NOTIFYICONDATAW_0, NOTIFYICONIDENTIFIER,
+    Shell_NotifyIconGetRect, Shell_NotifyIconW,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
     AppendMenuW, CreatePopupMenu, CreateWindowExW, DefWindowProcW, DestroyMenu, DestroyWindow,
-    DispatchMessageW, GWLP_USERDATA, GetCursorPos, GetWindowLongPtrW, HMENU, HWND_MESSAGE,
-    MF_POPUP, MF_SEPARATOR, MF_STRING, MSG, PM_REMOVE, PeekMessageW, PostMessageW, RegisterClassW,
-    RegisterWindowMessageW, SetForegroundWindow, SetWindowLongPtrW, TPM_BOTTOMALIGN, TPM_LEFTALIGN,
-    TrackPopupMenu, TranslateMessage, UnregisterClassW, WINDOW_EX_STYLE, WINDOW_STYLE, WM_APP,
-    WM_COMMAND, WM_LBUTTONDBLCLK, WM_LBUTTONUP, WM_MBUTTONUP, WM_NCCREATE, WM_NULL, WM_RBUTTONUP,
+    DispatchMessageW, EndMenu, FindWindowW, GWLP_USERDATA, GetCursorPos, GetWindowLongPtrW, HICON,
+    HMENU, KillTimer, MENUITEMINFOW, MF_CHECKED, MF_GRAYED, MF_POPUP, MF_SEPARATOR, MF_STRING,
+    MFT_RADIOCHECK, MFT_STRING, MIIM_FTYPE, MSG, PM_REMOVE, PeekMessageW, PostMessageW,
+    RegisterClassW, RegisterWindowMessageW, SetForegroundWindow, SetMenuItemInfoW, SetTimer,
+    SetWindowLongPtrW, TPM_BOTTOMALIGN, TPM_LEFTALIGN, TPM_RETURNCMD, TrackPopupMenu,
+    TranslateMessage, WHEEL_DELTA, WINDOW_EX_STYLE, WINDOW_STYLE, WM_APP, WM_CONTEXTMENU,
+    WM_DISPLAYCHANGE, WM_DPICHANGED, WM_HELP, WM_KEYDOWN, WM_LBUTTONDBLCLK, WM_LBUTTONUP,
+    WM_MBUTTONDBLCLK, WM_MBUTTONUP, WM_NCCREATE, WM_NULL, WM_RBUTTONUP, WM_SETTINGCHANGE, WM_TIMER,
     WNDCLASSW,
 };
-use windows::core::PCWSTR;
+use windows::core::{GUID, PCWSTR};
 
 const WM_TRAYICON: u32 = WM_APP + 71;
+/// Opens the popup menu for `PlatformTray::show_menu`, from `window_proc`
+/// rather than while a command borrows the state.
+const WM_TRAY_SHOW_MENU: u32 = WM_APP + 73;
 const TRAY_CLASS_NAME: &str = "GPUI::Tray::VNext";
 const TRAY_ID: u32 = 1;
+/// Timer that keeps commands flowing while `TrackPopupMenu` runs its modal loop.
+const MENU_PUMP_TIMER_ID: usize = 1;
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// Timer that blinks the icon while the tray asks for attention.
+const ATTENTION_TIMER_ID: usize = 2;
+const ATTENTION_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+/// Decoded animation frames kept at most; longer animations decode the rest
+/// every time.
+const MAX_CACHED_FRAMES: usize = 256;
+/// How long dropping the backend waits for the icon to be removed.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(500);
 
+// Commands only pass through the channel, so the tray is not boxed: that
+// would cost an allocation on every update.
+#[allow(clippy::large_enum_variant)]
 enum BackendCommand {
     SetTray {
         tray: Tray,
         response: Sender<Result<()>>,
     },
+    SetIcon {
+        icon: Option<gpui::Image>,
+        response: Sender<Result<()>>,
+    },
     RemoveTray {
         response: Sender<Result<()>>,
     },
+    Flush {
+        response: Sender<Result<()>>,
+    },
+    ShowMenu {
+        response: Sender<Result<()>>,
+    },
+    Notify {
+        notification: Box<Notification>,
+        response: Sender<Result<()>>,
+    },
     IconDecoded {
         revision: u64,
         icon_key: u64,
-        decoded: Result<DecodedIcon>,
+        decoded: Result<RgbaIcon>,
     },
     Shutdown,
 }
@@ -62,45 +114,124 @@ impl Drop for OwnedMenu {
 }
 
 struct TrayWindowState {
-    event_tx: Sender<RuntimeEvent>,
+    event_tx: EventSender,
     command_tx: Sender<BackendCommand>,
+    command_rx: Receiver<BackendCommand>,
     current_tray: Option<Tray>,
     current_icon: Option<OwnedIcon>,
-    current_menu: Option<OwnedMenu>,
-    menu_actions: HashMap<u16, Box<dyn Action>>,
+    /// Custom icon of the last notification, kept alive while it may be shown.
+    balloon_icon: Option<OwnedIcon>,
+    menu_open: bool,
+    /// Command ids of the popup menu entries.
+    menu_ids: MenuIds,
+    shutdown_requested: bool,
     registered: bool,
+    /// GUID the registered icon was added with.
+    icon_guid: Option<GUID>,
     requested_icon_revision: u64,
+    decoding_icon_revision: Option<u64>,
     current_icon_key: Option<u64>,
+    /// DPI the current icon was decoded for.
+    icon_dpi: u32,
+    /// Decoded frames of the running animation by image key, at `icon_dpi`,
+    /// so each frame is decoded once rather than every time it comes round.
+    frame_cache: HashMap<u64, RgbaIcon>,
+    /// Taskbar theme last reported to the app.
+    theme: Option<SystemTheme>,
+    flush_waiters: Vec<Sender<Result<()>>>,
     taskbar_restart_msg: u32,
+    /// Whether the shell accepted `NOTIFYICON_VERSION_4`, which reports
+    /// context menu requests from mouse and keyboard alike.
+    version_4: bool,
+    /// A right click was just reported; the `WM_CONTEXTMENU` that follows it
+    /// came from the mouse rather than the keyboard.
+    mouse_context_menu: bool,
+    /// Forwards the mouse wheel, which the shell does not report for icons.
+    wheel_hook: Option<WheelHook>,
+    /// Whether the attention timer runs.
+    blinking: bool,
+    /// Whether the icon is blanked for the current blink.
+    blink_off: bool,
 }
 
 impl TrayWindowState {
-    fn new(event_tx: Sender<RuntimeEvent>, command_tx: Sender<BackendCommand>) -> Self {
+    fn new(
+        event_tx: EventSender,
+        command_tx: Sender<BackendCommand>,
+        command_rx: Receiver<BackendCommand>,
+    ) -> Self {
         Self {
             event_tx,
             command_tx,
+            command_rx,
             current_tray: None,
             current_icon: None,
-            current_menu: None,
-            menu_actions: HashMap::new(),
+            balloon_icon: None,
+            menu_open: false,
+            menu_ids: MenuIds::default(),
+            shutdown_requested: false,
             registered: false,
+            icon_guid: None,
             requested_icon_revision: 0,
+            decoding_icon_revision: None,
             current_icon_key: None,
+            icon_dpi: 0,
+            frame_cache: HashMap::new(),
+            theme: None,
+            flush_waiters: Vec::new(),
             taskbar_restart_msg: unsafe {
                 RegisterWindowMessageW(windows::core::w!("TaskbarCreated"))
             },
+            version_4: false,
+            mouse_context_menu: false,
+            wheel_hook: None,
+            blinking: false,
+            blink_off: false,
         }
     }
 
-    fn clear_menu(&mut self) {
-        self.current_menu.take();
-        self.menu_actions.clear();
+    /// Whether an icon decode for the latest requested revision is in flight.
+    fn icon_decode_pending(&self) -> bool {
+        self.decoding_icon_revision == Some(self.requested_icon_revision)
+    }
+
+    fn settle_flush_waiters(&mut self) {
+        if self.icon_decode_pending() {
+            return;
+        }
+
+        for waiter in self.flush_waiters.drain(..) {
+            let _ = waiter.send(Ok(()));
+        }
     }
 }
 
+type ReplyChannel = (Sender<Result<()>>, Receiver<Result<()>>);
+
 pub(crate) struct WindowsBackend {
     command_tx: Sender<BackendCommand>,
-    event_rx: Mutex<Receiver<RuntimeEvent>>,
+    event_rx: EventReceiver,
+    /// Reply channel of `set_tray`, reused because apps may update the tray
+    /// every second. Locked for the whole round trip.
+    set_tray_reply: Mutex<ReplyChannel>,
+    /// Disconnects once the backend thread has exited.
+    exited: Mutex<Receiver<()>>,
+}
+
+// The thread keeps a command sender of its own for decoded icons, so it has
+// to be told to stop. Waiting for it means the icon is gone before a tray
+// that replaces this one adds its own, and before the process exits.
+impl Drop for WindowsBackend {
+    fn drop(&mut self) {
+        let _ = self.command_tx.send(BackendCommand::Shutdown);
+        let exited = self.exited.lock().unwrap_or_else(PoisonError::into_inner);
+        if !matches!(
+            exited.recv_timeout(SHUTDOWN_TIMEOUT),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        ) {
+            debug!("windows backend thread did not exit in time");
+        }
+    }
 }
 
 impl WindowsBackend {
@@ -116,6 +247,13 @@ impl WindowsBackend {
 
 impl PlatformTray for WindowsBackend {
     fn set_tray(&self, tray: Tray) -> Result<()> {
+        if tray.tooltip_overflow == TooltipOverflow::Error
+            && tip_text(&tray).encode_utf16().count() > WINDOWS_TOOLTIP_MAX_LEN
+        {
+            return Err(Error::TooltipTooLong {
+                max: WINDOWS_TOOLTIP_MAX_LEN,
+            });
+        }
         debug!(
             "set_tray requested, visible={}, tooltip={:?}, has_icon={}, has_menu={}",
             tray.visible,
@@ -123,20 +261,55 @@ impl PlatformTray for WindowsBackend {
             tray.icon.is_some(),
             tray.menu_builder.is_some()
         );
-        self.send_and_wait(|response| BackendCommand::SetTray { tray, response })
+        let reply = self
+            .set_tray_reply
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (response, reply_rx) = &*reply;
+        self.command_tx
+            .send(BackendCommand::SetTray {
+                tray,
+                response: response.clone(),
+            })
+            .map_err(|_| Error::Backend(BackendError::ChannelSend))?;
+        reply_rx
+            .recv()
+            .map_err(|_| Error::Backend(BackendError::ChannelReceive))?
+    }
+
+    fn set_icon(&self, icon: Option<gpui::Image>) -> Result<()> {
+        self.send_and_wait(|response| BackendCommand::SetIcon { icon, response })
     }
 
     fn remove_tray(&self) -> Result<()> {
         self.send_and_wait(|response| BackendCommand::RemoveTray { response })
     }
 
+    fn show_menu(&self) -> Result<()> {
+        self.send_and_wait(|response| BackendCommand::ShowMenu { response })
+    }
+
+    fn notify(&self, notification: Notification) -> Result<()> {
+        self.send_and_wait(|response| BackendCommand::Notify {
+            notification: Box::new(notification),
+            response,
+        })
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.send_and_wait(|response| BackendCommand::Flush { response })
+    }
+
     fn try_recv_event(&self) -> Result<Option<RuntimeEvent>> {
-        let rx = self.event_rx.lock().map_err(|_| Error::RuntimeClosed)?;
-        match rx.try_recv() {
-            Ok(event) => Ok(Some(event)),
-            Err(TryRecvError::Empty) => Ok(None),
-            Err(TryRecvError::Disconnected) => Err(Error::RuntimeClosed),
-        }
+        self.event_rx.try_recv()
+    }
+
+    fn set_event_policy(&self, policy: EventQueuePolicy) {
+        self.event_rx.set_policy(policy);
+    }
+
+    fn event_stats(&self) -> EventQueueStats {
+        self.event_rx.stats()
     }
 
     fn shutdown(&self) -> Result<()> {
@@ -149,13 +322,16 @@ impl PlatformTray for WindowsBackend {
 
 pub fn create() -> Result<Box<dyn PlatformTray>> {
     let (command_tx, command_rx) = mpsc::channel::<BackendCommand>();
-    let (event_tx, event_rx) = mpsc::channel::<RuntimeEvent>();
+    let (event_tx, event_rx) = event_queue(EventQueuePolicy::default());
     let (boot_tx, boot_rx) = mpsc::channel::<Result<()>>();
+    let (exit_tx, exited) = mpsc::channel::<()>();
 
     let thread_command_tx = command_tx.clone();
     thread::Builder::new()
         .name("gpui-tray-windows".to_string())
         .spawn(move || {
+            // Dropped however the thread ends, unwinding included.
+            let _exit_tx = exit_tx;
             backend_thread_main(command_rx, thread_command_tx, event_tx, boot_tx);
         })
         .map_err(|err| Error::Backend(BackendError::platform("spawn", err.to_string())))?;
@@ -166,16 +342,57 @@ pub fn create() -> Result<Box<dyn PlatformTray>> {
 
     Ok(Box::new(WindowsBackend {
         command_tx,
-        event_rx: Mutex::new(event_rx),
+        event_rx,
+        set_tray_reply: Mutex::new(mpsc::channel()),
+        exited: Mutex::new(exited),
     }))
 }
 
 fn backend_thread_main(
     command_rx: Receiver<BackendCommand>,
     command_tx: Sender<BackendCommand>,
-    event_tx: Sender<RuntimeEvent>,
+    event_tx: EventSender,
     boot_tx: Sender<Result<()>>,
 ) {
+    let state = Box::new(TrayWindowState::new(event_tx, command_tx, command_rx));
+    let mut window = match open_window(state) {
+        Ok(window) => window,
+        Err(err) => {
+            let _ = boot_tx.send(Err(err));
+            return;
+        }
+    };
+    let hwnd = window.hwnd;
+    let state = &mut window.state;
+    let _ = boot_tx.send(Ok(()));
+    refresh_theme(state.as_mut());
+
+    while !state.shutdown_requested {
+        process_window_messages();
+        if state.shutdown_requested {
+            break;
+        }
+
+        match state.command_rx.recv_timeout(COMMAND_POLL_INTERVAL) {
+            Ok(cmd) => {
+                if handle_command(hwnd, state.as_mut(), cmd) {
+                    drain_commands(hwnd, state.as_mut());
+                } else {
+                    state.shutdown_requested = true;
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                state.shutdown_requested = true;
+            }
+        }
+
+        state.settle_flush_waiters();
+    }
+}
+
+/// Creates the hidden window whose procedure handles `state`.
+fn open_window(mut state: Box<TrayWindowState>) -> Result<TrayWindow> {
     let class_name = encode_wide(TRAY_CLASS_NAME);
     let wc = WNDCLASSW {
         lpfnWndProc: Some(window_proc),
@@ -183,17 +400,14 @@ fn backend_thread_main(
         ..Default::default()
     };
 
+    // The class outlives this thread: a backend replacing this one may
+    // register it while this thread still shuts down, or two may run side
+    // by side, so it is registered once and never unregistered.
     let atom = unsafe { RegisterClassW(&wc) };
-    if atom == 0 {
-        let _ = boot_tx.send(Err(BackendError::platform(
-            "RegisterClassW",
-            "returned atom=0",
-        )
-        .into()));
-        return;
+    if atom == 0 && unsafe { GetLastError() } != ERROR_CLASS_ALREADY_EXISTS {
+        return Err(BackendError::platform("RegisterClassW", "returned atom=0").into());
     }
 
-    let mut state = Box::new(TrayWindowState::new(event_tx, command_tx));
     let hwnd = unsafe {
         CreateWindowExW(
             WINDOW_EX_STYLE(0),
@@ -204,56 +418,45 @@ fn backend_thread_main(
             0,
             0,
             0,
-            Some(HWND_MESSAGE),
+            // A hidden top-level window rather than a message-only one:
+            // only those receive the DPI, display and taskbar broadcasts.
+            None,
             None,
             None,
             Some(state.as_mut() as *mut TrayWindowState as *const _),
         )
-    };
+    }
+    .map_err(|err| {
+        debug!("CreateWindowExW failed: {err:?}");
+        BackendError::platform("CreateWindowExW", format!("{err:?}"))
+    })?;
 
-    let hwnd = match hwnd {
-        Ok(hwnd) => hwnd,
-        Err(err) => {
-            debug!("CreateWindowExW failed: {err:?}");
-            let _ = boot_tx.send(Err(BackendError::platform(
-                "CreateWindowExW",
-                format!("{err:?}"),
-            )
-            .into()));
-            unsafe {
-                let _ = UnregisterClassW(PCWSTR(class_name.as_ptr()), None);
-            }
-            return;
-        }
-    };
+    Ok(TrayWindow { hwnd, state })
+}
 
-    let _ = boot_tx.send(Ok(()));
+/// The hidden window with the state its window procedure points to.
+///
+/// Dropping it removes the icon and destroys the window before the state,
+/// so nothing is left in the notification area even when the thread
+/// unwinds from a panic. The icon and menu handles free themselves.
+struct TrayWindow {
+    hwnd: HWND,
+    state: Box<TrayWindowState>,
+}
 
-    let mut running = true;
-    while running {
-        process_window_messages();
+impl Drop for TrayWindow {
+    fn drop(&mut self) {
+        let state = self.state.as_mut();
+        state.wheel_hook = None;
+        remove_tray_icon(self.hwnd, state);
+        state.current_icon = None;
+        state.balloon_icon = None;
 
-        match command_rx.recv_timeout(Duration::from_millis(10)) {
-            Ok(cmd) => {
-                running = handle_command(hwnd, state.as_mut(), cmd);
-                while let Ok(cmd) = command_rx.try_recv() {
-                    if !handle_command(hwnd, state.as_mut(), cmd) {
-                        running = false;
-                        break;
-                    }
-                }
-            }
-            Err(mpsc::RecvTimeoutError::Timeout) => {}
-            Err(mpsc::RecvTimeoutError::Disconnected) => {
-                running = false;
-            }
+        unsafe {
+            SetWindowLongPtrW(self.hwnd, GWLP_USERDATA, 0);
+            let _ = DestroyWindow(self.hwnd);
         }
     }
-
-    cleanup(hwnd, state.as_mut());
-    unsafe {
-        let _ = UnregisterClassW(PCWSTR(class_name.as_ptr()), None);
-    }
 }
 
 fn process_window_messages() {
@@ -266,6 +469,25 @@ fn process_window_messages() {
     }
 }
 
+/// Handles every queued command without blocking.
+fn drain_commands(hwnd: HWND, state: &mut TrayWindowState) {
+    loop {
+        match state.command_rx.try_recv() {
+            Ok(cmd) => {
+                if !handle_command(hwnd, state, cmd) {
+                    state.shutdown_requested = true;
+                    return;
+                }
+            }
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                state.shutdown_requested = true;
+                return;
+            }
+        }
+    }
+}
+
 fn handle_command(hwnd: HWND, state: &mut TrayWindowState, cmd: BackendCommand) -> bool {
     match cmd {
         BackendCommand::SetTray { tray, response } => {
@@ -275,9 +497,32 @@ fn handle_command(hwnd: HWND, state: &mut TrayWindowState, cmd: BackendCommand)
                 tray.icon.is_some(),
                 tray.menu_builder.is_some()
             );
-            let result = apply_tray_snapshot(hwnd, state, tray.clone());
+            if tray.animation.is_none() {
+                state.frame_cache.clear();
+            }
+            let result = apply_tray_snapshot(hwnd, state, tray);
             if result.is_ok() {
-                schedule_icon_decode(state, tray);
+                schedule_icon_decode(hwnd, state);
+            }
+            let _ = response.send(result);
+            true
+        }
+        BackendCommand::SetIcon { icon, response } => {
+            let Some(tray) = state.current_tray.as_mut() else {
+                let _ = response.send(Err(Error::NotFound));
+                return true;
+            };
+            // Only the icon changes, so the tooltip and state stay as they are.
+            let clears_shown_icon = icon.is_none() && tray.visible;
+            tray.icon = icon;
+            let result = if clears_shown_icon {
+                state.current_icon = None;
+                add_or_update_icon(hwnd, state, false)
+            } else {
+                Ok(())
+            };
+            if result.is_ok() {
+                schedule_icon_decode(hwnd, state);
             }
             let _ = response.send(result);
             true
@@ -287,10 +532,26 @@ fn handle_command(hwnd: HWND, state: &mut TrayWindowState, cmd: BackendCommand)
             state.requested_icon_revision = state.requested_icon_revision.saturating_add(1);
             remove_tray_icon(hwnd, state);
             state.current_icon = None;
-            state.clear_menu();
             let _ = response.send(Ok(()));
             true
         }
+        BackendCommand::Flush { response } => {
+            state.flush_waiters.push(response);
+            true
+        }
+        BackendCommand::ShowMenu { response } => {
+            // Answer first: the menu loop blocks until the user closes it.
+            let _ = response.send(Ok(()));
+            let _ = unsafe { PostMessageW(Some(hwnd), WM_TRAY_SHOW_MENU, WPARAM(0), LPARAM(0)) };
+            true
+        }
+        BackendCommand::Notify {
+            notification,
+            response,
+        } => {
+            let _ = response.send(show_notification(hwnd, state, &notification));
+            true
+        }
         BackendCommand::IconDecoded {
             revision,
             icon_key,
@@ -307,38 +568,21 @@ fn handle_command(hwnd: HWND, state: &mut TrayWindowState, cmd: BackendCommand)
                 );
                 return true;
             }
-
-            let Some(tray) = state.current_tray.as_ref() else {
-                return true;
-            };
-
-            if !tray.visible {
-                debug!(
-                    "decoded icon ignored: tray hidden revision={} key={}",
-                    revision, icon_key
-                );
-                return true;
-            }
-
+            state.decoding_icon_revision = None;
             match decoded {
-                Ok(decoded) => match create_hicon(&decoded) {
-                    Ok(icon) => {
-                        debug!(
-                            "applying decoded icon revision={} key={}",
-                            revision, icon_key
-                        );
-                        state.current_icon = Some(icon);
-                        state.current_icon_key = Some(icon_key);
-                        if let Err(err) = add_or_update_icon(hwnd, state, false) {
-                            log::error!("failed to apply decoded icon: {err}");
-                        }
-                    }
-                    Err(err) => {
-                        log::error!("failed to create icon handle: {err}");
+                Ok(decoded) => {
+                    let animated = state
+                        .current_tray
+                        .as_ref()
+                        .is_some_and(|tray| tray.animation.is_some());
+                    if animated && state.frame_cache.len() < MAX_CACHED_FRAMES {
+                        state.frame_cache.insert(icon_key, decoded.clone());
                     }
-                },
+                    apply_decoded_icon(hwnd, state, icon_key, &decoded);
+                }
                 Err(err) => {
                     log::error!("failed to decode tray icon: {err}");
+                    report_error(state, err);
                 }
             }
             true
@@ -347,59 +591,170 @@ fn handle_command(hwnd: HWND, state: &mut TrayWindowState, cmd: BackendCommand)
     }
 }
 
-fn schedule_icon_decode(state: &mut TrayWindowState, tray: Tray) {
-    if let Some(image) = tray.icon {
+/// Decodes the icon of the current tray on a worker thread, unless the icon
+/// shown already is that one.
+fn schedule_icon_decode(hwnd: HWND, state: &mut TrayWindowState) {
+    let Some(image) = state
+        .current_tray
+        .as_ref()
+        .and_then(|tray| tray.icon.as_ref())
+    else {
         state.requested_icon_revision = state.requested_icon_revision.saturating_add(1);
-        let revision = state.requested_icon_revision;
-        let icon_key = image_key(&image);
+        state.current_icon_key = None;
+        return;
+    };
+
+    let dpi = window_dpi(hwnd);
+    state.requested_icon_revision = state.requested_icon_revision.saturating_add(1);
+    let revision = state.requested_icon_revision;
+    let icon_key = image_key(image);
+    debug!(
+        "schedule icon decode revision={} key={}",
+        revision, icon_key
+    );
+
+    if state.current_icon_key == Some(icon_key)
+        && state.current_icon.is_some()
+        && state.icon_dpi == dpi
+    {
+        debug!("icon decode skipped: unchanged key={}", icon_key);
+        return;
+    }
+    if state.icon_dpi != dpi {
+        state.frame_cache.clear();
+    }
+    state.icon_dpi = dpi;
+    if let Some(decoded) = state.frame_cache.get(&icon_key).cloned() {
+        debug!("icon decode skipped: cached frame key={}", icon_key);
+        state.decoding_icon_revision = None;
+        apply_decoded_icon(hwnd, state, icon_key, &decoded);
+        return;
+    }
+
+    // Copied only now, as most updates leave the icon alone.
+    let image = image.clone();
+    state.decoding_icon_revision = Some(revision);
+    let tx = state.command_tx.clone();
+    thread::spawn(move || {
+        let start = std::time::Instant::now();
+        let decoded = decode_icon(&image, IconSize::Small, dpi);
         debug!(
-            "schedule icon decode revision={} key={}",
-            revision, icon_key
+            "windows backend decode thread: revision={} key={} done in {:?}",
+            revision,
+            icon_key,
+            start.elapsed()
         );
+        let _ = tx.send(BackendCommand::IconDecoded {
+            revision,
+            icon_key,
+            decoded,
+        });
+    });
+}
 
-        if state.current_icon_key == Some(icon_key) && state.current_icon.is_some() {
-            debug!("icon decode skipped: unchanged key={}", icon_key);
-            return;
+/// Shows a decoded icon, or keeps it for when a hidden tray is shown again.
+fn apply_decoded_icon(hwnd: HWND, state: &mut TrayWindowState, icon_key: u64, decoded: &RgbaIcon) {
+    let Some(visible) = state.current_tray.as_ref().map(|tray| tray.visible) else {
+        return;
+    };
+    match create_hicon(decoded) {
+        Ok(icon) => {
+            debug!("applying decoded icon key={} visible={}", icon_key, visible);
+            // Keep the icon while hidden so showing the tray again restores
+            // it without another decode.
+            state.current_icon = Some(icon);
+            state.current_icon_key = Some(icon_key);
+            if !visible {
+                return;
+            }
+            if let Err(err) = add_or_update_icon(hwnd, state, false) {
+                log::error!("failed to apply decoded icon: {err}");
+                report_error(state, err);
+            }
+        }
+        Err(err) => {
+            log::error!("failed to create icon handle: {err}");
+            report_error(state, err);
         }
+    }
+}
 
-        let tx = state.command_tx.clone();
-        thread::spawn(move || {
-            let start = std::time::Instant::now();
-            let decoded = decode_icon(&image);
-            debug!(
-                "windows backend decode thread: revision={} key={} done in {:?}",
-                revision,
-                icon_key,
-                start.elapsed()
-            );
-            let _ = tx.send(BackendCommand::IconDecoded {
-                revision,
-                icon_key,
-                decoded,
-            });
-        });
+/// DPI of the monitor the tray window belongs to.
+fn window_dpi(hwnd: HWND) -> u32 {
+    match unsafe { GetDpiForWindow(hwnd) } {
+        0 => 96,
+        dpi => dpi,
+    }
+}
+
+/// Decodes the icon again when the DPI changed since it was last decoded,
+/// so it stays sharp after a scaling change or on mixed-DPI setups.
+fn refresh_icon_dpi(hwnd: HWND, state: &mut TrayWindowState) {
+    let dpi = window_dpi(hwnd);
+    if dpi == state.icon_dpi {
+        return;
+    }
+    if state.current_tray.is_none() {
+        return;
+    }
+    debug!(
+        "tray dpi changed {} -> {}, redecoding icon",
+        state.icon_dpi, dpi
+    );
+    schedule_icon_decode(hwnd, state);
+}
+
+/// Theme of the taskbar, which the notification area shares. Without the
+/// registry value, before Windows 10 1903, the taskbar is always dark.
+fn taskbar_theme() -> SystemTheme {
+    let mut light = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            windows::core::w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            windows::core::w!("SystemUsesLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut light as *mut u32 as *mut _),
+            Some(&mut size),
+        )
+    };
+    if status == ERROR_SUCCESS && light != 0 {
+        SystemTheme::Light
     } else {
-        state.requested_icon_revision = state.requested_icon_revision.saturating_add(1);
-        state.current_icon_key = None;
+        SystemTheme::Dark
     }
 }
 
+/// Reports the taskbar theme when it differs from the last one reported.
+fn refresh_theme(state: &mut TrayWindowState) {
+    let theme = taskbar_theme();
+    if state.theme == Some(theme) {
+        return;
+    }
+    debug!("taskbar theme is {theme:?}");
+    state.theme = Some(theme);
+    state
+        .event_tx
+        .send(RuntimeEvent::Action(Box::new(ThemeChangedEvent { theme })));
+}
+
 fn apply_tray_snapshot(hwnd: HWND, state: &mut TrayWindowState, tray: Tray) -> Result<()> {
     debug!(
         "apply snapshot visible={}, tooltip={:?}",
         tray.visible, tray.tooltip
     );
-    state.current_tray = Some(tray.clone());
-    state.clear_menu();
+    let visible = tray.visible;
+    let has_icon = tray.icon.is_some();
+    state.current_tray = Some(tray);
 
-    if !tray.visible {
+    if !visible {
         remove_tray_icon(hwnd, state);
-        state.current_icon = None;
-        state.current_icon_key = None;
         return Ok(());
     }
 
-    if tray.icon.is_none() {
+    if !has_icon {
         state.current_icon = None;
         state.current_icon_key = None;
     }
@@ -409,36 +764,43 @@ fn apply_tray_snapshot(hwnd: HWND, state: &mut TrayWindowState, tray: Tray) -> R
 }
 
 fn add_or_update_icon(hwnd: HWND, state: &mut TrayWindowState, force_add: bool) -> Result<()> {
+    let guid = state
+        .current_tray
+        .as_ref()
+        .ok_or(Error::NotFound)?
+        .windows
+        .guid
+        .map(GUID::from_u128);
+    if state.registered && state.icon_guid != guid {
+        debug!("tray icon guid changed, adding the icon again");
+        remove_tray_icon(hwnd, state);
+    }
     let Some(tray) = state.current_tray.as_ref() else {
         return Err(Error::NotFound);
     };
 
-    let mut tip = [0u16; 128];
-    if let Some(tooltip) = &tray.tooltip {
-        for (index, ch) in encode_wide(tooltip.as_ref())
-            .into_iter()
-            .take(127)
-            .enumerate()
-        {
-            tip[index] = ch;
-        }
-    }
+    let tip = wide_buffer::<128>(&tip_text(tray));
 
-    let hicon = state
-        .current_icon
-        .as_ref()
-        .map(|icon| icon.0)
-        .unwrap_or_default();
-    let flags = NIF_MESSAGE | NIF_TIP | NIF_ICON;
+    let hicon = shown_icon(state);
+    let state_bits = if tray.current_status() == TrayStatus::Passive || tray.windows.hidden {
+        NIS_HIDDEN
+    } else {
+        NOTIFY_ICON_STATE(0)
+    };
+    // Version 4 suppresses the standard tooltip unless NIF_SHOWTIP is set.
+    let mut flags = NIF_MESSAGE | NIF_TIP | NIF_ICON | NIF_STATE;
+    if tray.windows.show_tip {
+        flags |= NIF_SHOWTIP;
+    }
+    let base = icon_data(hwnd, guid);
     let nid = NOTIFYICONDATAW {
-        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
-        hWnd: hwnd,
-        uID: TRAY_ID,
-        uFlags: flags,
+        uFlags: base.uFlags | flags,
         uCallbackMessage: WM_TRAYICON,
         hIcon: hicon,
         szTip: tip,
-        ..unsafe { std::mem::zeroed() }
+        dwState: state_bits,
+        dwStateMask: NIS_HIDDEN,
+        ..base
     };
 
     let op = if force_add || !state.registered {
@@ -447,15 +809,29 @@ fn add_or_update_icon(hwnd: HWND, state: &mut TrayWindowState, force_add: bool)
         NIM_MODIFY
     };
     debug!(
-        "Shell_NotifyIconW op={:?}, force_add={}, registered={}, has_hicon={}",
+        "Shell_NotifyIconW op={:?}, force_add={}, registered={}, has_hicon={}, passive={}",
         op,
         force_add,
         state.registered,
-        !hicon.is_invalid()
+        !hicon.is_invalid(),
+        tray.passive
     );
 
+    // An icon a crashed run left under the same GUID makes adding fail.
+    if op == NIM_ADD
+        && guid.is_some()
+        && unsafe { Shell_NotifyIconW(NIM_DELETE, &icon_data(hwnd, guid)) } == TRUE
+    {
+        debug!("removed a tray icon left behind by an earlier run");
+    }
+
     let result = unsafe { Shell_NotifyIconW(op, &nid) };
     if result != TRUE {
+        if op == NIM_ADD && !taskbar_running() {
+            return Err(Error::PlatformUnavailable {
+                reason: "the taskbar is not running".into(),
+            });
+        }
         return Err(BackendError::platform(
             "Shell_NotifyIconW",
             format!("operation {op:?} failed"),
@@ -463,32 +839,205 @@ fn add_or_update_icon(hwnd: HWND, state: &mut TrayWindowState, force_add: bool)
         .into());
     }
 
+    if op == NIM_ADD {
+        state.version_4 = set_icon_version(hwnd, guid);
+    }
+
+    state.icon_guid = guid;
     state.registered = true;
+    sync_attention_blink(hwnd, state);
+    sync_wheel_hook(hwnd, state);
     Ok(())
 }
 
-fn remove_tray_icon(hwnd: HWND, state: &mut TrayWindowState) {
-    if !state.registered {
+/// The icon as it should show right now, blanked between blinks.
+fn shown_icon(state: &TrayWindowState) -> HICON {
+    state
+        .current_icon
+        .as_ref()
+        .filter(|_| !state.blink_off)
+        .map(|icon| icon.0)
+        .unwrap_or_default()
+}
+
+/// Starts or stops blinking the icon to follow the tray's attention state.
+/// Windows has no attention state of its own.
+fn sync_attention_blink(hwnd: HWND, state: &mut TrayWindowState) {
+    let wanted = state.registered
+        && state
+            .current_tray
+            .as_ref()
+            .is_some_and(|tray| tray.attention && !tray.windows.hidden);
+    if wanted == state.blinking {
         return;
     }
+    state.blinking = wanted;
+    if wanted {
+        unsafe {
+            SetTimer(
+                Some(hwnd),
+                ATTENTION_TIMER_ID,
+                ATTENTION_BLINK_INTERVAL.as_millis() as u32,
+                None,
+            );
+        }
+        return;
+    }
+    let _ = unsafe { KillTimer(Some(hwnd), ATTENTION_TIMER_ID) };
+    if state.blink_off {
+        state.blink_off = false;
+        if state.registered {
+            set_shown_icon(hwnd, state);
+        }
+    }
+}
+
+/// Swaps only the icon, leaving tooltip and state as they are.
+fn set_shown_icon(hwnd: HWND, state: &TrayWindowState) {
+    let base = icon_data(hwnd, state.icon_guid);
+    let nid = NOTIFYICONDATAW {
+        uFlags: base.uFlags | NIF_ICON,
+        hIcon: shown_icon(state),
+        ..base
+    };
+    if unsafe { Shell_NotifyIconW(NIM_MODIFY, &nid) } != TRUE {
+        debug!("Shell_NotifyIconW failed to swap the blinking icon");
+    }
+}
+
+/// Opts into `NOTIFYICON_VERSION_4` callbacks, which carry the event in the
+/// low word of `lParam` and add the tooltip popup notifications.
+fn set_icon_version(hwnd: HWND, guid: Option<GUID>) -> bool {
+    let nid = NOTIFYICONDATAW {
+        Anonymous: NOTIFYICONDATAW_0 {
+            uVersion: NOTIFYICON_VERSION_4,
+        },
+        ..icon_data(hwnd, guid)
+    };
+
+    if unsafe { Shell_NotifyIconW(NIM_SETVERSION, &nid) } != TRUE {
+        debug!("Shell_NotifyIconW NIM_SETVERSION failed, tooltip events unavailable");
+        return false;
+    }
+    true
+}
+
+/// Shows a balloon notification, a toast on Windows 10 and later.
+fn show_notification(
+    hwnd: HWND,
+    state: &mut TrayWindowState,
+    notification: &Notification,
+) -> Result<()> {
+    // The shell anchors notifications to an icon, so one must be registered.
+    if !state.registered {
+        return Err(Error::NotFound);
+    }
+
+    let options = &notification.options;
+    let default_icon = state
+        .current_tray
+        .as_ref()
+        .and_then(|tray| tray.windows.balloon_icon.clone());
+    let custom_icon = match &options.icon {
+        NotificationIcon::Custom(image) => Some(image),
+        NotificationIcon::None => default_icon.as_ref(),
+        _ => None,
+    };
+    let mut info_flags = match &options.icon {
+        _ if custom_icon.is_some() => NIIF_USER | NIIF_LARGE_ICON,
+        NotificationIcon::None => NIIF_NONE,
+        NotificationIcon::Info => NIIF_INFO,
+        NotificationIcon::Warning => NIIF_WARNING,
+        NotificationIcon::Error => NIIF_ERROR,
+        NotificationIcon::Custom(_) => NIIF_USER | NIIF_LARGE_ICON,
+    };
+    if options.silent {
+        info_flags |= NIIF_NOSOUND;
+    }
+
+    let mut flags = NIF_INFO;
+    if options.realtime {
+        flags |= NIF_REALTIME;
+    }
+
+    let balloon_icon = match custom_icon {
+        Some(image) => {
+            let icon = create_hicon(&decode_icon(image, IconSize::Large, window_dpi(hwnd))?)?;
+            let handle = icon.0;
+            state.balloon_icon = Some(icon);
+            handle
+        }
+        None => Default::default(),
+    };
 
+    let base = icon_data(hwnd, state.icon_guid);
     let nid = NOTIFYICONDATAW {
+        uFlags: base.uFlags | flags,
+        szInfo: wide_buffer(notification.body.as_ref()),
+        szInfoTitle: wide_buffer(notification.title.as_ref()),
+        dwInfoFlags: info_flags,
+        hBalloonIcon: balloon_icon,
+        ..base
+    };
+
+    debug!(
+        "Shell_NotifyIconW notification title_len={}, flags={:?}",
+        notification.title.len(),
+        info_flags
+    );
+    if unsafe { Shell_NotifyIconW(NIM_MODIFY, &nid) } != TRUE {
+        return Err(BackendError::platform("Shell_NotifyIconW", "NIF_INFO failed").into());
+    }
+    Ok(())
+}
+
+/// Whether Explorer's taskbar, which hosts the notification area, exists.
+pub(crate) fn taskbar_running() -> bool {
+    unsafe { FindWindowW(windows::core::w!("Shell_TrayWnd"), PCWSTR::null()) }.is_ok()
+}
+
+/// Identifies the icon by `guid` when it has one, otherwise by the window
+/// and `TRAY_ID`.
+fn icon_data(hwnd: HWND, guid: Option<GUID>) -> NOTIFYICONDATAW {
+    let mut nid = NOTIFYICONDATAW {
         cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
         hWnd: hwnd,
         uID: TRAY_ID,
         ..unsafe { std::mem::zeroed() }
     };
+    if let Some(guid) = guid {
+        nid.uFlags = NIF_GUID;
+        nid.guidItem = guid;
+    }
+    nid
+}
+
+fn remove_tray_icon(hwnd: HWND, state: &mut TrayWindowState) {
+    if !state.registered {
+        return;
+    }
+
+    let nid = icon_data(hwnd, state.icon_guid);
     let _ = unsafe { Shell_NotifyIconW(NIM_DELETE, &nid) };
     state.registered = false;
+    sync_attention_blink(hwnd, state);
+    sync_wheel_hook(hwnd, state);
 }
 
-fn cleanup(hwnd: HWND, state: &mut TrayWindowState) {
-    remove_tray_icon(hwnd, state);
-    state.current_icon = None;
-    state.clear_menu();
-
-    unsafe {
-        let _ = DestroyWindow(hwnd);
+/// Installs the wheel hook while the shown tray asks for scroll events, and
+/// removes it otherwise.
+fn sync_wheel_hook(hwnd: HWND, state: &mut TrayWindowState) {
+    let wanted = state.registered
+        && state
+            .current_tray
+            .as_ref()
+            .is_some_and(|tray| tray.windows.scroll_events);
+    if wanted != state.wheel_hook.is_some() {
+        state.wheel_hook = if wanted {
+            WheelHook::install(hwnd)
+        } else {
+            None
+        };
     }
 }
 
@@ -514,47 +1063,136 @@ unsafe extern "system" fn window_proc(
 
     let state = unsafe { &mut *ptr };
 
+    if let Some(handler) = state
+        .current_tray
+        .as_ref()
+        .and_then(|tray| tray.raw_event_handler.as_ref())
+    {
+        handler(&RawPlatformEvent::WindowMessage {
+            msg,
+            wparam: wparam.0,
+            lparam: lparam.0,
+        });
+    }
+
     match msg {
         WM_TRAYICON => {
-            let event = lparam.0 as u32;
+            let event = (lparam.0 as u32) & 0xFFFF;
             match event {
                 WM_LBUTTONUP => {
                     debug!("WM_TRAYICON event=WM_LBUTTONUP");
-                    dispatch_click(state, MouseButton::Left)
+                    // The primary action would run along with the menu.
+                    if menu_opens_on(state, MouseButton::Left) {
+                        show_context_menu(hwnd, ptr, None);
+                    } else {
+                        dispatch_click(state, MouseButton::Left);
+                    }
                 }
                 WM_MBUTTONUP => {
                     debug!("WM_TRAYICON event=WM_MBUTTONUP");
-                    dispatch_click(state, MouseButton::Middle)
+                    dispatch_click(state, MouseButton::Middle);
+                    if menu_opens_on(state, MouseButton::Middle) {
+                        show_context_menu(hwnd, ptr, None);
+                    }
                 }
                 WM_RBUTTONUP => {
                     debug!("WM_TRAYICON event=WM_RBUTTONUP");
                     dispatch_click(state, MouseButton::Right);
-                    show_context_menu(hwnd, state);
+                    if state.version_4 {
+                        state.mouse_context_menu = true;
+                    } else if menu_opens_on(state, MouseButton::Right) {
+                        show_context_menu(hwnd, ptr, None);
+                    }
+                }
+                WM_CONTEXTMENU => {
+                    // Shift+F10 or the menu key on the focused icon (Win+B)
+                    // is not preceded by a right click.
+                    let keyboard = !std::mem::take(&mut state.mouse_context_menu);
+                    debug!("WM_TRAYICON event=WM_CONTEXTMENU, keyboard={keyboard}");
+                    if !keyboard && !menu_opens_on(state, MouseButton::Right) {
+                        return LRESULT(0);
+                    }
+                    // The anchor is packed into `wParam` as signed screen coordinates.
+                    let anchor = keyboard.then_some(POINT {
+                        x: (wparam.0 & 0xFFFF) as i16 as i32,
+                        y: ((wparam.0 >> 16) & 0xFFFF) as i16 as i32,
+                    });
+                    show_context_menu(hwnd, ptr, anchor);
                 }
                 WM_LBUTTONDBLCLK => {
                     debug!("WM_TRAYICON event=WM_LBUTTONDBLCLK");
-                    let _ = state
+                    dispatch_double_click(state, MouseButton::Left);
+                }
+                WM_MBUTTONDBLCLK => {
+                    debug!("WM_TRAYICON event=WM_MBUTTONDBLCLK");
+                    dispatch_double_click(state, MouseButton::Middle);
+                }
+                NIN_POPUPOPEN => {
+                    debug!("WM_TRAYICON event=NIN_POPUPOPEN");
+                    state
+                        .event_tx
+                        .send(RuntimeEvent::Action(Box::new(TooltipShownEvent)));
+                }
+                NIN_BALLOONUSERCLICK => {
+                    debug!("WM_TRAYICON event=NIN_BALLOONUSERCLICK");
+                    state
+                        .event_tx
+                        .send(RuntimeEvent::Action(Box::new(NotificationClickedEvent)));
+                }
+                NIN_POPUPCLOSE => {
+                    debug!("WM_TRAYICON event=NIN_POPUPCLOSE");
+                    state
                         .event_tx
-                        .send(RuntimeEvent::Action(Box::new(DoubleClickEvent)));
+                        .send(RuntimeEvent::Action(Box::new(TooltipHiddenEvent)));
                 }
                 _ => {}
             }
             return LRESULT(0);
         }
-        WM_COMMAND => {
-            let action_id = (wparam.0 & 0xFFFF) as u16;
-            debug!("WM_COMMAND action_id={action_id}");
-            if let Some(action) = state.menu_actions.get(&action_id) {
-                let _ = state
-                    .event_tx
-                    .send(RuntimeEvent::Action(action.boxed_clone()));
+        WM_TRAY_SHOW_MENU => {
+            show_context_menu(hwnd, ptr, None);
+            return LRESULT(0);
+        }
+        WM_HELP => {
+            dispatch_help(state, lparam);
+            return LRESULT(1);
+        }
+        WM_TRAY_WHEEL => {
+            dispatch_scroll(hwnd, state, WheelInput::from_message(wparam, lparam));
+            return LRESULT(0);
+        }
+        WM_TIMER if wparam.0 == MENU_PUMP_TIMER_ID => {
+            drain_commands(hwnd, state);
+            state.settle_flush_waiters();
+            if state.shutdown_requested {
+                let _ = unsafe { EndMenu() };
             }
             return LRESULT(0);
         }
+        WM_TIMER if wparam.0 == ATTENTION_TIMER_ID => {
+            state.blink_off = !state.blink_off;
+            set_shown_icon(hwnd, state);
+            return LRESULT(0);
+        }
+        WM_DPICHANGED | WM_DISPLAYCHANGE => {
+            refresh_icon_dpi(hwnd, state);
+        }
+        WM_SETTINGCHANGE => {
+            refresh_icon_dpi(hwnd, state);
+            refresh_theme(state);
+        }
         _ => {
             if msg == state.taskbar_restart_msg && state.current_tray.is_some() {
                 debug!("taskbar restart detected, re-registering tray");
-                let _ = add_or_update_icon(hwnd, state, true);
+                match add_or_update_icon(hwnd, state, true) {
+                    Ok(()) => state
+                        .event_tx
+                        .send(RuntimeEvent::Action(Box::new(HostRestartedEvent))),
+                    Err(err) => {
+                        log::error!("failed to re-register tray after taskbar restart: {err}");
+                        report_error(state, err);
+                    }
+                }
                 return LRESULT(0);
             }
         }
@@ -563,104 +1201,652 @@ unsafe extern "system" fn window_proc(
     unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
 }
 
+/// F1 on a highlighted entry of the open popup menu.
+fn dispatch_help(state: &TrayWindowState, lparam: LPARAM) {
+    // Safety: `WM_HELP` always points `lParam` at a `HELPINFO`.
+    let info = unsafe { &*(lparam.0 as *const HELPINFO) };
+    if info.iContextType != HELPINFO_MENUITEM {
+        return;
+    }
+    let Some(item_id) = u32::try_from(info.iCtrlId)
+        .ok()
+        .and_then(|command| state.menu_ids.entry(command))
+        .and_then(|item| item.id.clone())
+    else {
+        return;
+    };
+    debug!("help requested for menu entry {item_id}");
+    state
+        .event_tx
+        .send(RuntimeEvent::Action(Box::new(HelpRequestedEvent {
+            item_id,
+        })));
+}
+
+/// Whether a click with `button` opens the context menu.
+fn menu_opens_on(state: &TrayWindowState, button: MouseButton) -> bool {
+    state
+        .current_tray
+        .as_ref()
+        .is_some_and(|tray| tray.menu_trigger.opens_on(button))
+}
+
 fn dispatch_click(state: &TrayWindowState, button: MouseButton) {
-    let mut pos = POINT::default();
-    let _ = unsafe { GetCursorPos(&mut pos) };
+    let position = cursor_position();
     let event = ClickEvent {
         button,
-        position: Point::new(pos.x as f32, pos.y as f32),
+        position,
+        activation_token: None,
     };
     debug!(
         "dispatch click button={:?} pos=({}, {})",
-        button, pos.x, pos.y
+        button, position.x, position.y
     );
-    let _ = state.event_tx.send(RuntimeEvent::Action(Box::new(event)));
+    state.event_tx.send(RuntimeEvent::Action(Box::new(event)));
 }
 
-fn show_context_menu(hwnd: HWND, state: &mut TrayWindowState) {
-    let Some(tray) = state.current_tray.as_ref() else {
+/// The shell sends the double-click between the two button-ups, so the click
+/// of the second press still follows it.
+fn dispatch_double_click(state: &TrayWindowState, button: MouseButton) {
+    let position = cursor_position();
+    debug!(
+        "dispatch double click button={:?} pos=({}, {})",
+        button, position.x, position.y
+    );
+    state
+        .event_tx
+        .send(RuntimeEvent::Action(Box::new(DoubleClickEvent {
+            button,
+            position,
+        })));
+}
+
+/// Reports `input` as a scroll if it happened over the icon.
+fn dispatch_scroll(hwnd: HWND, state: &TrayWindowState, input: WheelInput) {
+    if !state.registered || state.menu_open || input.delta == 0 {
         return;
+    }
+    let identifier = NOTIFYICONIDENTIFIER {
+        cbSize: std::mem::size_of::<NOTIFYICONIDENTIFIER>() as u32,
+        hWnd: hwnd,
+        uID: TRAY_ID,
+        guidItem: state.icon_guid.unwrap_or_default(),
     };
-    let Some(builder) = tray.menu_builder.as_ref() else {
+    let Ok(rect) = (unsafe { Shell_NotifyIconGetRect(&identifier) }) else {
         return;
     };
-
-    let items = builder();
-    debug!("rebuild menu lazily, items={}", items.len());
-    if items.is_empty() {
+    let POINT { x, y } = input.position;
+    if x < rect.left || x >= rect.right || y < rect.top || y >= rect.bottom {
         return;
     }
 
-    let mut next_id: u16 = 0;
-    let mut actions = HashMap::new();
-    let Some(menu) = build_menu(&items, &mut next_id, &mut actions) else {
-        return;
+    // The wheel counts away from the user as positive, scroll events count down.
+    let notches = f32::from(input.delta) / WHEEL_DELTA as f32;
+    let (lines, orientation) = if input.horizontal {
+        (notches, ScrollOrientation::Horizontal)
+    } else {
+        (-notches, ScrollOrientation::Vertical)
     };
+    debug!("dispatch scroll lines={lines}, orientation={orientation:?}");
+    state
+        .event_tx
+        .send(RuntimeEvent::Action(Box::new(ScrollEvent {
+            delta: ScrollDelta {
+                lines,
+                precise: i32::from(input.delta) % WHEEL_DELTA as i32 != 0,
+                orientation,
+            },
+        })));
+}
 
-    state.current_menu = Some(OwnedMenu(menu));
-    state.menu_actions = actions;
-    debug!("popup menu ready, actions={}", state.menu_actions.len());
+fn cursor_position() -> Point<f32> {
+    let mut pos = POINT::default();
+    let _ = unsafe { GetCursorPos(&mut pos) };
+    Point::new(pos.x as f32, pos.y as f32)
+}
 
-    let mut cursor = POINT::default();
-    let _ = unsafe { GetCursorPos(&mut cursor) };
-    unsafe {
+fn report_error(state: &TrayWindowState, err: Error) {
+    state.event_tx.send(RuntimeEvent::Error(err));
+}
+
+/// Opens the popup menu and reports the selected entry.
+///
+/// The menu opens at the cursor, or at `keyboard_anchor` with the first
+/// entry highlighted when it was requested from the keyboard.
+/// `TrackPopupMenu` runs a modal loop that calls `window_proc` again, which
+/// borrows the state from `state` for every message and keeps draining
+/// backend commands on a timer. No borrow may live across the loop, so the
+/// state is only borrowed before and after it.
+fn show_context_menu(hwnd: HWND, state: *mut TrayWindowState, keyboard_anchor: Option<POINT>) {
+    // Safety: `state` is the window's state, which outlives its messages.
+    let Some(menu) = build_context_menu(unsafe { &mut *state }) else {
+        return;
+    };
+
+    let cursor = keyboard_anchor.unwrap_or_else(|| {
+        let mut cursor = POINT::default();
+        let _ = unsafe { GetCursorPos(&mut cursor) };
+        cursor
+    });
+    let selected = unsafe {
+        SetTimer(
+            Some(hwnd),
+            MENU_PUMP_TIMER_ID,
+            COMMAND_POLL_INTERVAL.as_millis() as u32,
+            None,
+        );
         let _ = SetForegroundWindow(hwnd);
-        let _ = TrackPopupMenu(
-            menu,
-            TPM_BOTTOMALIGN | TPM_LEFTALIGN,
+        if keyboard_anchor.is_some() {
+            // The menu loop picks this up and highlights the first enabled item,
+            // as native keyboard-opened menus do.
+            let _ = PostMessageW(
+                Some(hwnd),
+                WM_KEYDOWN,
+                WPARAM(VK_DOWN.0 as usize),
+                LPARAM(0),
+            );
+        }
+        // No TPM_NONOTIFY: F1 on an entry is reported with `WM_HELP`, a menu
+        // notification. The selection is still returned rather than posted.
+        let selected = TrackPopupMenu(
+            menu.0,
+            TPM_BOTTOMALIGN | TPM_LEFTALIGN | TPM_RETURNCMD,
             cursor.x,
             cursor.y,
             Some(0),
             hwnd,
             None,
         );
+        let _ = KillTimer(Some(hwnd), MENU_PUMP_TIMER_ID);
         let _ = PostMessageW(Some(hwnd), WM_NULL, WPARAM(0), LPARAM(0));
+        selected.0
+    };
+
+    // Safety: as above; the modal loop has returned.
+    let state = unsafe { &mut *state };
+    state.menu_open = false;
+
+    debug!("popup menu closed, selected={selected}");
+    if let Some(item) = u32::try_from(selected)
+        .ok()
+        .and_then(|command| state.menu_ids.entry(command))
+    {
+        state
+            .event_tx
+            .send(RuntimeEvent::MenuSelection(item.selection_actions()));
+    }
+}
+
+/// Builds the popup menu of the current tray and marks it open. Returns
+/// `None` when there is nothing to show.
+fn build_context_menu(state: &mut TrayWindowState) -> Option<OwnedMenu> {
+    if state.menu_open {
+        return None;
+    }
+    let tray = state.current_tray.as_ref()?;
+
+    // The message window never receives keyboard input, so its thread key
+    // state is stale; ask for the physical key state instead.
+    let shift_held = unsafe { GetAsyncKeyState(VK_SHIFT.0 as i32) } < 0;
+    let items = match tray.build_alt_menu() {
+        Some(items) if shift_held => items,
+        _ => tray.build_menu(),
+    };
+    debug!("rebuild menu lazily, items={}", items.len());
+    if items.is_empty() {
+        return None;
     }
+
+    state.menu_ids.begin_build();
+    let Some(menu) = build_menu(&items, &mut Vec::new(), &mut state.menu_ids) else {
+        log::error!("failed to create the popup menu");
+        report_error(state, Error::MenuBuildFailed);
+        return None;
+    };
+    state.menu_open = true;
+    state
+        .event_tx
+        .send(RuntimeEvent::Action(Box::new(MenuOpenedEvent)));
+    Some(OwnedMenu(menu))
 }
 
-fn build_menu(
-    items: &[MenuItem],
-    next_id: &mut u16,
-    actions: &mut HashMap<u16, Box<dyn Action>>,
-) -> Option<HMENU> {
+/// Builds the popup menu for `items`, found at `path` below the top-level
+/// menu, taking command ids from `ids`.
+fn build_menu(items: &[TrayMenuItem], path: &mut Vec<usize>, ids: &mut MenuIds) -> Option<HMENU> {
     let menu = unsafe { CreatePopupMenu().ok()? };
 
-    for item in items {
-        match item {
-            MenuItem::Separator => unsafe {
+    for (index, item) in items.iter().enumerate() {
+        // The popup is rebuilt every time it opens, so hidden items can
+        // simply be left out.
+        if !item.visible {
+            continue;
+        }
+        path.push(index);
+
+        match &item.kind {
+            MenuItemKind::Separator => unsafe {
                 let _ = AppendMenuW(menu, MF_SEPARATOR, 0, PCWSTR::null());
             },
-            MenuItem::Action { name, action, .. } => {
-                *next_id = next_id.saturating_add(1);
-                let id = *next_id;
-                let wide = encode_wide(name.as_ref());
+            MenuItemKind::Action(_)
+            | MenuItemKind::Checkbox { .. }
+            | MenuItemKind::Radio { .. } => {
+                let Some(id) = ids.assign(item, path) else {
+                    path.pop();
+                    continue;
+                };
+                let mut flags = MF_STRING;
+                if !item.enabled {
+                    flags |= MF_GRAYED;
+                }
+                if item.kind.checked() == Some(true) {
+                    flags |= MF_CHECKED;
+                }
+                // Text after a tab is drawn right-aligned, like a shortcut.
+                let label = match &item.accelerator {
+                    Some(accelerator) => format!("{}\t{accelerator}", item.display_label()),
+                    None => item.display_label().to_string(),
+                };
+                let wide = encode_wide(&label);
                 let result =
-                    unsafe { AppendMenuW(menu, MF_STRING, id as usize, PCWSTR(wide.as_ptr())) };
-                if result.is_ok() {
-                    actions.insert(id, action.boxed_clone());
+                    unsafe { AppendMenuW(menu, flags, id as usize, PCWSTR(wide.as_ptr())) };
+                if result.is_ok() && matches!(item.kind, MenuItemKind::Radio { .. }) {
+                    use_radio_check(menu, id);
                 }
             }
-            MenuItem::Submenu(submenu) => {
-                if let Some(sub) = build_menu(&submenu.items, next_id, actions) {
-                    let wide = encode_wide(submenu.name.as_ref());
-                    let _ = unsafe {
-                        AppendMenuW(menu, MF_POPUP, sub.0 as usize, PCWSTR(wide.as_ptr()))
+            MenuItemKind::Submenu(children) => {
+                if let Some(sub) = build_menu(children, path, ids) {
+                    let flags = if item.enabled {
+                        MF_POPUP
+                    } else {
+                        MF_POPUP | MF_GRAYED
                     };
+                    let wide = encode_wide(item.display_label().as_ref());
+                    let result =
+                        unsafe { AppendMenuW(menu, flags, sub.0 as usize, PCWSTR(wide.as_ptr())) };
+                    // Only an attached submenu is destroyed along with its parent.
+                    if result.is_err() {
+                        unsafe {
+                            let _ = DestroyMenu(sub);
+                        }
+                    }
                 }
             }
-            _ => {}
         }
+        path.pop();
     }
 
     Some(menu)
 }
 
+/// Draws the check mark of item `id` as a radio bullet.
+fn use_radio_check(menu: HMENU, id: u16) {
+    let info = MENUITEMINFOW {
+        cbSize: std::mem::size_of::<MENUITEMINFOW>() as u32,
+        fMask: MIIM_FTYPE,
+        fType: MFT_STRING | MFT_RADIOCHECK,
+        ..Default::default()
+    };
+    unsafe {
+        let _ = SetMenuItemInfoW(menu, id as u32, false, &info);
+    }
+}
+
 fn encode_wide<S: AsRef<OsStr>>(s: S) -> Vec<u16> {
     s.as_ref().encode_wide().chain(std::iter::once(0)).collect()
 }
 
+/// Copies `text` into a fixed-size, NUL-terminated buffer, cutting it with
+/// an ellipsis to fit.
+fn wide_buffer<const N: usize>(text: &str) -> [u16; N] {
+    let mut buffer = [0u16; N];
+    for (slot, unit) in buffer
+        .iter_mut()
+        .zip(truncate_utf16(text, N - 1).encode_utf16())
+    {
+        *slot = unit;
+    }
+    buffer
+}
+
+/// Text of the standard tooltip. Plain tooltips have no heading, so the
+/// body goes on the next line.
+fn tip_text(tray: &Tray) -> Cow<'_, str> {
+    let title = tray.tooltip.as_ref().map(SharedString::as_str);
+    let body = tray.tooltip_body.as_ref().map(SharedString::as_str);
+    match (title, body) {
+        (Some(title), Some(body)) => Cow::Owned(format!("{title}\n{body}")),
+        (title, body) => Cow::Borrowed(title.or(body).unwrap_or_default()),
+    }
+}
+
 fn image_key(image: &gpui::Image) -> u64 {
     let mut hasher = DefaultHasher::new();
     image.bytes.hash(&mut hasher);
     hasher.finish()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BackendCommand, OwnedMenu, TrayWindow, TrayWindowState, WM_TRAYICON, build_menu,
+        encode_wide, open_window,
+    };
+    use gpui::{Action, MouseButton};
+    use gpui_tray_core::{
+        ClickEvent, DoubleClickEvent, EventQueuePolicy, EventReceiver, HelpRequestedEvent,
+        MenuOpenedEvent, MenuSelectEvent, NotificationClickedEvent, RawPlatformEvent, RuntimeEvent,
+        TooltipHiddenEvent, TooltipShownEvent, Tray, TrayMenuItem, event_queue,
+    };
+    use std::sync::atomic::{AtomicIsize, AtomicU32, Ordering};
+    use std::sync::{Arc, Mutex, mpsc};
+    use std::thread;
+    use std::time::Duration;
+    use windows::Win32::Foundation::{GENERIC_ALL, HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::StationsAndDesktops::{
+        CloseDesktop, CreateDesktopW, DESKTOP_CONTROL_FLAGS, GetThreadDesktop, HDESK,
+        SetThreadDesktop,
+    };
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::Input::KeyboardAndMouse::VK_RETURN;
+    use windows::Win32::UI::Shell::{
+        HELPINFO, HELPINFO_MENUITEM, HELPINFO_WINDOW, NIN_BALLOONUSERCLICK, NIN_POPUPCLOSE,
+        NIN_POPUPOPEN,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        MF_HILITE, PostMessageW, SendMessageW, WM_CONTEXTMENU, WM_HELP, WM_KEYDOWN,
+        WM_LBUTTONDBLCLK, WM_LBUTTONUP, WM_MBUTTONDBLCLK, WM_MBUTTONUP, WM_MENUSELECT,
+        WM_MOUSEMOVE, WM_RBUTTONUP,
+    };
+    use windows::core::PCWSTR;
+
+    gpui::actions!(tray_test, [Open, About]);
+
+    /// A desktop of its own for the test thread, so the windows and menus
+    /// the tests create never show up on the user's desktop.
+    struct HiddenDesktop {
+        desktop: HDESK,
+        previous: HDESK,
+    }
+
+    impl HiddenDesktop {
+        fn enter() -> Self {
+            static NEXT: AtomicU32 = AtomicU32::new(0);
+            let name = encode_wide(format!(
+                "gpui-tray-test-{}-{}",
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::Relaxed)
+            ));
+            unsafe {
+                let previous = GetThreadDesktop(GetCurrentThreadId()).expect("GetThreadDesktop");
+                let desktop = CreateDesktopW(
+                    PCWSTR(name.as_ptr()),
+                    PCWSTR::null(),
+                    None,
+                    DESKTOP_CONTROL_FLAGS(0),
+                    GENERIC_ALL.0,
+                    None,
+                )
+                .expect("CreateDesktopW");
+                // Only succeeds while the thread has no windows yet, which
+                // holds for the fresh thread every test runs on.
+                SetThreadDesktop(desktop).expect("SetThreadDesktop");
+                Self { desktop, previous }
+            }
+        }
+    }
+
+    impl Drop for HiddenDesktop {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = SetThreadDesktop(self.previous);
+                let _ = CloseDesktop(self.desktop);
+            }
+        }
+    }
+
+    /// A tray window on a hidden desktop, driven with synthetic messages.
+    struct Harness {
+        window: TrayWindow,
+        events: EventReceiver,
+        // Dropped after the window, which has to be gone before the thread
+        // can leave the desktop.
+        _desktop: HiddenDesktop,
+    }
+
+    impl Harness {
+        fn new(tray: Tray) -> Self {
+            let desktop = HiddenDesktop::enter();
+            let (event_tx, events) = event_queue(EventQueuePolicy::default());
+            let (command_tx, command_rx) = mpsc::channel();
+            let mut state = Box::new(TrayWindowState::new(event_tx, command_tx, command_rx));
+            // Set directly rather than with a command, so no icon is added to
+            // the notification area.
+            state.current_tray = Some(tray);
+            let window = open_window(state).expect("tray window");
+            Self {
+                window,
+                events,
+                _desktop: desktop,
+            }
+        }
+
+        fn hwnd(&self) -> HWND {
+            self.window.hwnd
+        }
+
+        fn state(&mut self) -> &mut TrayWindowState {
+            &mut self.window.state
+        }
+
+        fn send(&self, msg: u32, wparam: usize, lparam: isize) -> LRESULT {
+            unsafe { SendMessageW(self.hwnd(), msg, Some(WPARAM(wparam)), Some(LPARAM(lparam))) }
+        }
+
+        /// Sends the icon's callback message the shell sends for `event`.
+        fn tray_message(&self, event: u32) -> LRESULT {
+            self.send(WM_TRAYICON, 0, event as isize)
+        }
+
+        /// Describes the queued events in order, draining the queue.
+        fn events(&self) -> Vec<String> {
+            std::iter::from_fn(|| self.events.try_recv().unwrap())
+                .map(|event| match event {
+                    RuntimeEvent::Action(action) => describe(action.as_ref()),
+                    RuntimeEvent::MenuSelection(actions) => {
+                        let actions: Vec<_> = actions
+                            .iter()
+                            .map(|action| describe(action.as_ref()))
+                            .collect();
+                        format!("selection [{}]", actions.join(", "))
+                    }
+                    RuntimeEvent::Error(err) => format!("error {err}"),
+                })
+                .collect()
+        }
+    }
+
+    fn describe(action: &dyn Action) -> String {
+        let any = action.as_any();
+        if let Some(click) = any.downcast_ref::<ClickEvent>() {
+            format!("click {:?}", click.button)
+        } else if let Some(click) = any.downcast_ref::<DoubleClickEvent>() {
+            format!("double click {:?}", click.button)
+        } else if let Some(help) = any.downcast_ref::<HelpRequestedEvent>() {
+            format!("help {}", help.item_id)
+        } else if let Some(select) = any.downcast_ref::<MenuSelectEvent>() {
+            format!("select {}", select.id)
+        } else if any.is::<MenuOpenedEvent>() {
+            "menu opened".to_string()
+        } else if any.is::<TooltipShownEvent>() {
+            "tooltip shown".to_string()
+        } else if any.is::<TooltipHiddenEvent>() {
+            "tooltip hidden".to_string()
+        } else if any.is::<NotificationClickedEvent>() {
+            "notification clicked".to_string()
+        } else {
+            action.name().to_string()
+        }
+    }
+
+    fn menu_tray() -> Tray {
+        Tray::new().menu(|| {
+            vec![
+                TrayMenuItem::action("Open", Open).id("open"),
+                TrayMenuItem::separator(),
+                TrayMenuItem::action("About", About).id("about"),
+            ]
+        })
+    }
+
+    #[test]
+    fn clicks_and_double_clicks_are_reported() {
+        let harness = Harness::new(Tray::new());
+        // The order the shell reports a left double click in.
+        harness.tray_message(WM_LBUTTONUP);
+        harness.tray_message(WM_LBUTTONDBLCLK);
+        harness.tray_message(WM_LBUTTONUP);
+        harness.tray_message(WM_MBUTTONUP);
+        harness.tray_message(WM_MBUTTONDBLCLK);
+        harness.tray_message(WM_RBUTTONUP);
+        assert_eq!(
+            harness.events(),
+            [
+                "click Left",
+                "double click Left",
+                "click Left",
+                "click Middle",
+                "double click Middle",
+                "click Right",
+            ]
+        );
+    }
+
+    #[test]
+    fn tooltip_and_notification_messages_are_reported() {
+        let harness = Harness::new(Tray::new());
+        harness.tray_message(NIN_POPUPOPEN);
+        harness.tray_message(NIN_POPUPCLOSE);
+        harness.tray_message(NIN_BALLOONUSERCLICK);
+        harness.tray_message(WM_MOUSEMOVE);
+        assert_eq!(
+            harness.events(),
+            ["tooltip shown", "tooltip hidden", "notification clicked"]
+        );
+    }
+
+    #[test]
+    fn right_click_defers_the_menu_to_the_context_menu_message() {
+        let mut harness = Harness::new(menu_tray().menu_on(MouseButton::Left));
+        harness.state().version_4 = true;
+
+        harness.tray_message(WM_RBUTTONUP);
+        assert!(harness.state().mouse_context_menu);
+        // The menu opens on the left button, so the mouse's context menu
+        // request is dropped.
+        harness.tray_message(WM_CONTEXTMENU);
+        assert!(!harness.state().mouse_context_menu);
+        assert!(!harness.state().menu_open);
+        assert_eq!(harness.events(), ["click Right"]);
+    }
+
+    #[test]
+    fn help_on_a_menu_entry_reports_its_id() {
+        let mut harness = Harness::new(menu_tray());
+        let items = menu_tray().build_menu();
+        let state = harness.state();
+        state.menu_ids.begin_build();
+        let _menu = OwnedMenu(build_menu(&items, &mut Vec::new(), &mut state.menu_ids).unwrap());
+        let about = (1..=u32::from(u16::MAX))
+            .find(|&command| {
+                state
+                    .menu_ids
+                    .entry(command)
+                    .is_some_and(|item| item.id == Some("about".into()))
+            })
+            .expect("a command id for the about entry");
+
+        let mut info = HELPINFO {
+            cbSize: std::mem::size_of::<HELPINFO>() as u32,
+            iContextType: HELPINFO_MENUITEM,
+            iCtrlId: about as i32,
+            ..Default::default()
+        };
+        let help = |info: &HELPINFO| harness.send(WM_HELP, 0, info as *const HELPINFO as isize);
+        assert_eq!(help(&info), LRESULT(1));
+        // Help for a window rather than a menu entry is not the tray's.
+        info.iContextType = HELPINFO_WINDOW;
+        help(&info);
+        assert_eq!(harness.events(), ["help about"]);
+    }
+
+    #[test]
+    fn raw_handler_sees_messages_before_translation() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let tray = Tray::new().on_raw_platform_event({
+            let seen = seen.clone();
+            move |event| {
+                if let RawPlatformEvent::WindowMessage {
+                    msg: WM_TRAYICON,
+                    lparam,
+                    ..
+                } = *event
+                {
+                    seen.lock().unwrap().push(lparam as u32);
+                }
+            }
+        });
+        let harness = Harness::new(tray);
+        harness.tray_message(WM_LBUTTONUP);
+        harness.tray_message(WM_MOUSEMOVE);
+        assert_eq!(*seen.lock().unwrap(), [WM_LBUTTONUP, WM_MOUSEMOVE]);
+        assert_eq!(harness.events(), ["click Left"]);
+    }
+
+    #[test]
+    fn selecting_a_menu_entry_reports_its_actions() {
+        // Presses Enter on the first entry the menu highlights, which the
+        // menu does for a context menu request from the keyboard.
+        let hwnd = Arc::new(AtomicIsize::new(0));
+        let tray = menu_tray().on_raw_platform_event({
+            let hwnd = hwnd.clone();
+            move |event| {
+                let RawPlatformEvent::WindowMessage {
+                    msg: WM_MENUSELECT,
+                    wparam,
+                    ..
+                } = *event
+                else {
+                    return;
+                };
+                let flags = (wparam >> 16) as u32 & 0xFFFF;
+                if flags != 0xFFFF && flags & MF_HILITE.0 != 0 {
+                    let hwnd = HWND(hwnd.load(Ordering::Relaxed) as *mut _);
+                    let _ = unsafe {
+                        PostMessageW(
+                            Some(hwnd),
+                            WM_KEYDOWN,
+                            WPARAM(VK_RETURN.0 as usize),
+                            LPARAM(0),
+                        )
+                    };
+                }
+            }
+        });
+        let mut harness = Harness::new(tray);
+        hwnd.store(harness.hwnd().0 as isize, Ordering::Relaxed);
+
+        // Closes the menu through the command timer if it never gets the key.
+        let command_tx = harness.state().command_tx.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(10));
+            let _ = command_tx.send(BackendCommand::Shutdown);
+        });
+
+        harness.tray_message(WM_CONTEXTMENU);
+        assert!(!harness.state().menu_open);
+        assert_eq!(
+            harness.events(),
+            ["menu opened", "selection [select open, tray_test::Open]"]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Mouse wheel over the tray icon.
+//!
+//! The shell does not forward wheel input to notification icons, so a
+//! low-level mouse hook watches for it instead. The hook runs on its own
+//! thread with a blocking message loop: low-level hooks stall mouse input for
+//! the whole desktop until they return, and the backend thread only pumps
+//! messages every few milliseconds. The hook posts every wheel event to the
+//! tray windows that asked for scrolling, which check it against their
+//! icon's rectangle. Because it sees all wheel input on the desktop, it is
+//! only installed while a tray opts in with `windows_scroll_events`.
+
+use log::debug;
+use std::sync::{Mutex, MutexGuard, TryLockError, mpsc};
+use std::thread::{self, JoinHandle};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, WPARAM};
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CallNextHookEx, GetMessageW, HC_ACTION, MSG, MSLLHOOKSTRUCT, PostMessageW, PostThreadMessageW,
+    SetWindowsHookExW, UnhookWindowsHookEx, WH_MOUSE_LL, WM_APP, WM_MOUSEHWHEEL, WM_MOUSEWHEEL,
+    WM_QUIT,
+};
+
+/// Posted to the tray window for each wheel event; decode with [`WheelInput::from_message`].
+pub(crate) const WM_TRAY_WHEEL: u32 = WM_APP + 72;
+
+/// Windows that receive [`WM_TRAY_WHEEL`], and the hook thread serving
+/// them. Hook procedures get no context pointer, so every backend of the
+/// process shares one hook.
+struct Hooks {
+    targets: Vec<isize>,
+    thread: Option<HookThread>,
+}
+
+static HOOKS: Mutex<Hooks> = Mutex::new(Hooks {
+    targets: Vec::new(),
+    thread: None,
+});
+
+fn hooks() -> MutexGuard<'static, Hooks> {
+    HOOKS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A wheel event anywhere on the desktop.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct WheelInput {
+    /// Rotation in `WHEEL_DELTA` units (120 per notch), positive away from
+    /// the user or to the right.
+    pub delta: i16,
+    pub horizontal: bool,
+    pub position: POINT,
+}
+
+impl WheelInput {
+    pub fn from_message(wparam: WPARAM, lparam: LPARAM) -> Self {
+        Self {
+            delta: (wparam.0 & 0xFFFF) as u16 as i16,
+            horizontal: wparam.0 & 0x1_0000 != 0,
+            position: POINT {
+                x: (lparam.0 & 0xFFFF) as u16 as i16 as i32,
+                y: ((lparam.0 >> 16) & 0xFFFF) as u16 as i16 as i32,
+            },
+        }
+    }
+
+    fn to_message(self) -> (WPARAM, LPARAM) {
+        let horizontal = if self.horizontal { 0x1_0000 } else { 0 };
+        let x = self.position.x as i16 as u16 as isize;
+        let y = self.position.y as i16 as u16 as isize;
+        (
+            WPARAM(self.delta as u16 as usize | horizontal),
+            LPARAM(x | (y << 16)),
+        )
+    }
+}
+
+/// One window's share of the low-level mouse hook, which is removed once
+/// the last share is dropped.
+pub(crate) struct WheelHook {
+    target: isize,
+}
+
+impl WheelHook {
+    /// Starts forwarding wheel events to `hwnd`, or returns `None` if the hook
+    /// could not be installed.
+    pub fn install(hwnd: HWND) -> Option<Self> {
+        let target = hwnd.0 as isize;
+        let mut hooks = hooks();
+        if hooks.thread.is_none() {
+            hooks.thread = Some(HookThread::start()?);
+        }
+        hooks.targets.push(target);
+        Some(Self { target })
+    }
+}
+
+impl Drop for WheelHook {
+    fn drop(&mut self) {
+        let thread = {
+            let mut hooks = hooks();
+            hooks.targets.retain(|target| *target != self.target);
+            if hooks.targets.is_empty() {
+                hooks.thread.take()
+            } else {
+                None
+            }
+        };
+        if let Some(thread) = thread {
+            thread.stop();
+        }
+    }
+}
+
+/// Thread running the hook's message loop.
+struct HookThread {
+    thread_id: u32,
+    thread: JoinHandle<()>,
+}
+
+impl HookThread {
+    fn start() -> Option<Self> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name("gpui-tray-windows-wheel".to_string())
+            .spawn(move || {
+                let hook = match unsafe { SetWindowsHookExW(WH_MOUSE_LL, Some(hook_proc), None, 0) }
+                {
+                    Ok(hook) => hook,
+                    Err(err) => {
+                        debug!("SetWindowsHookExW failed, tray scrolling unavailable: {err:?}");
+                        let _ = ready_tx.send(None);
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Some(unsafe { GetCurrentThreadId() }));
+
+                let mut msg = MSG::default();
+                while unsafe { GetMessageW(&mut msg, None, 0, 0) }.as_bool() {}
+
+                let _ = unsafe { UnhookWindowsHookEx(hook) };
+            })
+            .ok()?;
+
+        // The hook procedure only runs once the thread pumps messages, after
+        // this, so waiting here with `HOOKS` locked cannot deadlock.
+        let thread_id = ready_rx.recv().ok().flatten()?;
+        Some(Self { thread_id, thread })
+    }
+
+    fn stop(self) {
+        let _ = unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0)) };
+        let _ = self.thread.join();
+    }
+}
+
+unsafe extern "system" fn hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let message = wparam.0 as u32;
+    if code == HC_ACTION as i32 && (message == WM_MOUSEWHEEL || message == WM_MOUSEHWHEEL) {
+        let info = unsafe { &*(lparam.0 as *const MSLLHOOKSTRUCT) };
+        let input = WheelInput {
+            delta: (info.mouseData >> 16) as u16 as i16,
+            horizontal: message == WM_MOUSEHWHEEL,
+            position: info.pt,
+        };
+        post_to_targets(input);
+    }
+    unsafe { CallNextHookEx(None, code, wparam, lparam) }
+}
+
+fn post_to_targets(input: WheelInput) {
+    // Never stall the desktop's mouse input on a backend starting or
+    // stopping; the wheel event is simply not reported then.
+    let hooks = match HOOKS.try_lock() {
+        Ok(hooks) => hooks,
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        Err(TryLockError::WouldBlock) => return,
+    };
+    let (wparam, lparam) = input.to_message();
+    for target in &hooks.targets {
+        let hwnd = HWND(*target as *mut _);
+        let _ = unsafe { PostMessageW(Some(hwnd), WM_TRAY_WHEEL, wparam, lparam) };
+    }
+}